@@ -7,7 +7,7 @@ use ckb_core::service::{Request, DEFAULT_CHANNEL_SIZE, SIGNAL_CHANNEL_SIZE};
 use ckb_core::transaction::ProposalShortId;
 use ckb_core::{BlockNumber, Cycle};
 use ckb_logger::{self, debug, error, info, log_enabled, trace, warn};
-use ckb_notify::NotifyController;
+use ckb_notify::{ForkBlocks, NotifyController};
 use ckb_shared::cell_set::CellSetDiff;
 use ckb_shared::chain_state::ChainState;
 use ckb_shared::error::SharedError;
@@ -342,6 +342,11 @@ impl ChainService {
                 self.notify
                     .notify_new_uncle(Arc::new(detached_block.into()));
             }
+            self.notify.notify_new_tip(Arc::clone(&block));
+            self.notify.notify_switch_fork(Arc::new(ForkBlocks::new(
+                fork.detached_blocks().iter().cloned().collect(),
+                fork.attached_blocks().iter().cloned().collect(),
+            )));
             if log_enabled!(ckb_logger::Level::Debug) {
                 self.print_chain(&chain_state, 10);
             }
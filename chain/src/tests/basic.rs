@@ -426,6 +426,43 @@ fn test_chain_fork_by_total_difficulty() {
     );
 }
 
+#[test]
+fn test_chain_fork_notify_switch_fork() {
+    let (chain_controller, shared, parent) = start_chain(None);
+    let switch_fork_receiver = shared.notify().subscribe_switch_fork("test");
+    let final_number = 20;
+
+    let mock_store = MockStore::new(&parent, shared.store());
+    let mut chain1 = MockChain::new(parent.clone(), shared.consensus());
+    let mut chain2 = MockChain::new(parent.clone(), shared.consensus());
+    for _ in 0..final_number {
+        chain1.gen_empty_block_with_difficulty(100u64, &mock_store);
+        chain2.gen_empty_block_with_difficulty(101u64, &mock_store);
+    }
+
+    for block in chain1.blocks() {
+        chain_controller
+            .process_block(Arc::new(block.clone()), false)
+            .expect("process block ok");
+    }
+    // chain2 becomes the main chain only when its last block is processed
+    for block in chain2.blocks() {
+        chain_controller
+            .process_block(Arc::new(block.clone()), false)
+            .expect("process block ok");
+    }
+
+    for block in chain1.blocks() {
+        let fork = switch_fork_receiver.recv().expect("switch fork");
+        assert!(fork.detached().is_empty());
+        assert_eq!(fork.attached(), &[block.clone()][..]);
+    }
+    let fork = switch_fork_receiver.recv().expect("switch fork");
+    assert_eq!(fork.detached(), &chain1.blocks()[..]);
+    assert_eq!(fork.attached(), &chain2.blocks()[..]);
+    assert!(switch_fork_receiver.try_recv().is_err());
+}
+
 #[test]
 fn test_chain_fork_by_hash() {
     let (chain_controller, shared, parent) = start_chain(None);
@@ -10,7 +10,6 @@ use ckb_core::transaction::{
 use ckb_core::{capacity_bytes, Bytes, Capacity};
 use ckb_dao::DaoCalculator;
 use ckb_dao_utils::genesis_dao_data;
-use ckb_notify::NotifyService;
 use ckb_shared::shared::Shared;
 use ckb_shared::shared::SharedBuilder;
 use ckb_store::ChainStore;
@@ -55,8 +54,7 @@ pub(crate) fn start_chain(consensus: Option<Consensus>) -> (ChainController, Sha
     });
     let shared = builder.consensus(consensus).build().unwrap();
 
-    let notify = NotifyService::default().start::<&str>(None);
-    let chain_service = ChainService::new(shared.clone(), notify);
+    let chain_service = ChainService::new(shared.clone(), shared.notify().clone());
     let chain_controller = chain_service.start::<&str>(None);
     let parent = shared
         .store()
@@ -23,6 +23,13 @@ pub fn run_app(version: Version) -> Result<(), ExitCode> {
                 (cli::CMD_HASHES, Some(sub_matches)) => {
                     subcommand::cli::hashes(Setup::root_dir_from_matches(&matches)?, sub_matches)
                 }
+                (cli::CMD_VERIFY_TX, Some(sub_matches)) => subcommand::cli::verify_tx(
+                    Setup::from_matches(&matches)?.verify_tx(sub_matches)?,
+                ),
+                (cli::CMD_RPC_DISCOVER, Some(sub_matches)) => subcommand::cli::rpc_discover(
+                    Setup::root_dir_from_matches(&matches)?,
+                    sub_matches,
+                ),
                 _ => unreachable!(),
             };
         }
@@ -41,6 +48,7 @@ pub fn run_app(version: Version) -> Result<(), ExitCode> {
         (cli::CMD_EXPORT, Some(matches)) => subcommand::export(setup.export(&matches)?),
         (cli::CMD_IMPORT, Some(matches)) => subcommand::import(setup.import(&matches)?),
         (cli::CMD_STATS, Some(matches)) => subcommand::stats(setup.stats(&matches)?),
+        (cli::CMD_INDEXER, Some(matches)) => subcommand::indexer(setup.indexer(&matches)?),
         _ => unreachable!(),
     }
 }
@@ -1,10 +1,14 @@
 mod blake;
 mod hashes;
+mod rpc_discover;
 mod secp256k1_lock;
+mod verify_tx;
 
 pub use blake::{blake160, blake256};
 pub use hashes::hashes;
+pub use rpc_discover::rpc_discover;
 pub use secp256k1_lock::secp256k1_lock;
+pub use verify_tx::verify_tx;
 
 use ckb_app_config::ExitCode;
 use faster_hex::hex_decode;
@@ -0,0 +1,33 @@
+use ckb_app_config::{cli, CKBAppConfig, ExitCode};
+use ckb_resource::Resource;
+use ckb_rpc::{openrpc_document, Module};
+use clap::ArgMatches;
+use std::path::PathBuf;
+
+pub fn rpc_discover<'m>(root_dir: PathBuf, matches: &ArgMatches<'m>) -> Result<(), ExitCode> {
+    let document = if matches.is_present(cli::ARG_ALL) {
+        openrpc_document(|_, _| true)
+    } else {
+        let mut resource = Resource::ckb_config(&root_dir);
+        if !resource.exists() {
+            resource = Resource::bundled_ckb_config();
+        }
+
+        let config: CKBAppConfig = toml::from_slice(&resource.get()?)?;
+        let has_block_assembler = config.block_assembler.is_some();
+        let rpc = config.rpc;
+        // The miner methods are only served when the block assembler is configured
+        openrpc_document(|module, method| {
+            let module_enable = match module {
+                Some(Module::Miner) => has_block_assembler && rpc.miner_enable(),
+                Some(module) => rpc.module_enable(module),
+                None => true,
+            };
+            module_enable && rpc.method_enable(method)
+        })
+    };
+
+    println!("{}", serde_json::to_string_pretty(&document).unwrap());
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use ckb_app_config::{ExitCode, VerifyTxArgs};
+use ckb_core::cell::resolve_transaction;
+use ckb_core::transaction::Transaction;
+use ckb_jsonrpc_types::Transaction as JsonTransaction;
+use ckb_script::TransactionScriptsVerifier;
+use ckb_shared::shared::SharedBuilder;
+use ckb_store::data_loader_wrapper::DataLoaderWrapper;
+use std::fs::{self, File};
+use std::io::BufWriter;
+
+pub fn verify_tx(args: VerifyTxArgs) -> Result<(), ExitCode> {
+    let tx: JsonTransaction = serde_json::from_slice(&fs::read(&args.tx_file)?).map_err(|err| {
+        eprintln!("Malformed transaction json: {}", err);
+        ExitCode::Cli
+    })?;
+    let tx: Transaction = tx.into();
+
+    let shared = SharedBuilder::with_db_config(&args.config.db)
+        .consensus(args.consensus)
+        .script_config(args.config.script)
+        .build()
+        .map_err(|err| {
+            eprintln!("Verify tx error: {:?}", err);
+            ExitCode::Failure
+        })?;
+    let chain_state = shared.lock_chain_state();
+    let resolved = resolve_transaction(&tx, &mut Default::default(), &*chain_state, &*chain_state)
+        .map_err(|err| {
+            eprintln!("Failed to resolve transaction {:#x}: {:?}", tx.hash(), err);
+            ExitCode::Failure
+        })?;
+
+    let data_loader = DataLoaderWrapper::new(chain_state.store());
+    let mut verifier =
+        TransactionScriptsVerifier::new(&resolved, &data_loader, chain_state.script_config());
+    if args.profile.is_some() {
+        verifier.enable_profile();
+    }
+    let reports = verifier
+        .verify_script_groups(chain_state.consensus().max_block_cycles)
+        .map_err(|err| {
+            eprintln!("Failed to verify transaction {:#x}: {:?}", tx.hash(), err);
+            ExitCode::Failure
+        })?;
+
+    let mut cycles = 0;
+    let mut errors = 0;
+    for report in &reports {
+        println!(
+            "{:?} script group {:#x}: inputs {:?} outputs {:?} cycles {}",
+            report.group_type,
+            report.script_hash,
+            report.input_indices,
+            report.output_indices,
+            report.cycles
+        );
+        for message in &report.debug_messages {
+            println!("  DEBUG OUTPUT: {}", message);
+        }
+        if let Some(err) = report.error {
+            eprintln!("  error: {:?}", err);
+            errors += 1;
+        }
+        cycles += report.cycles;
+    }
+    println!("Transaction {:#x} consumed cycles {}", tx.hash(), cycles);
+
+    if let (Some(path), Some(profile)) = (args.profile, verifier.profile()) {
+        profile.write_folded(BufWriter::new(File::create(&path)?))?;
+        println!("Wrote the profile to {}", path.display());
+    }
+
+    if errors > 0 {
+        return Err(ExitCode::Failure);
+    }
+    Ok(())
+}
@@ -0,0 +1,325 @@
+use crate::subcommand::cli::parse_hex_data;
+use ckb_app_config::{ExitCode, IndexerArgs, IndexerCommand};
+use ckb_core::{BlockNumber, Capacity};
+use ckb_indexer::{DefaultIndexerStore, IndexerStore, LockHashIndexState};
+use ckb_shared::shared::{Shared, SharedBuilder};
+use ckb_store::ChainStore;
+use ckb_traits::chain_provider::ChainProvider;
+use faster_hex::hex_string;
+use numext_fixed_hash::H256;
+
+pub fn indexer(args: IndexerArgs) -> Result<(), ExitCode> {
+    let shared = SharedBuilder::with_db_config(&args.config.db)
+        .consensus(args.consensus)
+        .build()
+        .map_err(|err| {
+            eprintln!("Indexer error: {:?}", err);
+            ExitCode::Failure
+        })?;
+    let indexer = Indexer {
+        store: DefaultIndexerStore::new(&args.config.indexer_db, shared.clone()),
+        shared,
+    };
+
+    match args.command {
+        IndexerCommand::Verify => indexer.verify(),
+        IndexerCommand::Rebuild { lock_hash, from } => {
+            let lock_hash = match lock_hash {
+                Some(lock_hash) => Some(parse_lock_hash(&lock_hash)?),
+                None => None,
+            };
+            indexer.rebuild(lock_hash, from)
+        }
+        IndexerCommand::Compact => indexer.compact(),
+        IndexerCommand::Stats => indexer.stats(),
+    }
+}
+
+fn parse_lock_hash(lock_hash: &str) -> Result<H256, ExitCode> {
+    let data = parse_hex_data(lock_hash)?;
+    H256::from_slice(&data).map_err(|err| {
+        eprintln!("Malformed lock hash: {}, error: {:?}", lock_hash, err);
+        ExitCode::Cli
+    })
+}
+
+struct Indexer {
+    store: DefaultIndexerStore,
+    shared: Shared,
+}
+
+impl Indexer {
+    fn tip_number(&self) -> BlockNumber {
+        self.shared
+            .store()
+            .get_tip_header()
+            .expect("tip header exists")
+            .number()
+    }
+
+    // returns all index states, tagged with the kind and hash for printing
+    fn index_states(&self) -> Vec<(&'static str, H256, LockHashIndexState)> {
+        let lock_hashes = self.store.get_lock_hash_index_states().into_iter();
+        let type_hashes = self.store.get_type_hash_index_states().into_iter();
+        let lock_patterns = self.store.get_lock_pattern_index_states().into_iter();
+        lock_hashes
+            .map(|(hash, index_state)| ("lock hash", hash, index_state))
+            .chain(type_hashes.map(|(hash, index_state)| ("type hash", hash, index_state)))
+            .chain(lock_patterns.map(|(hash, index_state)| ("lock pattern", hash, index_state)))
+            .collect()
+    }
+
+    // An index state is recoverable if it is on the main chain, or it is on a fork chain whose
+    // blocks are all kept in the chain store, which are required to detach it in the sync.
+    fn check_index_state(&self, index_state: &LockHashIndexState) -> Result<(), String> {
+        let store = self.shared.store();
+        let tip_number = self.tip_number();
+        if index_state.block_number > tip_number {
+            return Err(format!(
+                "indexed block number {} is higher than the tip {}",
+                index_state.block_number, tip_number
+            ));
+        }
+        let mut block_hash = index_state.block_hash.to_owned();
+        loop {
+            let header = store.get_block_header(&block_hash).ok_or_else(|| {
+                format!(
+                    "indexed block {:#x} can not be detached, block {:#x} is missing in chain store",
+                    index_state.block_hash, block_hash
+                )
+            })?;
+            if store.get_block_number(&block_hash) == Some(header.number()) {
+                return Ok(());
+            }
+            block_hash = header.parent_hash().to_owned();
+        }
+    }
+
+    // The capacity and cells count of a lock hash are updated incrementally in the sync,
+    // compares them with the sum of the live cells.
+    fn check_capacity(&self, lock_hash: &H256) -> Result<(), String> {
+        let live_cells =
+            self.store
+                .get_live_cells(lock_hash, None, None, usize::max_value(), false);
+        let capacity = live_cells
+            .iter()
+            .try_fold(Capacity::zero(), |capacity, live_cell| {
+                capacity.safe_add(live_cell.cell_output.capacity)
+            })
+            .map_err(|err| format!("live cells capacity overflow: {:?}", err))?;
+        let lock_hash_capacity = self.store.get_capacity(lock_hash).unwrap_or_default();
+        if lock_hash_capacity.capacity != capacity
+            || lock_hash_capacity.cells_count != live_cells.len() as u64
+        {
+            return Err(format!(
+                "capacity {} of {} cells mismatches the live cells, expect capacity {} of {} cells",
+                lock_hash_capacity.capacity,
+                lock_hash_capacity.cells_count,
+                capacity,
+                live_cells.len()
+            ));
+        }
+        Ok(())
+    }
+
+    // The live cells of a lock hash indexed to the tip must be live in the chain store, with the
+    // same cell outputs.
+    fn check_live_cells(&self, lock_hash: &H256) -> Result<(), String> {
+        let store = self.shared.store();
+        let live_cells =
+            self.store
+                .get_live_cells(lock_hash, None, None, usize::max_value(), false);
+        let mut mismatched = live_cells.iter().filter(|live_cell| {
+            let created_by = &live_cell.created_by;
+            let is_live = store
+                .get_tx_meta(&created_by.tx_hash)
+                .and_then(|tx_meta| tx_meta.is_dead(created_by.index as usize))
+                == Some(false);
+            !is_live
+                || store
+                    .get_cell_meta(&created_by.tx_hash, created_by.index)
+                    .map(|cell_meta| cell_meta.cell_output)
+                    .as_ref()
+                    != Some(&live_cell.cell_output)
+        });
+        match mismatched.next() {
+            Some(live_cell) => Err(format!(
+                "{} of {} live cells mismatch the live cells in chain store, such as {:#x}:{}",
+                1 + mismatched.count(),
+                live_cells.len(),
+                live_cell.created_by.tx_hash,
+                live_cell.created_by.index
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn verify(&self) -> Result<(), ExitCode> {
+        let mut errors = 0;
+        let lock_hashes = self.store.get_lock_hash_index_states();
+        for (kind, hash, index_state) in self.index_states() {
+            if let Err(err) = self.check_index_state(&index_state) {
+                eprintln!("{} {:#x}: {}", kind, hash, err);
+                errors += 1;
+            } else if self
+                .shared
+                .store()
+                .get_block_number(&index_state.block_hash)
+                != Some(index_state.block_number)
+            {
+                println!(
+                    "{} {:#x}: indexed block {:#x} is on a fork chain, it will be detached in the next sync",
+                    kind, hash, index_state.block_hash
+                );
+            }
+        }
+        let tip_hash = self
+            .shared
+            .store()
+            .get_tip_header()
+            .expect("tip header exists")
+            .hash()
+            .to_owned();
+        for (lock_hash, index_state) in &lock_hashes {
+            if let Err(err) = self.check_capacity(lock_hash) {
+                eprintln!("lock hash {:#x}: {}", lock_hash, err);
+                errors += 1;
+            }
+            // the cells consumed after the indexed block are still live in the index
+            if index_state.block_hash != tip_hash {
+                println!(
+                    "lock hash {:#x}: live cells are not checked, indexed block {} is behind the tip",
+                    lock_hash, index_state.block_number
+                );
+            } else if let Err(err) = self.check_live_cells(lock_hash) {
+                eprintln!("lock hash {:#x}: {}", lock_hash, err);
+                errors += 1;
+            }
+        }
+
+        if errors > 0 {
+            eprintln!(
+                "Found {} inconsistencies, run `ckb indexer rebuild` to fix them",
+                errors
+            );
+            return Err(ExitCode::Failure);
+        }
+        println!("The indexer database is consistent with the chain store");
+        Ok(())
+    }
+
+    // rebuilds the given lock hash, or all the indexed lock hashes, type hashes and lock patterns
+    fn rebuild(&self, lock_hash: Option<H256>, from: BlockNumber) -> Result<(), ExitCode> {
+        match lock_hash {
+            Some(lock_hash) => {
+                // rebuilding must not start indexing a new lock hash
+                if !self
+                    .store
+                    .get_lock_hash_index_states()
+                    .contains_key(&lock_hash)
+                {
+                    eprintln!("lock hash {:#x} is not indexed", lock_hash);
+                    return Err(ExitCode::Cli);
+                }
+                self.store.remove_lock_hash(&lock_hash);
+                self.store.insert_lock_hash(&lock_hash, Some(from));
+            }
+            None => {
+                for lock_hash in self.store.get_lock_hash_index_states().keys() {
+                    self.store.remove_lock_hash(lock_hash);
+                    self.store.insert_lock_hash(lock_hash, Some(from));
+                }
+                for type_hash in self.store.get_type_hash_index_states().keys() {
+                    self.store.remove_type_hash(type_hash);
+                    self.store.insert_type_hash(type_hash, Some(from));
+                }
+                for (pattern_hash, lock_pattern) in self.store.get_lock_patterns() {
+                    self.store.remove_lock_pattern(&pattern_hash);
+                    self.store.insert_lock_pattern(&lock_pattern, Some(from));
+                }
+            }
+        }
+
+        // the sync detaches the index states on fork chain, which panics on unrecoverable ones
+        let mut errors = 0;
+        for (kind, hash, index_state) in self.index_states() {
+            if let Err(err) = self.check_index_state(&index_state) {
+                eprintln!("{} {:#x}: {}", kind, hash, err);
+                errors += 1;
+            }
+        }
+        if errors > 0 {
+            eprintln!("Rebuild all the index states by `ckb indexer rebuild` to fix them");
+            return Err(ExitCode::Failure);
+        }
+
+        let tip_number = self.tip_number();
+        loop {
+            self.store.sync_index_states();
+            let synced_number = self
+                .index_states()
+                .iter()
+                .map(|(_, _, index_state)| index_state.block_number)
+                .min()
+                .unwrap_or(tip_number);
+            println!("Synced index to block {}/{}", synced_number, tip_number);
+            if synced_number >= tip_number {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn compact(&self) -> Result<(), ExitCode> {
+        self.store.compact().map_err(|err| {
+            eprintln!("Compact indexer database error: {:?}", err);
+            ExitCode::Failure
+        })?;
+        println!("The indexer database is compacted");
+        Ok(())
+    }
+
+    fn stats(&self) -> Result<(), ExitCode> {
+        let tip_number = self.tip_number();
+        println!("tip: {}", tip_number);
+
+        let lock_hash_index_states = self.store.get_lock_hash_index_states();
+        println!("lock hashes: {}", lock_hash_index_states.len());
+        for (lock_hash, index_state) in lock_hash_index_states {
+            let lock_hash_capacity = self.store.get_capacity(&lock_hash).unwrap_or_default();
+            println!(
+                "  {:#x} block_number: {} capacity: {} cells_count: {}",
+                lock_hash,
+                index_state.block_number,
+                lock_hash_capacity.capacity,
+                lock_hash_capacity.cells_count
+            );
+        }
+
+        let type_hash_index_states = self.store.get_type_hash_index_states();
+        println!("type hashes: {}", type_hash_index_states.len());
+        for (type_hash, index_state) in type_hash_index_states {
+            println!(
+                "  {:#x} block_number: {}",
+                type_hash, index_state.block_number
+            );
+        }
+
+        let lock_patterns = self.store.get_lock_patterns();
+        let lock_pattern_index_states = self.store.get_lock_pattern_index_states();
+        println!("lock patterns: {}", lock_pattern_index_states.len());
+        for (pattern_hash, index_state) in lock_pattern_index_states {
+            if let Some(lock_pattern) = lock_patterns.get(&pattern_hash) {
+                println!(
+                    "  {:#x} block_number: {} code_hash: {:#x} hash_type: {:?} args_prefix: 0x{}",
+                    pattern_hash,
+                    index_state.block_number,
+                    lock_pattern.code_hash,
+                    lock_pattern.hash_type,
+                    hex_string(&lock_pattern.args_prefix).expect("hex string")
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use ckb_app_config::{ExitCode, MinerArgs};
-use ckb_miner::{Client, Miner, MinerConfig};
+use ckb_miner::{Client, Miner, MinerConfig, StratumClient};
 use crossbeam_channel::unbounded;
 use std::thread;
 
@@ -7,13 +7,25 @@ pub fn miner(args: MinerArgs) -> Result<(), ExitCode> {
     let (new_work_tx, new_work_rx) = unbounded();
     let MinerConfig { client, workers } = args.config;
 
-    let mut client = Client::new(new_work_tx, client);
-    let mut miner = Miner::new(args.pow_engine, client.clone(), new_work_rx, &workers);
+    let mut miner = if let Some(address) = client.stratum_address() {
+        let stratum_client =
+            StratumClient::new(new_work_tx, address.to_owned(), client.poll_interval);
+        thread::Builder::new()
+            .name("client".to_string())
+            .spawn(move || stratum_client.run())
+            .expect("Start client failed!");
 
-    thread::Builder::new()
-        .name("client".to_string())
-        .spawn(move || client.poll_block_template())
-        .expect("Start client failed!");
+        Miner::new(args.pow_engine, None, new_work_rx, &workers)
+    } else {
+        let mut client = Client::new(new_work_tx, client);
+        let miner = Miner::new(args.pow_engine, Some(client.clone()), new_work_rx, &workers);
+
+        thread::Builder::new()
+            .name("client".to_string())
+            .spawn(move || client.poll_block_template())
+            .expect("Start client failed!");
+        miner
+    };
 
     miner.run();
     Ok(())
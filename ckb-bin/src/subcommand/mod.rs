@@ -1,6 +1,7 @@
 pub mod cli;
 mod export;
 mod import;
+mod indexer;
 mod init;
 mod miner;
 mod prof;
@@ -9,6 +10,7 @@ mod stats;
 
 pub use self::export::export;
 pub use self::import::import;
+pub use self::indexer::indexer;
 pub use self::init::init;
 pub use self::miner::miner;
 pub use self::prof::profile;
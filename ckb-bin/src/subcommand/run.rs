@@ -8,23 +8,45 @@ use ckb_network::{CKBProtocol, NetworkService, NetworkState};
 use ckb_network_alert::alert_relayer::AlertRelayer;
 use ckb_notify::NotifyService;
 use ckb_resource::CODE_HASH_SECP256K1_BLAKE160_SIGHASH_ALL;
-use ckb_rpc::{RpcServer, ServiceBuilder};
+use ckb_rpc::{RpcServer, ServiceBuilder, StratumServer};
 use ckb_shared::shared::{Shared, SharedBuilder};
 use ckb_sync::{NetTimeProtocol, NetworkProtocol, Relayer, SyncSharedState, Synchronizer};
 use ckb_traits::chain_provider::ChainProvider;
+use ckb_tx_pool_executor::{TxPoolExpiryService, TxPoolPersistService};
 use ckb_verification::{BlockVerifier, Verifier};
+use numext_fixed_uint::U256;
 use std::sync::Arc;
+use std::time::Duration;
 
 const SECP256K1_BLAKE160_SIGHASH_ALL_ARG_LEN: usize = 20;
+// in seconds
+const TX_POOL_EXPIRY_INTERVAL: u64 = 10;
 
 pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     deadlock_detection();
 
+    if !args.config.rpc.transports_authenticated() {
+        eprintln!(
+            "Config error: rpc.ws_listen_address and rpc.tcp_listen_address can not be set with \
+             rpc.auth, the WebSocket and TCP transports are not authenticated"
+        );
+        return Err(ExitCode::Config);
+    }
+    if !args.config.rpc.rate_limit_per_client() {
+        eprintln!(
+            "Config error: rpc.rate_limit requires trust_forwarded_for, the HTTP clients can only \
+             be told apart by the X-Forwarded-For header set by a trusted reverse proxy"
+        );
+        return Err(ExitCode::Config);
+    }
+
+    let notify = NotifyService::default().start(Some("notify"));
     let shared = SharedBuilder::with_db_config(&args.config.db)
         .consensus(args.consensus)
         .tx_pool_config(args.config.tx_pool)
         .script_config(args.config.script)
         .store_config(args.config.store)
+        .notify(notify.clone())
         .build()
         .map_err(|err| {
             eprintln!("Run error: {:?}", err);
@@ -34,7 +56,6 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     // Verify genesis every time starting node
     verify_genesis(&shared)?;
 
-    let notify = NotifyService::default().start(Some("notify"));
     let chain_service = ChainService::new(shared.clone(), notify.clone());
     let chain_controller = chain_service.start(Some("ChainService"));
     info_target!(
@@ -43,6 +64,20 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
         shared.genesis_hash()
     );
 
+    let tx_pool_persist_controller = if args.config.tx_pool.max_persisted_age > 0 {
+        let service = TxPoolPersistService::new(
+            shared.clone(),
+            args.config.data_dir.join("tx_pool"),
+            args.config.tx_pool.max_persisted_age,
+        );
+        Some(service.start(Some("TxPoolPersistService")))
+    } else {
+        None
+    };
+    let tx_pool_expiry_controller =
+        TxPoolExpiryService::new(shared.clone(), Duration::from_secs(TX_POOL_EXPIRY_INTERVAL))
+            .start(Some("TxPoolExpiryService"));
+
     let block_assembler_controller =
         match (args.config.rpc.miner_enable(), args.config.block_assembler) {
             (true, Some(block_assembler)) => {
@@ -135,6 +170,30 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
     .start(version, Some("NetworkService"))
     .expect("Start network service failed");
 
+    let stratum_controller = match (
+        args.config.rpc.stratum_listen_address.clone(),
+        block_assembler_controller.clone(),
+    ) {
+        (Some(listen_address), Some(block_assembler_controller)) => {
+            let share_difficulty = args
+                .config
+                .rpc
+                .stratum_share_difficulty
+                .clone()
+                .unwrap_or_else(U256::max_value);
+            let server = StratumServer::new(
+                listen_address,
+                share_difficulty,
+                shared.clone(),
+                network_controller.clone(),
+                chain_controller.clone(),
+                block_assembler_controller,
+            );
+            Some(server.start(Some("StratumServer")))
+        }
+        _ => None,
+    };
+
     let builder = ServiceBuilder::new(&args.config.rpc)
         .enable_chain(shared.clone())
         .enable_pool(shared.clone(), network_controller.clone())
@@ -153,10 +212,11 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
             chain_controller.clone(),
         )
         .enable_alert(alert_verifier, alert_notifier, network_controller)
-        .enable_indexer(&args.config.indexer_db, shared.clone());
-    let io_handler = builder.build();
+        .enable_indexer(&args.config.indexer_db, shared.clone())
+        .enable_subscription(&notify);
+    let rpc_modules = builder.build();
 
-    let rpc_server = RpcServer::new(args.config.rpc, io_handler);
+    let rpc_server = RpcServer::new(args.config.rpc, rpc_modules);
 
     wait_for_exit();
 
@@ -164,6 +224,12 @@ pub fn run(args: RunArgs, version: Version) -> Result<(), ExitCode> {
 
     rpc_server.close();
     info_target!(crate::LOG_TARGET_MAIN, "Jsonrpc shutdown");
+
+    drop(stratum_controller);
+
+    drop(tx_pool_expiry_controller);
+    // save the tx pool
+    drop(tx_pool_persist_controller);
     Ok(())
 }
 
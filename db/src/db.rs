@@ -1,7 +1,9 @@
 use crate::transaction::RocksDBTransaction;
 use crate::{Col, DBConfig, Error, Result};
 use ckb_logger::{info, warn};
-use rocksdb::ops::{Get, GetColumnFamilys, GetPinnedCF, IterateCF, OpenCF, Put, SetOptions};
+use rocksdb::ops::{
+    CompactRangeCF, Get, GetColumnFamilys, GetPinnedCF, IterateCF, OpenCF, Put, SetOptions,
+};
 use rocksdb::{
     ColumnFamily, DBPinnableSlice, IteratorMode, OptimisticTransactionDB,
     OptimisticTransactionOptions, Options, WriteOptions,
@@ -152,6 +154,14 @@ impl RocksDB {
         Ok(())
     }
 
+    /// Compacts the keys in the range of the column, the whole column if the bounds are not set
+    pub fn compact_range(&self, col: Col, start: Option<&[u8]>, end: Option<&[u8]>) -> Result<()> {
+        let cf = cf_handle(&self.inner, col)?;
+        self.inner
+            .compact_range_cf(cf, start, end)
+            .map_err(Into::into)
+    }
+
     /// Set a snapshot at start of transaction by setting set_snapshot=true
     pub fn transaction(&self) -> RocksDBTransaction {
         let write_options = WriteOptions::default();
@@ -282,6 +292,25 @@ mod tests {
         assert!(vec![4u8, 3, 2].as_slice() == &ret.as_ref()[1..4]);
     }
 
+    #[test]
+    fn compact_range() {
+        let db = setup_db("compact_range", 2);
+
+        let txn = db.transaction();
+        txn.put("0", &[0, 0], &[0, 0, 0]).unwrap();
+        txn.put("0", &[1, 1], &[1, 1, 1]).unwrap();
+        txn.delete("0", &[1, 1]).unwrap();
+        txn.commit().unwrap();
+
+        db.compact_range("0", None, None).unwrap();
+        db.compact_range("1", Some(&[0]), Some(&[1])).unwrap();
+        assert!(
+            vec![0u8, 0, 0].as_slice() == db.get_pinned("0", &[0, 0]).unwrap().unwrap().as_ref()
+        );
+        assert!(db.get_pinned("0", &[1, 1]).unwrap().is_none());
+        assert!(db.compact_range("2", None, None).is_err());
+    }
+
     #[test]
     fn test_version_is_not_matched() {
         let tmp_dir = tempfile::Builder::new()
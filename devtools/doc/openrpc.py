@@ -0,0 +1,418 @@
+#!/usr/bin/env python
+
+# Generates the OpenRPC document of the RPC methods from the rpc traits in rpc/src/module and the
+# types in util/jsonrpc-types. The descriptions and the examples are taken from rpc/json/rpc.json.
+#
+#     ./devtools/doc/openrpc.py rpc/json/rpc.json > rpc/json/openrpc.json
+
+from __future__ import print_function
+
+import glob
+import json
+import os
+import re
+import sys
+from collections import OrderedDict
+
+ROOT = os.path.join(os.path.dirname(os.path.abspath(__file__)), os.pardir, os.pardir)
+MODULE_SOURCES = os.path.join(ROOT, "rpc", "src", "module", "*.rs")
+TYPE_SOURCES = [
+    os.path.join(ROOT, "util", "jsonrpc-types", "src", "*.rs"),
+    MODULE_SOURCES,
+]
+
+# The module in the rpc config of each trait, the methods without a module are always served
+TRAIT_MODULES = {
+    "AlertRpc": "Alert",
+    "ChainRpc": "Chain",
+    "DiscoverRpc": None,
+    "ExperimentRpc": "Experiment",
+    "IndexerRpc": "Indexer",
+    "IndexerSubscriptionRpc": "Indexer",
+    "IntegrationTestRpc": "IntegrationTest",
+    "MinerRpc": "Miner",
+    "NetworkRpc": "Net",
+    "PoolRpc": "Pool",
+    "StatsRpc": "Stats",
+    "SubscriptionRpc": "Subscription",
+}
+
+# The types defined outside of the rpc crates or serialized by hand
+PRIMITIVE_SCHEMAS = {
+    "bool": {"type": "boolean"},
+    "String": {"type": "string"},
+    "i8": {"type": "integer"},
+    "u8": {"type": "integer", "minimum": 0},
+    "u32": {"type": "integer", "minimum": 0},
+    "u64": {"type": "integer", "minimum": 0},
+    "usize": {"type": "integer", "minimum": 0},
+    "H256": {
+        "type": "string",
+        "description": "32 bytes hash encoded in 0x-prefixed hex",
+        "pattern": "^0x[0-9a-f]{64}$",
+    },
+    "U256": {
+        "type": "string",
+        "description": "256 bits unsigned integer encoded in 0x-prefixed hex",
+        "pattern": "^0x[0-9a-f]+$",
+    },
+    "JsonBytes": {
+        "type": "string",
+        "description": "Bytes encoded in 0x-prefixed hex",
+        "pattern": "^0x([0-9a-fA-F]{2})*$",
+    },
+    "ProposalShortId": {
+        "type": "string",
+        "description": "10 bytes proposal short id encoded in 0x-prefixed hex",
+        "pattern": "^0x[0-9a-fA-F]{20}$",
+    },
+    "SubscriptionId": {"oneOf": [{"type": "integer"}, {"type": "string"}]},
+}
+
+# The number newtypes with `#[serde(with = "string")]`
+DECIMAL_SCHEMA = {
+    "type": "string",
+    "description": "Unsigned integer encoded in decimal",
+    "pattern": "^[0-9]+$",
+}
+
+META_SCHEMA = {"$ref": "https://raw.githubusercontent.com/open-rpc/meta-schema/master/schema.json"}
+
+
+def fail(message):
+    print("openrpc.py: {}".format(message), file=sys.stderr)
+    sys.exit(1)
+
+
+def split_top_level(text, sep=","):
+    parts, depth, current = [], 0, ""
+    for c in text:
+        if c in "<([{":
+            depth += 1
+        elif c in ">)]}":
+            depth -= 1
+        if c == sep and depth == 0:
+            parts.append(current.strip())
+            current = ""
+        else:
+            current += c
+    if current.strip():
+        parts.append(current.strip())
+    return parts
+
+
+def strip_generic(ty, name):
+    prefix = name + "<"
+    if ty.startswith(prefix) and ty.endswith(">"):
+        return ty[len(prefix):-1].strip()
+    return None
+
+
+def comment_text(lines):
+    return " ".join(line.strip().lstrip("/").strip() for line in lines).strip()
+
+
+def rename(name, rule):
+    if rule is None:
+        return name
+    snake = re.sub(r"(?<!^)([A-Z])", r"_\1", name).lower()
+    if rule == "snake_case":
+        return snake
+    if rule == "lowercase":
+        return name.lower()
+    fail("unsupported rename_all {}".format(rule))
+
+
+def serde_args(attrs):
+    args = {}
+    for attr in attrs:
+        m = re.match(r"#\[serde\((.*)\)\]$", attr)
+        if not m:
+            continue
+        for arg in split_top_level(m.group(1)):
+            if "=" in arg:
+                key, value = arg.split("=", 1)
+                args[key.strip()] = value.strip().strip('"')
+            else:
+                args[arg.strip()] = True
+    return args
+
+
+def parse_items(body):
+    """Parses the fields of a struct body or the variants of an enum body."""
+    items, attrs, comments, pending = [], [], [], ""
+    for line in body.splitlines():
+        line = line.strip()
+        if not pending:
+            if not line:
+                continue
+            if line.startswith("//"):
+                comments.append(line)
+                continue
+            if line.startswith("#["):
+                attrs.append(line)
+                continue
+        line = line.split("//", 1)[0].strip()
+        pending = (pending + " " + line).strip()
+        if pending.count("{") > pending.count("}") or not (
+            pending.endswith(",") or pending.endswith("}")
+        ):
+            continue
+        items.append((pending.rstrip(","), attrs, comment_text(comments)))
+        attrs, comments, pending = [], [], ""
+    if pending:
+        items.append((pending, attrs, comment_text(comments)))
+    return items
+
+
+def parse_fields(body):
+    fields = []
+    for item, attrs, description in parse_items(body):
+        m = re.match(r"(?:pub(?:\([a-z]+\))? )?(\w+): (.+)$", item)
+        if not m:
+            fail("unrecognized field {}".format(item))
+        fields.append((m.group(1), m.group(2).strip(), serde_args(attrs), description))
+    return fields
+
+
+def parse_types():
+    types = {}
+    item_re = re.compile(
+        r"((?:^//.*\n)*)((?:^#\[.*\]\n)+)^pub (struct|enum) (\w+)(.*?)$",
+        re.M,
+    )
+    for pattern in TYPE_SOURCES:
+        for path in sorted(glob.glob(pattern)):
+            with open(path) as fp:
+                source = fp.read()
+            for m in item_re.finditer(source):
+                comments, attrs, kind, name, rest = m.groups()
+                attrs = attrs.splitlines()
+                if not any("Serialize" in attr or "Deserialize" in attr for attr in attrs):
+                    continue
+                ty = {
+                    "kind": kind,
+                    "serde": serde_args(attrs),
+                    "description": comment_text(comments.splitlines()),
+                }
+                rest = rest.strip()
+                if rest.startswith("("):
+                    inner = rest[1 : rest.rindex(")")]
+                    ty["kind"] = "newtype"
+                    ty["decimal"] = 'with = "string"' in inner
+                else:
+                    start = m.end()
+                    end = source.index("\n}", start)
+                    body = source[start:end]
+                    if kind == "struct":
+                        ty["fields"] = parse_fields(body)
+                    else:
+                        ty["variants"] = parse_items(body)
+                types[name] = ty
+    return types
+
+
+class SchemaBuilder(object):
+    def __init__(self, types):
+        self.types = types
+        self.schemas = OrderedDict()
+
+    def reference(self, name):
+        if name not in self.schemas:
+            self.schemas[name] = None
+            self.schemas[name] = self.build(name)
+        return {"$ref": "#/components/schemas/{}".format(name)}
+
+    def schema(self, ty):
+        ty = ty.strip()
+        inner = strip_generic(ty, "Option")
+        if inner is not None:
+            return {"oneOf": [self.schema(inner), {"type": "null"}]}
+        inner = strip_generic(ty, "Vec")
+        if inner is not None:
+            return {"type": "array", "items": self.schema(inner)}
+        if ty == "()":
+            return {"type": "null"}
+        if ty in PRIMITIVE_SCHEMAS and ty not in ("H256", "U256", "JsonBytes", "ProposalShortId"):
+            return dict(PRIMITIVE_SCHEMAS[ty])
+        if ty in PRIMITIVE_SCHEMAS or ty in self.types:
+            return self.reference(ty)
+        fail("unknown type {}".format(ty))
+
+    def object_schema(self, fields, rename_all=None):
+        schema = OrderedDict([("type", "object")])
+        properties, required, flattened = OrderedDict(), [], []
+        for name, ty, serde, description in fields:
+            if serde.get("flatten"):
+                flattened.append(self.schema(ty))
+                continue
+            property_schema = self.schema(ty)
+            if description:
+                property_schema["description"] = description
+            key = serde.get("rename", rename(name, rename_all))
+            properties[key] = property_schema
+            if strip_generic(ty, "Option") is None and "default" not in serde:
+                required.append(key)
+        schema["properties"] = properties
+        if required:
+            schema["required"] = required
+        if flattened:
+            return {"allOf": flattened + [schema]}
+        return schema
+
+    def build(self, name):
+        if name in PRIMITIVE_SCHEMAS:
+            return dict(PRIMITIVE_SCHEMAS[name])
+        ty = self.types[name]
+        if ty["kind"] == "newtype":
+            if not ty["decimal"]:
+                fail("newtype {} is not serialized as a decimal string".format(name))
+            schema = dict(DECIMAL_SCHEMA)
+        elif ty["kind"] == "struct":
+            schema = self.object_schema(ty["fields"])
+        else:
+            schema = self.enum_schema(name, ty)
+        if ty["description"]:
+            schema["description"] = ty["description"]
+        return schema
+
+    def enum_schema(self, name, ty):
+        rename_all = ty["serde"].get("rename_all")
+        tag = ty["serde"].get("tag")
+        units, structs = [], []
+        for item, _, description in ty["variants"]:
+            m = re.match(r"(\w+)\s*\{(.*)\}$", item, re.S)
+            if m:
+                structs.append((m.group(1), m.group(2), description))
+            elif re.match(r"\w+$", item):
+                units.append(rename(item, rename_all))
+            else:
+                fail("unsupported variant {} of {}".format(item, name))
+        if not structs:
+            return {"type": "string", "enum": units}
+        if units or tag is None:
+            fail("only the internally tagged struct variants are supported in {}".format(name))
+        variants = []
+        for variant, body, description in structs:
+            fields = parse_fields(body.replace(",", ",\n"))
+            schema = self.object_schema(fields)
+            tag_value = rename(variant, rename_all)
+            schema["properties"] = OrderedDict(
+                [(tag, {"type": "string", "enum": [tag_value]})]
+                + list(schema["properties"].items())
+            )
+            schema["required"] = [tag] + schema.get("required", [])
+            if description:
+                schema["description"] = description
+            variants.append(schema)
+        return {"oneOf": variants}
+
+
+def parse_methods():
+    methods = []
+    trait_re = re.compile(r"((?:^//[^\n]*\n)*)#\[rpc\]\npub trait (\w+) \{\n(.*?)\n\}", re.M | re.S)
+    method_re = re.compile(
+        r"((?:^ *//[^\n]*\n)*)^ *#\[(rpc|pubsub)\((.*?)\)\]\s*fn (\w+)\((.*?)\)\s*(?:->\s*(.*?))?;",
+        re.M | re.S,
+    )
+    for path in sorted(glob.glob(MODULE_SOURCES)):
+        with open(path) as fp:
+            source = fp.read()
+        for trait in trait_re.finditer(source):
+            trait_comments, trait_name, body = trait.groups()
+            if trait_name not in TRAIT_MODULES:
+                fail("unknown module of trait {}".format(trait_name))
+            for m in method_re.finditer(body):
+                comments, kind, attr, _, args, result = m.groups()
+                name = re.search(r'name = "(\w+)"', attr).group(1)
+                comments = [line for line in comments.splitlines() if "curl " not in line]
+                method = {
+                    "name": name,
+                    "module": TRAIT_MODULES[trait_name],
+                    "description": comment_text(comments),
+                    "params": [],
+                    "subscribe": kind == "pubsub" and "subscribe" in split_top_level(attr),
+                }
+                if method["subscribe"] and not method["description"]:
+                    method["description"] = comment_text(trait_comments.splitlines())
+                for arg in split_top_level(args):
+                    if arg in ("&self", "self"):
+                        continue
+                    arg_name, arg_type = [part.strip() for part in arg.split(":", 1)]
+                    if arg_type == "Self::Metadata":
+                        continue
+                    notification = strip_generic(arg_type, "Subscriber")
+                    if notification is not None:
+                        method["notification"] = notification
+                        continue
+                    method["params"].append((arg_name.lstrip("_"), arg_type))
+                if method["subscribe"]:
+                    method["result"] = "SubscriptionId"
+                else:
+                    method["result"] = strip_generic(result.strip(), "Result")
+                methods.append(method)
+    return methods
+
+
+def example(case, params):
+    result = OrderedDict()
+    result["name"] = case["method"]
+    result["params"] = [
+        OrderedDict([("name", name), ("value", value)])
+        for (name, _), value in zip(params, case["params"])
+    ]
+    result["result"] = OrderedDict([("name", "result"), ("value", case["result"])])
+    return result
+
+
+def main():
+    if len(sys.argv) != 2:
+        fail("usage: openrpc.py rpc/json/rpc.json")
+    with open(sys.argv[1]) as fp:
+        cases = dict((case["method"], case) for case in json.load(fp, object_pairs_hook=OrderedDict))
+    with open(os.path.join(ROOT, "rpc", "Cargo.toml")) as fp:
+        version = re.search(r'^version = "(.*)"$', fp.read(), re.M).group(1)
+
+    builder = SchemaBuilder(parse_types())
+    methods = []
+    for method in parse_methods():
+        document = OrderedDict()
+        document["name"] = method["name"]
+        if method["module"] is not None:
+            document["tags"] = [{"name": method["module"]}]
+        case = cases.get(method["name"])
+        description = case["description"] if case else method["description"]
+        if description:
+            document["description"] = description
+        document["params"] = []
+        for name, ty in method["params"]:
+            param = OrderedDict([("name", name)])
+            optional = strip_generic(ty, "Option")
+            param["required"] = optional is None
+            param["schema"] = builder.schema(optional or ty)
+            document["params"].append(param)
+        if method["name"] == "rpc_discover":
+            result_schema = META_SCHEMA
+        else:
+            result_schema = builder.schema(method["result"])
+        document["result"] = OrderedDict([("name", "result"), ("schema", result_schema)])
+        if "notification" in method:
+            document["x-notification"] = OrderedDict(
+                [("name", "result"), ("schema", builder.schema(method["notification"]))]
+            )
+        if case and not case.get("skip"):
+            document["examples"] = [example(case, method["params"])]
+        methods.append(document)
+
+    openrpc = OrderedDict()
+    openrpc["openrpc"] = "1.2.1"
+    openrpc["info"] = OrderedDict([("title", "CKB JSON-RPC"), ("version", version)])
+    openrpc["methods"] = methods
+    openrpc["components"] = {"schemas": OrderedDict(sorted(builder.schemas.items()))}
+
+    for line in json.dumps(openrpc, indent=4, sort_keys=True).splitlines():
+        print(line.rstrip())
+
+
+if __name__ == "__main__":
+    main()
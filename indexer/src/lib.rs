@@ -2,4 +2,7 @@ mod store;
 mod types;
 
 pub use store::{DefaultIndexerStore, IndexerStore};
-pub use types::{CellTransaction, LiveCell, TransactionPoint};
+pub use types::{
+    CellTransaction, IndexerEvent, LiveCell, LiveCellFilter, LockHashCapacity, LockHashIndex,
+    LockHashIndexState, LockPattern, LockPatternIndex, TransactionPoint, TypeHashIndex,
+};
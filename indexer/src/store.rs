@@ -1,17 +1,24 @@
 use crate::types::{
-    CellTransaction, LiveCell, LockHashCellOutput, LockHashIndex, LockHashIndexState,
-    TransactionPoint,
+    CellTransaction, IndexerEvent, LiveCell, LiveCellFilter, LockHashCapacity, LockHashCellOutput,
+    LockHashIndex, LockHashIndexState, LockPattern, LockPatternCellOutput, LockPatternIndex,
+    LockPatternIndexState, TransactionPoint, TypeHashCellOutput, TypeHashIndex, TypeHashIndexState,
 };
 use bincode::{deserialize, serialize};
 use ckb_core::block::Block;
+use ckb_core::script::Script;
 use ckb_core::transaction::{CellOutPoint, CellOutput};
-use ckb_core::BlockNumber;
-use ckb_db::{db::RocksDB, Col, DBConfig, DBIterator, Direction, RocksDBTransaction};
-use ckb_logger::{debug, error, trace};
+use ckb_core::{BlockNumber, Capacity};
+use ckb_db::{
+    db::RocksDB, Col, DBConfig, DBIterator, Direction, Error as DBError, RocksDBTransaction,
+};
+use ckb_logger::{debug, error, trace, warn};
 use ckb_shared::shared::Shared;
 use ckb_store::ChainStore;
 use ckb_traits::chain_provider::ChainProvider;
+use ckb_util::Mutex;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, TrySendError};
 use numext_fixed_hash::H256;
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::thread;
@@ -19,27 +26,65 @@ use std::time::Duration;
 
 const TXN_ATTACH_BLOCK_NUMS: usize = 100;
 const SYNC_INTERVAL: Duration = Duration::from_secs(1);
-const COLUMNS: u32 = 4;
-
-/// +---------------------------------+---------------+--------------------------+
-/// |             Column              |      Key      |          Value           |
-/// +---------------------------------+---------------+--------------------------+
-/// | COLUMN_LOCK_HASH_INDEX_STATE    | H256          | LockHashIndexState       |
-/// | COLUMN_LOCK_HASH_LIVE_CELL      | LockHashIndex | CellOutput               |
-/// | COLUMN_LOCK_HASH_TRANSACTION    | LockHashIndex | Option<TransactionPoint> |
-/// | COLUMN_CELL_OUT_POINT_LOCK_HASH | CellOutPoint  | LockHashCellOutput       |
-/// +---------------------------------+---------------+--------------------------+
+const COLUMNS: u32 = 14;
+const SUBSCRIBER_CHANNEL_SIZE: usize = 1024;
+
+/// +------------------------------------+---------------------+--------------------------+
+/// |               Column               |         Key         |          Value           |
+/// +------------------------------------+---------------------+--------------------------+
+/// | COLUMN_LOCK_HASH_INDEX_STATE       | H256                | LockHashIndexState       |
+/// | COLUMN_LOCK_HASH_LIVE_CELL         | LockHashIndex       | CellOutput               |
+/// | COLUMN_LOCK_HASH_TRANSACTION       | LockHashIndex       | Option<TransactionPoint> |
+/// | COLUMN_CELL_OUT_POINT_LOCK_HASH    | CellOutPoint        | LockHashCellOutput       |
+/// | COLUMN_TYPE_HASH_INDEX_STATE       | H256                | TypeHashIndexState       |
+/// | COLUMN_TYPE_HASH_LIVE_CELL         | TypeHashIndex       | CellOutput               |
+/// | COLUMN_TYPE_HASH_TRANSACTION       | TypeHashIndex       | Option<TransactionPoint> |
+/// | COLUMN_CELL_OUT_POINT_TYPE_HASH    | CellOutPoint        | TypeHashCellOutput       |
+/// | COLUMN_LOCK_HASH_CAPACITY          | H256                | LockHashCapacity         |
+/// | COLUMN_LOCK_PATTERN                | H256                | LockPattern              |
+/// | COLUMN_LOCK_PATTERN_INDEX_STATE    | H256                | LockPatternIndexState    |
+/// | COLUMN_LOCK_PATTERN_LIVE_CELL      | LockPatternIndex    | CellOutput               |
+/// | COLUMN_LOCK_PATTERN_TRANSACTION    | LockPatternIndex    | Option<TransactionPoint> |
+/// | COLUMN_CELL_OUT_POINT_LOCK_PATTERN | H256 + CellOutPoint | LockPatternCellOutput    |
+/// +------------------------------------+---------------------+--------------------------+
 
 const COLUMN_LOCK_HASH_INDEX_STATE: Col = "0";
 const COLUMN_LOCK_HASH_LIVE_CELL: Col = "1";
 const COLUMN_LOCK_HASH_TRANSACTION: Col = "2";
 const COLUMN_CELL_OUT_POINT_LOCK_HASH: Col = "3";
+const COLUMN_TYPE_HASH_INDEX_STATE: Col = "4";
+const COLUMN_TYPE_HASH_LIVE_CELL: Col = "5";
+const COLUMN_TYPE_HASH_TRANSACTION: Col = "6";
+const COLUMN_CELL_OUT_POINT_TYPE_HASH: Col = "7";
+const COLUMN_LOCK_HASH_CAPACITY: Col = "8";
+const COLUMN_LOCK_PATTERN: Col = "9";
+const COLUMN_LOCK_PATTERN_INDEX_STATE: Col = "10";
+const COLUMN_LOCK_PATTERN_LIVE_CELL: Col = "11";
+const COLUMN_LOCK_PATTERN_TRANSACTION: Col = "12";
+const COLUMN_CELL_OUT_POINT_LOCK_PATTERN: Col = "13";
+const ALL_COLUMNS: [Col; COLUMNS as usize] = [
+    COLUMN_LOCK_HASH_INDEX_STATE,
+    COLUMN_LOCK_HASH_LIVE_CELL,
+    COLUMN_LOCK_HASH_TRANSACTION,
+    COLUMN_CELL_OUT_POINT_LOCK_HASH,
+    COLUMN_TYPE_HASH_INDEX_STATE,
+    COLUMN_TYPE_HASH_LIVE_CELL,
+    COLUMN_TYPE_HASH_TRANSACTION,
+    COLUMN_CELL_OUT_POINT_TYPE_HASH,
+    COLUMN_LOCK_HASH_CAPACITY,
+    COLUMN_LOCK_PATTERN,
+    COLUMN_LOCK_PATTERN_INDEX_STATE,
+    COLUMN_LOCK_PATTERN_LIVE_CELL,
+    COLUMN_LOCK_PATTERN_TRANSACTION,
+    COLUMN_CELL_OUT_POINT_LOCK_PATTERN,
+];
 
 pub trait IndexerStore: Sync + Send {
     fn get_live_cells(
         &self,
         lock_hash: &H256,
-        skip_num: usize,
+        cursor: Option<&LockHashIndex>,
+        filter: Option<&LiveCellFilter>,
         take_num: usize,
         reverse_order: bool,
     ) -> Vec<LiveCell>;
@@ -47,7 +92,7 @@ pub trait IndexerStore: Sync + Send {
     fn get_transactions(
         &self,
         lock_hash: &H256,
-        skip_num: usize,
+        cursor: Option<&LockHashIndex>,
         take_num: usize,
         reverse_order: bool,
     ) -> Vec<CellTransaction>;
@@ -61,11 +106,72 @@ pub trait IndexerStore: Sync + Send {
     ) -> LockHashIndexState;
 
     fn remove_lock_hash(&self, lock_hash: &H256);
+
+    // returns None if the lock hash has not been indexed yet
+    fn get_capacity(&self, lock_hash: &H256) -> Option<LockHashCapacity>;
+
+    fn get_live_cells_by_type_hash(
+        &self,
+        type_hash: &H256,
+        cursor: Option<&TypeHashIndex>,
+        filter: Option<&LiveCellFilter>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<LiveCell>;
+
+    fn get_transactions_by_type_hash(
+        &self,
+        type_hash: &H256,
+        cursor: Option<&TypeHashIndex>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<CellTransaction>;
+
+    fn get_type_hash_index_states(&self) -> HashMap<H256, TypeHashIndexState>;
+
+    fn insert_type_hash(
+        &self,
+        type_hash: &H256,
+        index_from: Option<BlockNumber>,
+    ) -> TypeHashIndexState;
+
+    fn remove_type_hash(&self, type_hash: &H256);
+
+    fn get_live_cells_by_lock_pattern(
+        &self,
+        pattern_hash: &H256,
+        cursor: Option<&LockPatternIndex>,
+        filter: Option<&LiveCellFilter>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<LiveCell>;
+
+    fn get_transactions_by_lock_pattern(
+        &self,
+        pattern_hash: &H256,
+        cursor: Option<&LockPatternIndex>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<CellTransaction>;
+
+    fn get_lock_patterns(&self) -> HashMap<H256, LockPattern>;
+
+    fn get_lock_pattern_index_states(&self) -> HashMap<H256, LockPatternIndexState>;
+
+    // the registered lock pattern is identified by `LockPattern::hash`
+    fn insert_lock_pattern(
+        &self,
+        lock_pattern: &LockPattern,
+        index_from: Option<BlockNumber>,
+    ) -> LockPatternIndexState;
+
+    fn remove_lock_pattern(&self, pattern_hash: &H256);
 }
 
 pub struct DefaultIndexerStore {
     db: Arc<RocksDB>,
     shared: Shared,
+    subscribers: Arc<Mutex<HashMap<String, Sender<IndexerEvent>>>>,
 }
 
 impl Clone for DefaultIndexerStore {
@@ -73,6 +179,7 @@ impl Clone for DefaultIndexerStore {
         DefaultIndexerStore {
             db: Arc::clone(&self.db),
             shared: self.shared.clone(),
+            subscribers: Arc::clone(&self.subscribers),
         }
     }
 }
@@ -81,65 +188,58 @@ impl IndexerStore for DefaultIndexerStore {
     fn get_live_cells(
         &self,
         lock_hash: &H256,
-        skip_num: usize,
+        cursor: Option<&LockHashIndex>,
+        filter: Option<&LiveCellFilter>,
         take_num: usize,
         reverse_order: bool,
     ) -> Vec<LiveCell> {
-        let mut from_key = lock_hash.to_vec();
-        let iter = if reverse_order {
-            from_key.extend_from_slice(&BlockNumber::max_value().to_be_bytes());
-            self.db
-                .iter(COLUMN_LOCK_HASH_LIVE_CELL, &from_key, Direction::Reverse)
-        } else {
-            self.db
-                .iter(COLUMN_LOCK_HASH_LIVE_CELL, &from_key, Direction::Forward)
-        };
-        iter.expect("indexer db iter should be ok")
-            .skip(skip_num)
-            .take(take_num)
-            .take_while(|(key, _)| key.starts_with(lock_hash.as_bytes()))
-            .map(|(key, value)| {
+        self.iter_by_hash(
+            COLUMN_LOCK_HASH_LIVE_CELL,
+            lock_hash,
+            cursor.map(LockHashIndex::to_vec),
+            take_num,
+            reverse_order,
+            |key, value| {
                 let cell_output: CellOutput =
-                    deserialize(&value).expect("deserialize CellOutput should be ok");
-                let lock_hash_index = LockHashIndex::from_slice(&key);
-                LiveCell {
-                    created_by: lock_hash_index.into(),
-                    cell_output,
+                    deserialize(value).expect("deserialize CellOutput should be ok");
+                let lock_hash_index = LockHashIndex::from_slice(key);
+                if filter.map_or(true, |filter| {
+                    filter.matches(lock_hash_index.block_number, &cell_output)
+                }) {
+                    Some(LiveCell {
+                        created_by: lock_hash_index.into(),
+                        cell_output,
+                    })
+                } else {
+                    None
                 }
-            })
-            .collect()
+            },
+        )
     }
 
     fn get_transactions(
         &self,
         lock_hash: &H256,
-        skip_num: usize,
+        cursor: Option<&LockHashIndex>,
         take_num: usize,
         reverse_order: bool,
     ) -> Vec<CellTransaction> {
-        let mut from_key = lock_hash.to_vec();
-        let iter = if reverse_order {
-            from_key.extend_from_slice(&BlockNumber::max_value().to_be_bytes());
-            self.db
-                .iter(COLUMN_LOCK_HASH_TRANSACTION, &from_key, Direction::Reverse)
-        } else {
-            self.db
-                .iter(COLUMN_LOCK_HASH_TRANSACTION, &from_key, Direction::Forward)
-        };
-        iter.expect("indexer db iter should be ok")
-            .skip(skip_num)
-            .take(take_num)
-            .take_while(|(key, _)| key.starts_with(lock_hash.as_bytes()))
-            .map(|(key, value)| {
+        self.iter_by_hash(
+            COLUMN_LOCK_HASH_TRANSACTION,
+            lock_hash,
+            cursor.map(LockHashIndex::to_vec),
+            take_num,
+            reverse_order,
+            |key, value| {
                 let consumed_by: Option<TransactionPoint> =
-                    deserialize(&value).expect("deserialize TransactionPoint should be ok");
-                let lock_hash_index = LockHashIndex::from_slice(&key);
-                CellTransaction {
+                    deserialize(value).expect("deserialize TransactionPoint should be ok");
+                let lock_hash_index = LockHashIndex::from_slice(key);
+                Some(CellTransaction {
                     created_by: lock_hash_index.into(),
                     consumed_by,
-                }
-            })
-            .collect()
+                })
+            },
+        )
     }
 
     fn get_lock_hash_index_states(&self) -> HashMap<H256, LockHashIndexState> {
@@ -160,25 +260,11 @@ impl IndexerStore for DefaultIndexerStore {
         lock_hash: &H256,
         index_from: Option<BlockNumber>,
     ) -> LockHashIndexState {
-        let index_state = {
-            let tip_number = self
-                .shared
-                .store()
-                .get_tip_header()
-                .expect("tip header exists")
-                .number();
-            let block_number = index_from.unwrap_or_else(|| tip_number).min(tip_number);
-            LockHashIndexState {
-                block_number,
-                block_hash: self
-                    .shared
-                    .store()
-                    .get_block_hash(block_number)
-                    .expect("block exists"),
-            }
-        };
+        let index_state = self.init_index_state(index_from);
+        let lock_hash_capacity = self.compute_lock_hash_capacity(lock_hash);
         self.commit_txn(|txn| {
             txn.insert_lock_hash_index_state(lock_hash, &index_state);
+            txn.insert_lock_hash_capacity(lock_hash, &lock_hash_capacity);
         });
         index_state
     }
@@ -216,9 +302,305 @@ impl IndexerStore for DefaultIndexerStore {
                     txn.delete_lock_hash_transaction(&lock_hash_index);
                 });
 
+            txn.delete_lock_hash_capacity(&lock_hash);
             txn.delete_lock_hash_index_state(&lock_hash);
         });
     }
+
+    fn get_capacity(&self, lock_hash: &H256) -> Option<LockHashCapacity> {
+        self.db
+            .get_pinned(COLUMN_LOCK_HASH_INDEX_STATE, lock_hash.as_bytes())
+            .expect("indexer db read should be ok")
+            .map(|_| {
+                self.db
+                    .get_pinned(COLUMN_LOCK_HASH_CAPACITY, lock_hash.as_bytes())
+                    .expect("indexer db read should be ok")
+                    .map(|value| {
+                        deserialize(&value).expect("deserialize LockHashCapacity should be ok")
+                    })
+                    // the lock hashes indexed before the capacity is tracked have no capacity row
+                    .unwrap_or_else(|| self.compute_lock_hash_capacity(lock_hash))
+            })
+    }
+
+    fn get_live_cells_by_type_hash(
+        &self,
+        type_hash: &H256,
+        cursor: Option<&TypeHashIndex>,
+        filter: Option<&LiveCellFilter>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<LiveCell> {
+        self.iter_by_hash(
+            COLUMN_TYPE_HASH_LIVE_CELL,
+            type_hash,
+            cursor.map(TypeHashIndex::to_vec),
+            take_num,
+            reverse_order,
+            |key, value| {
+                let cell_output: CellOutput =
+                    deserialize(value).expect("deserialize CellOutput should be ok");
+                let type_hash_index = TypeHashIndex::from_slice(key);
+                if filter.map_or(true, |filter| {
+                    filter.matches(type_hash_index.block_number, &cell_output)
+                }) {
+                    Some(LiveCell {
+                        created_by: type_hash_index.into(),
+                        cell_output,
+                    })
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    fn get_transactions_by_type_hash(
+        &self,
+        type_hash: &H256,
+        cursor: Option<&TypeHashIndex>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<CellTransaction> {
+        self.iter_by_hash(
+            COLUMN_TYPE_HASH_TRANSACTION,
+            type_hash,
+            cursor.map(TypeHashIndex::to_vec),
+            take_num,
+            reverse_order,
+            |key, value| {
+                let consumed_by: Option<TransactionPoint> =
+                    deserialize(value).expect("deserialize TransactionPoint should be ok");
+                let type_hash_index = TypeHashIndex::from_slice(key);
+                Some(CellTransaction {
+                    created_by: type_hash_index.into(),
+                    consumed_by,
+                })
+            },
+        )
+    }
+
+    fn get_type_hash_index_states(&self) -> HashMap<H256, TypeHashIndexState> {
+        self.db
+            .iter(COLUMN_TYPE_HASH_INDEX_STATE, &[], Direction::Forward)
+            .expect("indexer db iter should be ok")
+            .map(|(key, value)| {
+                (
+                    H256::from_slice(&key).expect("db safe access"),
+                    deserialize(&value).expect("deserialize TypeHashIndexState should be ok"),
+                )
+            })
+            .collect()
+    }
+
+    fn insert_type_hash(
+        &self,
+        type_hash: &H256,
+        index_from: Option<BlockNumber>,
+    ) -> TypeHashIndexState {
+        let index_state = self.init_index_state(index_from);
+        self.commit_txn(|txn| {
+            txn.insert_type_hash_index_state(type_hash, &index_state);
+        });
+        index_state
+    }
+
+    fn remove_type_hash(&self, type_hash: &H256) {
+        self.commit_txn(|txn| {
+            let iter = self
+                .db
+                .iter(
+                    COLUMN_TYPE_HASH_LIVE_CELL,
+                    type_hash.as_bytes(),
+                    Direction::Forward,
+                )
+                .expect("indexer db iter should be ok");
+
+            iter.take_while(|(key, _)| key.starts_with(type_hash.as_bytes()))
+                .for_each(|(key, _)| {
+                    let type_hash_index = TypeHashIndex::from_slice(&key);
+                    txn.delete_type_hash_live_cell(&type_hash_index);
+                    txn.delete_cell_out_point_type_hash(&type_hash_index.cell_out_point);
+                });
+
+            let iter = self
+                .db
+                .iter(
+                    COLUMN_TYPE_HASH_TRANSACTION,
+                    type_hash.as_bytes(),
+                    Direction::Forward,
+                )
+                .expect("indexer db iter should be ok");
+
+            iter.take_while(|(key, _)| key.starts_with(type_hash.as_bytes()))
+                .for_each(|(key, _)| {
+                    let type_hash_index = TypeHashIndex::from_slice(&key);
+                    txn.delete_type_hash_transaction(&type_hash_index);
+                });
+
+            txn.delete_type_hash_index_state(&type_hash);
+        });
+    }
+
+    fn get_live_cells_by_lock_pattern(
+        &self,
+        pattern_hash: &H256,
+        cursor: Option<&LockPatternIndex>,
+        filter: Option<&LiveCellFilter>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<LiveCell> {
+        self.iter_by_hash(
+            COLUMN_LOCK_PATTERN_LIVE_CELL,
+            pattern_hash,
+            cursor.map(LockPatternIndex::to_vec),
+            take_num,
+            reverse_order,
+            |key, value| {
+                let cell_output: CellOutput =
+                    deserialize(value).expect("deserialize CellOutput should be ok");
+                let lock_pattern_index = LockPatternIndex::from_slice(key);
+                if filter.map_or(true, |filter| {
+                    filter.matches(lock_pattern_index.block_number, &cell_output)
+                }) {
+                    Some(LiveCell {
+                        created_by: lock_pattern_index.into(),
+                        cell_output,
+                    })
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    fn get_transactions_by_lock_pattern(
+        &self,
+        pattern_hash: &H256,
+        cursor: Option<&LockPatternIndex>,
+        take_num: usize,
+        reverse_order: bool,
+    ) -> Vec<CellTransaction> {
+        self.iter_by_hash(
+            COLUMN_LOCK_PATTERN_TRANSACTION,
+            pattern_hash,
+            cursor.map(LockPatternIndex::to_vec),
+            take_num,
+            reverse_order,
+            |key, value| {
+                let consumed_by: Option<TransactionPoint> =
+                    deserialize(value).expect("deserialize TransactionPoint should be ok");
+                let lock_pattern_index = LockPatternIndex::from_slice(key);
+                Some(CellTransaction {
+                    created_by: lock_pattern_index.into(),
+                    consumed_by,
+                })
+            },
+        )
+    }
+
+    fn get_lock_patterns(&self) -> HashMap<H256, LockPattern> {
+        self.db
+            .iter(COLUMN_LOCK_PATTERN, &[], Direction::Forward)
+            .expect("indexer db iter should be ok")
+            .map(|(key, value)| {
+                (
+                    H256::from_slice(&key).expect("db safe access"),
+                    deserialize(&value).expect("deserialize LockPattern should be ok"),
+                )
+            })
+            .collect()
+    }
+
+    fn get_lock_pattern_index_states(&self) -> HashMap<H256, LockPatternIndexState> {
+        self.db
+            .iter(COLUMN_LOCK_PATTERN_INDEX_STATE, &[], Direction::Forward)
+            .expect("indexer db iter should be ok")
+            .map(|(key, value)| {
+                (
+                    H256::from_slice(&key).expect("db safe access"),
+                    deserialize(&value).expect("deserialize LockPatternIndexState should be ok"),
+                )
+            })
+            .collect()
+    }
+
+    fn insert_lock_pattern(
+        &self,
+        lock_pattern: &LockPattern,
+        index_from: Option<BlockNumber>,
+    ) -> LockPatternIndexState {
+        let pattern_hash = lock_pattern.hash();
+        let index_state = self.init_index_state(index_from);
+        self.commit_txn(|txn| {
+            txn.insert_lock_pattern(&pattern_hash, lock_pattern);
+            txn.insert_lock_pattern_index_state(&pattern_hash, &index_state);
+        });
+        index_state
+    }
+
+    fn remove_lock_pattern(&self, pattern_hash: &H256) {
+        self.commit_txn(|txn| {
+            let iter = self
+                .db
+                .iter(
+                    COLUMN_LOCK_PATTERN_LIVE_CELL,
+                    pattern_hash.as_bytes(),
+                    Direction::Forward,
+                )
+                .expect("indexer db iter should be ok");
+
+            iter.take_while(|(key, _)| key.starts_with(pattern_hash.as_bytes()))
+                .for_each(|(key, _)| {
+                    let lock_pattern_index = LockPatternIndex::from_slice(&key);
+                    txn.delete_lock_pattern_live_cell(&lock_pattern_index);
+                });
+
+            let iter = self
+                .db
+                .iter(
+                    COLUMN_LOCK_PATTERN_TRANSACTION,
+                    pattern_hash.as_bytes(),
+                    Direction::Forward,
+                )
+                .expect("indexer db iter should be ok");
+
+            iter.take_while(|(key, _)| key.starts_with(pattern_hash.as_bytes()))
+                .for_each(|(key, _)| {
+                    let lock_pattern_index = LockPatternIndex::from_slice(&key);
+                    txn.delete_lock_pattern_transaction(&lock_pattern_index);
+                });
+
+            // the consumed cells are kept in this column also, so it's cleaned up by the key prefix
+            let iter = self
+                .db
+                .iter(
+                    COLUMN_CELL_OUT_POINT_LOCK_PATTERN,
+                    pattern_hash.as_bytes(),
+                    Direction::Forward,
+                )
+                .expect("indexer db iter should be ok");
+
+            iter.take_while(|(key, _)| key.starts_with(pattern_hash.as_bytes()))
+                .for_each(|(key, _)| {
+                    let cell_out_point: CellOutPoint =
+                        deserialize(&key[32..]).expect("deserialize CellOutPoint should be ok");
+                    txn.delete_cell_out_point_lock_pattern(pattern_hash, &cell_out_point);
+                });
+
+            txn.delete_lock_pattern_index_state(pattern_hash);
+            txn.delete_lock_pattern(pattern_hash);
+        });
+    }
+}
+
+// The lock hashes, type hashes and lock patterns (keyed by pattern hash) which should be indexed
+// when attaching or detaching a block
+#[derive(Default)]
+struct IndexHashes {
+    lock_hashes: HashSet<H256>,
+    type_hashes: HashSet<H256>,
+    lock_patterns: HashMap<H256, LockPattern>,
 }
 
 impl DefaultIndexerStore {
@@ -227,7 +609,50 @@ impl DefaultIndexerStore {
         DefaultIndexerStore {
             db: Arc::new(db),
             shared,
+            subscribers: Default::default(),
+        }
+    }
+
+    // compacts all the columns to reclaim the space of the deleted entries, such as the ones of
+    // the removed or rebuilt hashes
+    pub fn compact(&self) -> Result<(), DBError> {
+        for &col in ALL_COLUMNS.iter() {
+            self.db.compact_range(col, None, None)?;
+        }
+        Ok(())
+    }
+
+    // subscribes the events of all indexed lock hashes, a subscriber with the same name is replaced
+    pub fn subscribe<S: ToString>(&self, name: S) -> Receiver<IndexerEvent> {
+        let (sender, receiver) = crossbeam_channel::bounded(SUBSCRIBER_CHANNEL_SIZE);
+        self.subscribers.lock().insert(name.to_string(), sender);
+        receiver
+    }
+
+    pub fn unsubscribe(&self, name: &str) {
+        self.subscribers.lock().remove(name);
+    }
+
+    // events are dropped if the subscriber lags behind, to avoid blocking the sync
+    fn publish(&self, events: Vec<IndexerEvent>) {
+        if events.is_empty() {
+            return;
         }
+        self.subscribers.lock().retain(|name, sender| {
+            for event in &events {
+                match sender.try_send(event.clone()) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => {
+                        warn!("Indexer subscriber {} lags behind, drop event", name);
+                    }
+                    Err(TrySendError::Disconnected(_)) => {
+                        debug!("Indexer subscriber {} is disconnected", name);
+                        return false;
+                    }
+                }
+            }
+            true
+        });
     }
 
     pub fn start<S: ToString>(self, thread_name: Option<S>) {
@@ -236,10 +661,20 @@ impl DefaultIndexerStore {
             thread_builder = thread_builder.name(name.to_string());
         }
 
+        let switch_fork_receiver = self.shared.notify().subscribe_switch_fork("IndexerStore");
         thread_builder
             .spawn(move || loop {
                 self.sync_index_states();
-                thread::sleep(SYNC_INTERVAL);
+                // Syncs once the main chain is changed, and periodically in case the events are
+                // dropped because the indexer lags behind
+                match switch_fork_receiver.recv_timeout(SYNC_INTERVAL) {
+                    Ok(_) => {
+                        // the pending events are covered by the coming sync
+                        switch_fork_receiver.try_iter().for_each(drop);
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => thread::sleep(SYNC_INTERVAL),
+                }
             })
             .expect("start DefaultIndexerStore failed");
     }
@@ -250,71 +685,197 @@ impl DefaultIndexerStore {
         F: FnOnce(&IndexerStoreTransaction),
     {
         let db_txn = self.db.transaction();
-        let mut txn = IndexerStoreTransaction { txn: db_txn };
+        let mut txn = IndexerStoreTransaction {
+            txn: db_txn,
+            stale_capacities: Default::default(),
+        };
         process(&mut txn);
+        let stale_capacities = txn.stale_capacities.replace(HashSet::new());
         txn.commit();
+        if !stale_capacities.is_empty() {
+            self.recompute_lock_hash_capacities(&stale_capacities);
+        }
     }
 
-    pub fn sync_index_states(&self) {
-        debug!("Start sync index states with chain store");
-        let mut lock_hash_index_states = self.get_lock_hash_index_states();
-        if lock_hash_index_states.is_empty() {
-            return;
-        }
+    // Sums the live cells of the lock hash
+    fn compute_lock_hash_capacity(&self, lock_hash: &H256) -> LockHashCapacity {
+        self.db
+            .iter(
+                COLUMN_LOCK_HASH_LIVE_CELL,
+                lock_hash.as_bytes(),
+                Direction::Forward,
+            )
+            .expect("indexer db iter should be ok")
+            .take_while(|(key, _)| key.starts_with(lock_hash.as_bytes()))
+            .fold(
+                LockHashCapacity::default(),
+                |mut lock_hash_capacity, (_, value)| {
+                    let cell_output: CellOutput =
+                        deserialize(&value).expect("deserialize CellOutput should be ok");
+                    lock_hash_capacity.capacity = lock_hash_capacity
+                        .capacity
+                        .safe_add(cell_output.capacity)
+                        .expect("lock hash capacity overflow");
+                    lock_hash_capacity.cells_count += 1;
+                    lock_hash_capacity
+                },
+            )
+    }
 
-        // retains the lock hashes on fork chain and detach blocks
-        lock_hash_index_states.retain(|_, index_state| {
-            self.shared
-                .store()
-                .get_block_number(&index_state.block_hash)
-                != Some(index_state.block_number)
+    // Rewrites the capacities which are missing or out of sync from the committed live cells
+    fn recompute_lock_hash_capacities(&self, lock_hashes: &HashSet<H256>) {
+        let lock_hash_index_states = self.get_lock_hash_index_states();
+        self.commit_txn(|txn| {
+            for lock_hash in lock_hashes {
+                if lock_hash_index_states.contains_key(lock_hash) {
+                    let lock_hash_capacity = self.compute_lock_hash_capacity(lock_hash);
+                    txn.insert_lock_hash_capacity(lock_hash, &lock_hash_capacity);
+                }
+            }
         });
-        lock_hash_index_states
-            .iter()
-            .for_each(|(lock_hash, index_state)| {
-                let mut index_lock_hashes = HashSet::new();
-                index_lock_hashes.insert(lock_hash.to_owned());
-
-                let mut block = self
-                    .shared
-                    .store()
-                    .get_block(&index_state.block_hash)
-                    .expect("block exists");
-                // detach blocks until reach a block on main chain
-                self.commit_txn(|txn| {
-                    self.detach_block(txn, &index_lock_hashes, &block);
-                    while self
-                        .shared
-                        .store()
-                        .get_block_hash(block.header().number() - 1)
-                        != Some(block.header().parent_hash().to_owned())
-                    {
-                        block = self
-                            .shared
-                            .store()
-                            .get_block(block.header().parent_hash())
-                            .expect("block exists");
-                        self.detach_block(txn, &index_lock_hashes, &block);
-                    }
-                    let index_state = LockHashIndexState {
-                        block_number: block.header().number() - 1,
-                        block_hash: block.header().parent_hash().to_owned(),
-                    };
+    }
+
+    // collect at most `take_num` entries of the given hash in a column which are accepted by
+    // `filter_map`, the entries are ordered by block number and the iteration starts after the
+    // cursor (exclusive) if present
+    fn iter_by_hash<T, F>(
+        &self,
+        col: Col,
+        hash: &H256,
+        cursor: Option<Vec<u8>>,
+        take_num: usize,
+        reverse_order: bool,
+        filter_map: F,
+    ) -> Vec<T>
+    where
+        F: Fn(&[u8], &[u8]) -> Option<T>,
+    {
+        let from_key = cursor.unwrap_or_else(|| {
+            let mut from_key = hash.to_vec();
+            if reverse_order {
+                from_key.extend_from_slice(&BlockNumber::max_value().to_be_bytes());
+            }
+            from_key
+        });
+        let direction = if reverse_order {
+            Direction::Reverse
+        } else {
+            Direction::Forward
+        };
+        self.db
+            .iter(col, &from_key, direction)
+            .expect("indexer db iter should be ok")
+            .skip_while(|(key, _)| key[..] == from_key[..])
+            .take_while(|(key, _)| key.starts_with(hash.as_bytes()))
+            .filter_map(|(key, value)| filter_map(&key, &value))
+            .take(take_num)
+            .collect()
+    }
+
+    fn init_index_state(&self, index_from: Option<BlockNumber>) -> LockHashIndexState {
+        let tip_number = self
+            .shared
+            .store()
+            .get_tip_header()
+            .expect("tip header exists")
+            .number();
+        let block_number = index_from.unwrap_or_else(|| tip_number).min(tip_number);
+        LockHashIndexState {
+            block_number,
+            block_hash: self
+                .shared
+                .store()
+                .get_block_hash(block_number)
+                .expect("block exists"),
+        }
+    }
+
+    pub fn sync_index_states(&self) {
+        debug!("Start sync index states with chain store");
+        let mut lock_hash_index_states = self.get_lock_hash_index_states();
+        let mut type_hash_index_states = self.get_type_hash_index_states();
+        let mut lock_pattern_index_states = self.get_lock_pattern_index_states();
+        if lock_hash_index_states.is_empty()
+            && type_hash_index_states.is_empty()
+            && lock_pattern_index_states.is_empty()
+        {
+            return;
+        }
+        let lock_patterns = self.get_lock_patterns();
+
+        // retains the lock hashes, type hashes and lock patterns on fork chain and detach blocks
+        let on_fork_chain = |index_state: &LockHashIndexState| {
+            self.shared
+                .store()
+                .get_block_number(&index_state.block_hash)
+                != Some(index_state.block_number)
+        };
+        lock_hash_index_states.retain(|_, index_state| on_fork_chain(index_state));
+        type_hash_index_states.retain(|_, index_state| on_fork_chain(index_state));
+        lock_pattern_index_states.retain(|_, index_state| on_fork_chain(index_state));
+        lock_hash_index_states
+            .iter()
+            .for_each(|(lock_hash, index_state)| {
+                let mut index_hashes = IndexHashes::default();
+                index_hashes.lock_hashes.insert(lock_hash.to_owned());
+                let mut rollback_state = None;
+                self.commit_txn(|txn| {
+                    let index_state = self.detach_blocks(txn, &index_hashes, index_state);
                     txn.insert_lock_hash_index_state(lock_hash, &index_state);
+                    rollback_state = Some(index_state);
+                });
+                if let Some(index_state) = rollback_state {
+                    self.publish(vec![IndexerEvent::Rollback {
+                        lock_hash: lock_hash.to_owned(),
+                        block_number: index_state.block_number,
+                        block_hash: index_state.block_hash,
+                    }]);
+                }
+            });
+        type_hash_index_states
+            .iter()
+            .for_each(|(type_hash, index_state)| {
+                let mut index_hashes = IndexHashes::default();
+                index_hashes.type_hashes.insert(type_hash.to_owned());
+                self.commit_txn(|txn| {
+                    let index_state = self.detach_blocks(txn, &index_hashes, index_state);
+                    txn.insert_type_hash_index_state(type_hash, &index_state);
                 });
             });
+        lock_pattern_index_states
+            .iter()
+            .for_each(|(pattern_hash, index_state)| {
+                if let Some(lock_pattern) = lock_patterns.get(pattern_hash) {
+                    let mut index_hashes = IndexHashes::default();
+                    index_hashes
+                        .lock_patterns
+                        .insert(pattern_hash.to_owned(), lock_pattern.to_owned());
+                    self.commit_txn(|txn| {
+                        let index_state = self.detach_blocks(txn, &index_hashes, index_state);
+                        txn.insert_lock_pattern_index_state(pattern_hash, &index_state);
+                    });
+                }
+            });
 
         // attach blocks until reach tip or txn limit
         // need to check empty again because `remove_lock_hash` may be called during detach
         let mut lock_hash_index_states = self.get_lock_hash_index_states();
-        if lock_hash_index_states.is_empty() {
+        let mut type_hash_index_states = self.get_type_hash_index_states();
+        let mut lock_pattern_index_states = self.get_lock_pattern_index_states();
+        if lock_hash_index_states.is_empty()
+            && type_hash_index_states.is_empty()
+            && lock_pattern_index_states.is_empty()
+        {
             return;
         }
+        let lock_patterns = self.get_lock_patterns();
         let min_block_number: BlockNumber = lock_hash_index_states
             .values()
-            .min_by_key(|index_state| index_state.block_number)
-            .expect("none empty index states")
-            .block_number;
+            .chain(type_hash_index_states.values())
+            .chain(lock_pattern_index_states.values())
+            .map(|index_state| index_state.block_number)
+            .min()
+            .expect("none empty index states");
 
         // should index genesis block also
         let start_number = if min_block_number == 0 {
@@ -331,46 +892,116 @@ impl DefaultIndexerStore {
                 .expect("tip header exists");
             (tip_header.number(), tip_header.hash().to_owned())
         };
+        let mut events = Vec::new();
         self.commit_txn(|txn| {
             (start_number..=tip_number)
                 .take(TXN_ATTACH_BLOCK_NUMS)
                 .for_each(|block_number| {
-                    let index_lock_hashes = lock_hash_index_states
-                        .iter()
-                        .filter(|(_, index_state)| index_state.block_number <= block_number)
-                        .map(|(lock_hash, _)| lock_hash)
-                        .cloned()
-                        .collect();
+                    let index_hashes = IndexHashes {
+                        lock_hashes: lock_hash_index_states
+                            .iter()
+                            .filter(|(_, index_state)| index_state.block_number <= block_number)
+                            .map(|(lock_hash, _)| lock_hash)
+                            .cloned()
+                            .collect(),
+                        type_hashes: type_hash_index_states
+                            .iter()
+                            .filter(|(_, index_state)| index_state.block_number <= block_number)
+                            .map(|(type_hash, _)| type_hash)
+                            .cloned()
+                            .collect(),
+                        lock_patterns: lock_pattern_index_states
+                            .iter()
+                            .filter(|(_, index_state)| index_state.block_number <= block_number)
+                            .filter_map(|(pattern_hash, _)| {
+                                lock_patterns.get(pattern_hash).map(|lock_pattern| {
+                                    (pattern_hash.clone(), lock_pattern.clone())
+                                })
+                            })
+                            .collect(),
+                    };
                     let block = self
                         .shared
                         .store()
                         .get_ancestor(&tip_hash, block_number)
                         .and_then(|header| self.shared.store().get_block(&header.hash()))
                         .expect("block exists");
-                    self.attach_block(txn, &index_lock_hashes, &block);
+                    events.extend(self.attach_block(txn, &index_hashes, &block));
                     let index_state = LockHashIndexState {
                         block_number,
                         block_hash: block.header().hash().to_owned(),
                     };
-                    index_lock_hashes.into_iter().for_each(|lock_hash| {
+                    index_hashes.lock_hashes.into_iter().for_each(|lock_hash| {
                         lock_hash_index_states.insert(lock_hash, index_state.clone());
-                    })
+                    });
+                    index_hashes.type_hashes.into_iter().for_each(|type_hash| {
+                        type_hash_index_states.insert(type_hash, index_state.clone());
+                    });
+                    index_hashes
+                        .lock_patterns
+                        .into_iter()
+                        .for_each(|(pattern_hash, _)| {
+                            lock_pattern_index_states.insert(pattern_hash, index_state.clone());
+                        });
                 });
 
             lock_hash_index_states
                 .iter()
                 .for_each(|(lock_hash, index_state)| {
                     txn.insert_lock_hash_index_state(lock_hash, index_state);
-                })
+                });
+            type_hash_index_states
+                .iter()
+                .for_each(|(type_hash, index_state)| {
+                    txn.insert_type_hash_index_state(type_hash, index_state);
+                });
+            lock_pattern_index_states
+                .iter()
+                .for_each(|(pattern_hash, index_state)| {
+                    txn.insert_lock_pattern_index_state(pattern_hash, index_state);
+                });
         });
+        self.publish(events);
 
         debug!("End sync index states with chain store");
     }
 
+    // detach blocks until reach a block on main chain, returns the new index state
+    fn detach_blocks(
+        &self,
+        txn: &IndexerStoreTransaction,
+        index_hashes: &IndexHashes,
+        index_state: &LockHashIndexState,
+    ) -> LockHashIndexState {
+        let mut block = self
+            .shared
+            .store()
+            .get_block(&index_state.block_hash)
+            .expect("block exists");
+        self.detach_block(txn, index_hashes, &block);
+        while self
+            .shared
+            .store()
+            .get_block_hash(block.header().number() - 1)
+            != Some(block.header().parent_hash().to_owned())
+        {
+            block = self
+                .shared
+                .store()
+                .get_block(block.header().parent_hash())
+                .expect("block exists");
+            self.detach_block(txn, index_hashes, &block);
+        }
+        LockHashIndexState {
+            block_number: block.header().number() - 1,
+            block_hash: block.header().parent_hash().to_owned(),
+        }
+    }
+
     fn detach_block(
         &self,
         txn: &IndexerStoreTransaction,
-        index_lock_hashes: &HashSet<H256>,
+        index_hashes: &IndexHashes,
         block: &Block,
     ) {
         trace!("detach block {:x}", block.header().hash());
@@ -380,13 +1011,40 @@ impl DefaultIndexerStore {
             tx.outputs().iter().enumerate().for_each(|(index, output)| {
                 let index = index as u32;
                 let lock_hash = output.lock.hash();
-                if index_lock_hashes.contains(&lock_hash) {
+                if index_hashes.lock_hashes.contains(&lock_hash) {
                     let lock_hash_index =
                         LockHashIndex::new(lock_hash, block_number, tx_hash.clone(), index);
-                    txn.delete_lock_hash_live_cell(&lock_hash_index);
-                    txn.delete_lock_hash_transaction(&lock_hash_index);
-                    txn.delete_cell_out_point_lock_hash(&lock_hash_index.cell_out_point);
+                    txn.delete_live_cell(&lock_hash_index);
+                }
+
+                if let Some(type_hash) = output.type_.as_ref().map(Script::hash) {
+                    if index_hashes.type_hashes.contains(&type_hash) {
+                        let type_hash_index =
+                            TypeHashIndex::new(type_hash, block_number, tx_hash.clone(), index);
+                        txn.delete_type_hash_live_cell(&type_hash_index);
+                        txn.delete_type_hash_transaction(&type_hash_index);
+                        txn.delete_cell_out_point_type_hash(&type_hash_index.cell_out_point);
+                    }
                 }
+
+                index_hashes
+                    .lock_patterns
+                    .iter()
+                    .filter(|(_, lock_pattern)| lock_pattern.matches(&output.lock))
+                    .for_each(|(pattern_hash, _)| {
+                        let lock_pattern_index = LockPatternIndex::new(
+                            pattern_hash.clone(),
+                            block_number,
+                            tx_hash.clone(),
+                            index,
+                        );
+                        txn.delete_lock_pattern_live_cell(&lock_pattern_index);
+                        txn.delete_lock_pattern_transaction(&lock_pattern_index);
+                        txn.delete_cell_out_point_lock_pattern(
+                            pattern_hash,
+                            &lock_pattern_index.cell_out_point,
+                        );
+                    });
             });
 
             if !tx.is_cellbase() {
@@ -395,7 +1053,10 @@ impl DefaultIndexerStore {
                         if let Some(lock_hash_cell_output) =
                             txn.get_lock_hash_cell_output(&cell_out_point)
                         {
-                            if index_lock_hashes.contains(&lock_hash_cell_output.lock_hash) {
+                            if index_hashes
+                                .lock_hashes
+                                .contains(&lock_hash_cell_output.lock_hash)
+                            {
                                 if let Some(cell_output) = lock_hash_cell_output.cell_output {
                                     let lock_hash_index = LockHashIndex::new(
                                         lock_hash_cell_output.lock_hash.clone(),
@@ -407,42 +1068,129 @@ impl DefaultIndexerStore {
                                 }
                             }
                         }
+
+                        if let Some(type_hash_cell_output) =
+                            txn.get_type_hash_cell_output(&cell_out_point)
+                        {
+                            if index_hashes
+                                .type_hashes
+                                .contains(&type_hash_cell_output.type_hash)
+                            {
+                                if let Some(cell_output) = type_hash_cell_output.cell_output {
+                                    let type_hash_index = TypeHashIndex::new(
+                                        type_hash_cell_output.type_hash.clone(),
+                                        type_hash_cell_output.block_number,
+                                        cell_out_point.tx_hash.clone(),
+                                        cell_out_point.index,
+                                    );
+                                    txn.generate_type_hash_live_cell(type_hash_index, cell_output);
+                                }
+                            }
+                        }
+
+                        index_hashes.lock_patterns.keys().for_each(|pattern_hash| {
+                            if let Some(lock_pattern_cell_output) =
+                                txn.get_lock_pattern_cell_output(pattern_hash, &cell_out_point)
+                            {
+                                if let Some(cell_output) = lock_pattern_cell_output.cell_output {
+                                    let lock_pattern_index = LockPatternIndex::new(
+                                        pattern_hash.clone(),
+                                        lock_pattern_cell_output.block_number,
+                                        cell_out_point.tx_hash.clone(),
+                                        cell_out_point.index,
+                                    );
+                                    txn.generate_lock_pattern_live_cell(
+                                        lock_pattern_index,
+                                        cell_output,
+                                    );
+                                }
+                            }
+                        });
                     }
                 });
             }
         })
     }
 
+    // returns the events of the indexed lock hashes
     fn attach_block(
         &self,
         txn: &IndexerStoreTransaction,
-        index_lock_hashes: &HashSet<H256>,
+        index_hashes: &IndexHashes,
         block: &Block,
-    ) {
+    ) -> Vec<IndexerEvent> {
         trace!("attach block {:x}", block.header().hash());
         let block_number = block.header().number();
+        let mut events = Vec::new();
         block.transactions().iter().for_each(|tx| {
             let tx_hash = tx.hash();
             if !tx.is_cellbase() {
                 tx.inputs().iter().enumerate().for_each(|(index, input)| {
                     let index = index as u32;
                     if let Some(cell_out_point) = input.previous_output.cell.clone() {
+                        let consumed_by = TransactionPoint {
+                            block_number,
+                            tx_hash: tx_hash.clone(),
+                            index,
+                        };
+
                         if let Some(lock_hash_cell_output) =
                             txn.get_lock_hash_cell_output(&cell_out_point)
                         {
-                            if index_lock_hashes.contains(&lock_hash_cell_output.lock_hash) {
+                            if index_hashes
+                                .lock_hashes
+                                .contains(&lock_hash_cell_output.lock_hash)
+                            {
                                 let lock_hash_index = LockHashIndex::new(
-                                    lock_hash_cell_output.lock_hash,
+                                    lock_hash_cell_output.lock_hash.clone(),
                                     lock_hash_cell_output.block_number,
+                                    cell_out_point.tx_hash.clone(),
+                                    cell_out_point.index,
+                                );
+                                events.push(IndexerEvent::CellConsumed {
+                                    lock_hash: lock_hash_cell_output.lock_hash,
+                                    created_by: TransactionPoint {
+                                        block_number: lock_hash_index.block_number,
+                                        tx_hash: cell_out_point.tx_hash.clone(),
+                                        index: cell_out_point.index,
+                                    },
+                                    consumed_by: consumed_by.clone(),
+                                });
+                                txn.consume_live_cell(lock_hash_index, consumed_by.clone());
+                            }
+                        }
+
+                        index_hashes.lock_patterns.keys().for_each(|pattern_hash| {
+                            if let Some(lock_pattern_cell_output) =
+                                txn.get_lock_pattern_cell_output(pattern_hash, &cell_out_point)
+                            {
+                                let lock_pattern_index = LockPatternIndex::new(
+                                    pattern_hash.clone(),
+                                    lock_pattern_cell_output.block_number,
+                                    cell_out_point.tx_hash.clone(),
+                                    cell_out_point.index,
+                                );
+                                txn.consume_lock_pattern_live_cell(
+                                    lock_pattern_index,
+                                    consumed_by.clone(),
+                                );
+                            }
+                        });
+
+                        if let Some(type_hash_cell_output) =
+                            txn.get_type_hash_cell_output(&cell_out_point)
+                        {
+                            if index_hashes
+                                .type_hashes
+                                .contains(&type_hash_cell_output.type_hash)
+                            {
+                                let type_hash_index = TypeHashIndex::new(
+                                    type_hash_cell_output.type_hash,
+                                    type_hash_cell_output.block_number,
                                     cell_out_point.tx_hash,
                                     cell_out_point.index,
                                 );
-                                let consumed_by = TransactionPoint {
-                                    block_number,
-                                    tx_hash: tx_hash.clone(),
-                                    index,
-                                };
-                                txn.consume_live_cell(lock_hash_index, consumed_by);
+                                txn.consume_type_hash_live_cell(type_hash_index, consumed_by);
                             }
                         }
                     }
@@ -452,22 +1200,61 @@ impl DefaultIndexerStore {
             tx.outputs().iter().enumerate().for_each(|(index, output)| {
                 let index = index as u32;
                 let lock_hash = output.lock.hash();
-                if index_lock_hashes.contains(&lock_hash) {
+                if index_hashes.lock_hashes.contains(&lock_hash) {
                     let lock_hash_index =
                         LockHashIndex::new(lock_hash.clone(), block_number, tx_hash.clone(), index);
+                    events.push(IndexerEvent::CellCreated {
+                        lock_hash: lock_hash.clone(),
+                        live_cell: LiveCell {
+                            created_by: TransactionPoint {
+                                block_number,
+                                tx_hash: tx_hash.clone(),
+                                index,
+                            },
+                            cell_output: output.clone(),
+                        },
+                    });
                     txn.generate_live_cell(lock_hash_index, output.clone());
                 }
+
+                if let Some(type_hash) = output.type_.as_ref().map(Script::hash) {
+                    if index_hashes.type_hashes.contains(&type_hash) {
+                        let type_hash_index =
+                            TypeHashIndex::new(type_hash, block_number, tx_hash.clone(), index);
+                        txn.generate_type_hash_live_cell(type_hash_index, output.clone());
+                    }
+                }
+
+                index_hashes
+                    .lock_patterns
+                    .iter()
+                    .filter(|(_, lock_pattern)| lock_pattern.matches(&output.lock))
+                    .for_each(|(pattern_hash, _)| {
+                        let lock_pattern_index = LockPatternIndex::new(
+                            pattern_hash.clone(),
+                            block_number,
+                            tx_hash.clone(),
+                            index,
+                        );
+                        txn.generate_lock_pattern_live_cell(lock_pattern_index, output.clone());
+                    });
             });
-        })
+        });
+        events
     }
 }
 
 struct IndexerStoreTransaction {
     pub txn: RocksDBTransaction,
+    // the lock hashes whose capacities are recomputed after the commit
+    stale_capacities: RefCell<HashSet<H256>>,
 }
 
 impl IndexerStoreTransaction {
     fn generate_live_cell(&self, lock_hash_index: LockHashIndex, cell_output: CellOutput) {
+        if self.get_lock_hash_live_cell(&lock_hash_index).is_none() {
+            self.increase_lock_hash_capacity(&lock_hash_index.lock_hash, cell_output.capacity);
+        }
         self.insert_lock_hash_live_cell(&lock_hash_index, &cell_output);
         self.insert_lock_hash_transaction(&lock_hash_index, &None);
 
@@ -483,19 +1270,15 @@ impl IndexerStoreTransaction {
     }
 
     fn consume_live_cell(&self, lock_hash_index: LockHashIndex, consumed_by: TransactionPoint) {
-        if let Some(lock_hash_cell_output) = self
-            .txn
-            .get(COLUMN_LOCK_HASH_LIVE_CELL, &lock_hash_index.to_vec())
-            .expect("indexer db read should be ok")
-            .map(|value| deserialize(&value).expect("deserialize CellOutput should be ok"))
-            .map(|cell_output: CellOutput| LockHashCellOutput {
+        if let Some(cell_output) = self.get_lock_hash_live_cell(&lock_hash_index) {
+            self.decrease_lock_hash_capacity(&lock_hash_index.lock_hash, cell_output.capacity);
+            self.delete_lock_hash_live_cell(&lock_hash_index);
+            self.insert_lock_hash_transaction(&lock_hash_index, &Some(consumed_by));
+            let lock_hash_cell_output = LockHashCellOutput {
                 lock_hash: lock_hash_index.lock_hash.clone(),
                 block_number: lock_hash_index.block_number,
                 cell_output: Some(cell_output),
-            })
-        {
-            self.delete_lock_hash_live_cell(&lock_hash_index);
-            self.insert_lock_hash_transaction(&lock_hash_index, &Some(consumed_by));
+            };
             self.insert_cell_out_point_lock_hash(
                 &lock_hash_index.cell_out_point,
                 &lock_hash_cell_output,
@@ -503,6 +1286,149 @@ impl IndexerStoreTransaction {
         }
     }
 
+    // delete the cell generated by a detached block, the cell may be consumed already
+    fn delete_live_cell(&self, lock_hash_index: &LockHashIndex) {
+        if let Some(cell_output) = self.get_lock_hash_live_cell(lock_hash_index) {
+            self.decrease_lock_hash_capacity(&lock_hash_index.lock_hash, cell_output.capacity);
+        }
+        self.delete_lock_hash_live_cell(lock_hash_index);
+        self.delete_lock_hash_transaction(lock_hash_index);
+        self.delete_cell_out_point_lock_hash(&lock_hash_index.cell_out_point);
+    }
+
+    fn increase_lock_hash_capacity(&self, lock_hash: &H256, capacity: Capacity) {
+        self.update_lock_hash_capacity(lock_hash, |lock_hash_capacity| {
+            Some(LockHashCapacity {
+                capacity: lock_hash_capacity.capacity.safe_add(capacity).ok()?,
+                cells_count: lock_hash_capacity.cells_count.checked_add(1)?,
+            })
+        });
+    }
+
+    fn decrease_lock_hash_capacity(&self, lock_hash: &H256, capacity: Capacity) {
+        self.update_lock_hash_capacity(lock_hash, |lock_hash_capacity| {
+            Some(LockHashCapacity {
+                capacity: lock_hash_capacity.capacity.safe_sub(capacity).ok()?,
+                cells_count: lock_hash_capacity.cells_count.checked_sub(1)?,
+            })
+        });
+    }
+
+    // The capacity is recomputed after the commit if the row is missing, which is the case for
+    // the lock hashes indexed before the capacity is tracked, or if the update overflows, which
+    // means the row is out of sync with the live cells.
+    fn update_lock_hash_capacity<F>(&self, lock_hash: &H256, update: F)
+    where
+        F: FnOnce(&LockHashCapacity) -> Option<LockHashCapacity>,
+    {
+        if self.stale_capacities.borrow().contains(lock_hash) {
+            return;
+        }
+        match self.get_lock_hash_capacity(lock_hash).map(|c| update(&c)) {
+            Some(Some(lock_hash_capacity)) => {
+                self.insert_lock_hash_capacity(lock_hash, &lock_hash_capacity)
+            }
+            Some(None) => {
+                error!(
+                    "lock hash {:#x} capacity is out of sync with the live cells, recompute it",
+                    lock_hash
+                );
+                self.stale_capacities
+                    .borrow_mut()
+                    .insert(lock_hash.to_owned());
+            }
+            None => {
+                debug!("lock hash {:#x} has no capacity, compute it", lock_hash);
+                self.stale_capacities
+                    .borrow_mut()
+                    .insert(lock_hash.to_owned());
+            }
+        }
+    }
+
+    fn generate_type_hash_live_cell(
+        &self,
+        type_hash_index: TypeHashIndex,
+        cell_output: CellOutput,
+    ) {
+        self.insert_type_hash_live_cell(&type_hash_index, &cell_output);
+        self.insert_type_hash_transaction(&type_hash_index, &None);
+
+        let type_hash_cell_output = TypeHashCellOutput {
+            type_hash: type_hash_index.type_hash.clone(),
+            block_number: type_hash_index.block_number,
+            cell_output: Some(cell_output),
+        };
+        self.insert_cell_out_point_type_hash(
+            &type_hash_index.cell_out_point,
+            &type_hash_cell_output,
+        );
+    }
+
+    fn consume_type_hash_live_cell(
+        &self,
+        type_hash_index: TypeHashIndex,
+        consumed_by: TransactionPoint,
+    ) {
+        if let Some(type_hash_cell_output) = self
+            .txn
+            .get(COLUMN_TYPE_HASH_LIVE_CELL, &type_hash_index.to_vec())
+            .expect("indexer db read should be ok")
+            .map(|value| deserialize(&value).expect("deserialize CellOutput should be ok"))
+            .map(|cell_output: CellOutput| TypeHashCellOutput {
+                type_hash: type_hash_index.type_hash.clone(),
+                block_number: type_hash_index.block_number,
+                cell_output: Some(cell_output),
+            })
+        {
+            self.delete_type_hash_live_cell(&type_hash_index);
+            self.insert_type_hash_transaction(&type_hash_index, &Some(consumed_by));
+            self.insert_cell_out_point_type_hash(
+                &type_hash_index.cell_out_point,
+                &type_hash_cell_output,
+            );
+        }
+    }
+
+    fn generate_lock_pattern_live_cell(
+        &self,
+        lock_pattern_index: LockPatternIndex,
+        cell_output: CellOutput,
+    ) {
+        self.insert_lock_pattern_live_cell(&lock_pattern_index, &cell_output);
+        self.insert_lock_pattern_transaction(&lock_pattern_index, &None);
+
+        let lock_pattern_cell_output = LockPatternCellOutput {
+            block_number: lock_pattern_index.block_number,
+            cell_output: Some(cell_output),
+        };
+        self.insert_cell_out_point_lock_pattern(
+            &lock_pattern_index.pattern_hash,
+            &lock_pattern_index.cell_out_point,
+            &lock_pattern_cell_output,
+        );
+    }
+
+    fn consume_lock_pattern_live_cell(
+        &self,
+        lock_pattern_index: LockPatternIndex,
+        consumed_by: TransactionPoint,
+    ) {
+        if let Some(cell_output) = self.get_lock_pattern_live_cell(&lock_pattern_index) {
+            self.delete_lock_pattern_live_cell(&lock_pattern_index);
+            self.insert_lock_pattern_transaction(&lock_pattern_index, &Some(consumed_by));
+            let lock_pattern_cell_output = LockPatternCellOutput {
+                block_number: lock_pattern_index.block_number,
+                cell_output: Some(cell_output),
+            };
+            self.insert_cell_out_point_lock_pattern(
+                &lock_pattern_index.pattern_hash,
+                &lock_pattern_index.cell_out_point,
+                &lock_pattern_cell_output,
+            );
+        }
+    }
+
     fn insert_lock_hash_index_state(&self, lock_hash: &H256, index_state: &LockHashIndexState) {
         self.txn
             .put(
@@ -556,6 +1482,137 @@ impl IndexerStoreTransaction {
             .expect("txn insert COLUMN_CELL_OUT_POINT_LOCK_HASH failed");
     }
 
+    fn insert_lock_hash_capacity(&self, lock_hash: &H256, lock_hash_capacity: &LockHashCapacity) {
+        self.txn
+            .put(
+                COLUMN_LOCK_HASH_CAPACITY,
+                lock_hash.as_bytes(),
+                &serialize(lock_hash_capacity).expect("serialize LockHashCapacity should be ok"),
+            )
+            .expect("txn insert COLUMN_LOCK_HASH_CAPACITY failed");
+    }
+
+    fn insert_type_hash_index_state(&self, type_hash: &H256, index_state: &TypeHashIndexState) {
+        self.txn
+            .put(
+                COLUMN_TYPE_HASH_INDEX_STATE,
+                type_hash.as_bytes(),
+                &serialize(index_state).expect("serialize TypeHashIndexState should be ok"),
+            )
+            .expect("txn insert COLUMN_TYPE_HASH_INDEX_STATE failed");
+    }
+
+    fn insert_type_hash_live_cell(
+        &self,
+        type_hash_index: &TypeHashIndex,
+        cell_output: &CellOutput,
+    ) {
+        self.txn
+            .put(
+                COLUMN_TYPE_HASH_LIVE_CELL,
+                &type_hash_index.to_vec(),
+                &serialize(cell_output).expect("serialize CellOutput should be ok"),
+            )
+            .expect("txn insert COLUMN_TYPE_HASH_LIVE_CELL failed");
+    }
+
+    fn insert_type_hash_transaction(
+        &self,
+        type_hash_index: &TypeHashIndex,
+        consumed_by: &Option<TransactionPoint>,
+    ) {
+        self.txn
+            .put(
+                COLUMN_TYPE_HASH_TRANSACTION,
+                &type_hash_index.to_vec(),
+                &serialize(consumed_by).expect("serialize TransactionPoint should be ok"),
+            )
+            .expect("txn insert COLUMN_TYPE_HASH_TRANSACTION failed");
+    }
+
+    fn insert_cell_out_point_type_hash(
+        &self,
+        cell_out_point: &CellOutPoint,
+        type_hash_cell_output: &TypeHashCellOutput,
+    ) {
+        self.txn
+            .put(
+                COLUMN_CELL_OUT_POINT_TYPE_HASH,
+                &serialize(&cell_out_point).expect("serialize OutPoint should be ok"),
+                &serialize(&type_hash_cell_output)
+                    .expect("serialize TypeHashCellOutput should be ok"),
+            )
+            .expect("txn insert COLUMN_CELL_OUT_POINT_TYPE_HASH failed");
+    }
+
+    fn insert_lock_pattern(&self, pattern_hash: &H256, lock_pattern: &LockPattern) {
+        self.txn
+            .put(
+                COLUMN_LOCK_PATTERN,
+                pattern_hash.as_bytes(),
+                &serialize(lock_pattern).expect("serialize LockPattern should be ok"),
+            )
+            .expect("txn insert COLUMN_LOCK_PATTERN failed");
+    }
+
+    fn insert_lock_pattern_index_state(
+        &self,
+        pattern_hash: &H256,
+        index_state: &LockPatternIndexState,
+    ) {
+        self.txn
+            .put(
+                COLUMN_LOCK_PATTERN_INDEX_STATE,
+                pattern_hash.as_bytes(),
+                &serialize(index_state).expect("serialize LockPatternIndexState should be ok"),
+            )
+            .expect("txn insert COLUMN_LOCK_PATTERN_INDEX_STATE failed");
+    }
+
+    fn insert_lock_pattern_live_cell(
+        &self,
+        lock_pattern_index: &LockPatternIndex,
+        cell_output: &CellOutput,
+    ) {
+        self.txn
+            .put(
+                COLUMN_LOCK_PATTERN_LIVE_CELL,
+                &lock_pattern_index.to_vec(),
+                &serialize(cell_output).expect("serialize CellOutput should be ok"),
+            )
+            .expect("txn insert COLUMN_LOCK_PATTERN_LIVE_CELL failed");
+    }
+
+    fn insert_lock_pattern_transaction(
+        &self,
+        lock_pattern_index: &LockPatternIndex,
+        consumed_by: &Option<TransactionPoint>,
+    ) {
+        self.txn
+            .put(
+                COLUMN_LOCK_PATTERN_TRANSACTION,
+                &lock_pattern_index.to_vec(),
+                &serialize(consumed_by).expect("serialize TransactionPoint should be ok"),
+            )
+            .expect("txn insert COLUMN_LOCK_PATTERN_TRANSACTION failed");
+    }
+
+    fn insert_cell_out_point_lock_pattern(
+        &self,
+        pattern_hash: &H256,
+        cell_out_point: &CellOutPoint,
+        lock_pattern_cell_output: &LockPatternCellOutput,
+    ) {
+        self.txn
+            .put(
+                COLUMN_CELL_OUT_POINT_LOCK_PATTERN,
+                &cell_out_point_lock_pattern_key(pattern_hash, cell_out_point),
+                &serialize(&lock_pattern_cell_output)
+                    .expect("serialize LockPatternCellOutput should be ok"),
+            )
+            .expect("txn insert COLUMN_CELL_OUT_POINT_LOCK_PATTERN failed");
+    }
+
     fn delete_lock_hash_index_state(&self, lock_hash: &H256) {
         self.txn
             .delete(COLUMN_LOCK_HASH_INDEX_STATE, lock_hash.as_bytes())
@@ -583,10 +1640,83 @@ impl IndexerStoreTransaction {
             .expect("txn delete COLUMN_CELL_OUT_POINT_LOCK_HASH failed");
     }
 
-    fn get_lock_hash_cell_output(
-        &self,
-        cell_out_point: &CellOutPoint,
-    ) -> Option<LockHashCellOutput> {
+    fn delete_lock_hash_capacity(&self, lock_hash: &H256) {
+        self.txn
+            .delete(COLUMN_LOCK_HASH_CAPACITY, lock_hash.as_bytes())
+            .expect("txn delete COLUMN_LOCK_HASH_CAPACITY failed");
+    }
+
+    fn delete_type_hash_index_state(&self, type_hash: &H256) {
+        self.txn
+            .delete(COLUMN_TYPE_HASH_INDEX_STATE, type_hash.as_bytes())
+            .expect("txn delete COLUMN_TYPE_HASH_INDEX_STATE failed");
+    }
+
+    fn delete_type_hash_live_cell(&self, type_hash_index: &TypeHashIndex) {
+        self.txn
+            .delete(COLUMN_TYPE_HASH_LIVE_CELL, &type_hash_index.to_vec())
+            .expect("txn delete COLUMN_TYPE_HASH_LIVE_CELL failed");
+    }
+
+    fn delete_type_hash_transaction(&self, type_hash_index: &TypeHashIndex) {
+        self.txn
+            .delete(COLUMN_TYPE_HASH_TRANSACTION, &type_hash_index.to_vec())
+            .expect("txn delete COLUMN_TYPE_HASH_TRANSACTION failed");
+    }
+
+    fn delete_cell_out_point_type_hash(&self, cell_out_point: &CellOutPoint) {
+        self.txn
+            .delete(
+                COLUMN_CELL_OUT_POINT_TYPE_HASH,
+                &serialize(cell_out_point).expect("serialize CellOutPoint should be ok"),
+            )
+            .expect("txn delete COLUMN_CELL_OUT_POINT_TYPE_HASH failed");
+    }
+
+    fn delete_lock_pattern(&self, pattern_hash: &H256) {
+        self.txn
+            .delete(COLUMN_LOCK_PATTERN, pattern_hash.as_bytes())
+            .expect("txn delete COLUMN_LOCK_PATTERN failed");
+    }
+
+    fn delete_lock_pattern_index_state(&self, pattern_hash: &H256) {
+        self.txn
+            .delete(COLUMN_LOCK_PATTERN_INDEX_STATE, pattern_hash.as_bytes())
+            .expect("txn delete COLUMN_LOCK_PATTERN_INDEX_STATE failed");
+    }
+
+    fn delete_lock_pattern_live_cell(&self, lock_pattern_index: &LockPatternIndex) {
+        self.txn
+            .delete(COLUMN_LOCK_PATTERN_LIVE_CELL, &lock_pattern_index.to_vec())
+            .expect("txn delete COLUMN_LOCK_PATTERN_LIVE_CELL failed");
+    }
+
+    fn delete_lock_pattern_transaction(&self, lock_pattern_index: &LockPatternIndex) {
+        self.txn
+            .delete(
+                COLUMN_LOCK_PATTERN_TRANSACTION,
+                &lock_pattern_index.to_vec(),
+            )
+            .expect("txn delete COLUMN_LOCK_PATTERN_TRANSACTION failed");
+    }
+
+    fn delete_cell_out_point_lock_pattern(
+        &self,
+        pattern_hash: &H256,
+        cell_out_point: &CellOutPoint,
+    ) {
+        self.txn
+            .delete(
+                COLUMN_CELL_OUT_POINT_LOCK_PATTERN,
+                &cell_out_point_lock_pattern_key(pattern_hash, cell_out_point),
+            )
+            .expect("txn delete COLUMN_CELL_OUT_POINT_LOCK_PATTERN failed");
+    }
+
+    fn get_lock_hash_cell_output(
+        &self,
+        cell_out_point: &CellOutPoint,
+    ) -> Option<LockHashCellOutput> {
         self.txn
             .get(
                 COLUMN_CELL_OUT_POINT_LOCK_HASH,
@@ -596,6 +1726,59 @@ impl IndexerStoreTransaction {
             .map(|value| deserialize(&value).expect("deserialize LockHashCellOutput should be ok"))
     }
 
+    fn get_lock_hash_live_cell(&self, lock_hash_index: &LockHashIndex) -> Option<CellOutput> {
+        self.txn
+            .get(COLUMN_LOCK_HASH_LIVE_CELL, &lock_hash_index.to_vec())
+            .expect("indexer db read should be ok")
+            .map(|value| deserialize(&value).expect("deserialize CellOutput should be ok"))
+    }
+
+    fn get_lock_hash_capacity(&self, lock_hash: &H256) -> Option<LockHashCapacity> {
+        self.txn
+            .get(COLUMN_LOCK_HASH_CAPACITY, lock_hash.as_bytes())
+            .expect("indexer db read should be ok")
+            .map(|value| deserialize(&value).expect("deserialize LockHashCapacity should be ok"))
+    }
+
+    fn get_type_hash_cell_output(
+        &self,
+        cell_out_point: &CellOutPoint,
+    ) -> Option<TypeHashCellOutput> {
+        self.txn
+            .get(
+                COLUMN_CELL_OUT_POINT_TYPE_HASH,
+                &serialize(cell_out_point).expect("serialize OutPoint should be ok"),
+            )
+            .expect("indexer db read should be ok")
+            .map(|value| deserialize(&value).expect("deserialize TypeHashCellOutput should be ok"))
+    }
+
+    fn get_lock_pattern_live_cell(
+        &self,
+        lock_pattern_index: &LockPatternIndex,
+    ) -> Option<CellOutput> {
+        self.txn
+            .get(COLUMN_LOCK_PATTERN_LIVE_CELL, &lock_pattern_index.to_vec())
+            .expect("indexer db read should be ok")
+            .map(|value| deserialize(&value).expect("deserialize CellOutput should be ok"))
+    }
+
+    fn get_lock_pattern_cell_output(
+        &self,
+        pattern_hash: &H256,
+        cell_out_point: &CellOutPoint,
+    ) -> Option<LockPatternCellOutput> {
+        self.txn
+            .get(
+                COLUMN_CELL_OUT_POINT_LOCK_PATTERN,
+                &cell_out_point_lock_pattern_key(pattern_hash, cell_out_point),
+            )
+            .expect("indexer db read should be ok")
+            .map(|value| {
+                deserialize(&value).expect("deserialize LockPatternCellOutput should be ok")
+            })
+    }
+
     fn commit(self) {
         // only log the error, indexer store commit failure should not causing the thread to panic entirely.
         if let Err(err) = self.txn.commit() {
@@ -604,6 +1787,13 @@ impl IndexerStoreTransaction {
     }
 }
 
+// a cell may match several lock patterns, so the pattern hash is prepended to the out point
+fn cell_out_point_lock_pattern_key(pattern_hash: &H256, cell_out_point: &CellOutPoint) -> Vec<u8> {
+    let mut key = pattern_hash.to_vec();
+    key.extend_from_slice(&serialize(cell_out_point).expect("serialize CellOutPoint should be ok"));
+    key
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -651,6 +1841,9 @@ mod tests {
 
         store.remove_lock_hash(&CODE_HASH_DAO);
         assert_eq!(1, store.get_lock_hash_index_states().len());
+
+        store.compact().unwrap();
+        assert_eq!(1, store.get_lock_hash_index_states().len());
     }
 
     #[test]
@@ -766,38 +1959,410 @@ mod tests {
         chain.process_block(Arc::new(block2), false).unwrap();
         store.sync_index_states();
 
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(2, cells.len());
         assert_eq!(capacity_bytes!(1000), cells[0].cell_output.capacity);
         assert_eq!(capacity_bytes!(3000), cells[1].cell_output.capacity);
 
         // test reverse order
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, true);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, true);
         assert_eq!(2, cells.len());
         assert_eq!(capacity_bytes!(3000), cells[0].cell_output.capacity);
         assert_eq!(capacity_bytes!(1000), cells[1].cell_output.capacity);
 
-        let cells = store.get_live_cells(&script2.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script2.hash(), None, None, 100, false);
         assert_eq!(2, cells.len());
         assert_eq!(capacity_bytes!(2000), cells[0].cell_output.capacity);
         assert_eq!(capacity_bytes!(4000), cells[1].cell_output.capacity);
 
         chain.process_block(Arc::new(block2_fork), false).unwrap();
         store.sync_index_states();
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(1, cells.len());
         assert_eq!(capacity_bytes!(5000), cells[0].cell_output.capacity);
 
-        let cells = store.get_live_cells(&script2.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script2.hash(), None, None, 100, false);
         assert_eq!(1, cells.len());
         assert_eq!(capacity_bytes!(6000), cells[0].cell_output.capacity);
 
-        // remove script1's lock hash should remove its indexed data also
-        store.remove_lock_hash(&script1.hash());
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
-        assert_eq!(0, cells.len());
-        let cells = store.get_live_cells(&script2.hash(), 0, 100, false);
-        assert_eq!(1, cells.len());
+        // remove script1's lock hash should remove its indexed data also
+        store.remove_lock_hash(&script1.hash());
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
+        assert_eq!(0, cells.len());
+        let cells = store.get_live_cells(&script2.hash(), None, None, 100, false);
+        assert_eq!(1, cells.len());
+    }
+
+    #[test]
+    fn get_live_cells_with_cursor() {
+        let (store, chain, shared) = setup("get_live_cells_with_cursor");
+        let script1 = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
+        store.insert_lock_hash(&script1.hash(), None);
+
+        let tx11 = TransactionBuilder::default()
+            .outputs((1..=3).map(|i| {
+                CellOutputBuilder::default()
+                    .capacity(Capacity::bytes(1000 * i).unwrap())
+                    .lock(script1.clone())
+                    .build()
+            }))
+            .outputs_data((1..=3).map(|_| Bytes::new()))
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(1u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        chain.process_block(Arc::new(block1), false).unwrap();
+        store.sync_index_states();
+
+        let cursor_of = |live_cell: &LiveCell| {
+            LockHashIndex::new(
+                script1.hash(),
+                live_cell.created_by.block_number,
+                live_cell.created_by.tx_hash.clone(),
+                live_cell.created_by.index,
+            )
+        };
+
+        let cells = store.get_live_cells(&script1.hash(), None, None, 2, false);
+        assert_eq!(2, cells.len());
+        assert_eq!(capacity_bytes!(1000), cells[0].cell_output.capacity);
+        assert_eq!(capacity_bytes!(2000), cells[1].cell_output.capacity);
+        let cursor = cursor_of(&cells[1]);
+        let cells = store.get_live_cells(&script1.hash(), Some(&cursor), None, 2, false);
+        assert_eq!(1, cells.len());
+        assert_eq!(capacity_bytes!(3000), cells[0].cell_output.capacity);
+        let cursor = cursor_of(&cells[0]);
+        let cells = store.get_live_cells(&script1.hash(), Some(&cursor), None, 2, false);
+        assert_eq!(0, cells.len());
+
+        // test reverse order
+        let cells = store.get_live_cells(&script1.hash(), None, None, 2, true);
+        assert_eq!(2, cells.len());
+        assert_eq!(capacity_bytes!(3000), cells[0].cell_output.capacity);
+        assert_eq!(capacity_bytes!(2000), cells[1].cell_output.capacity);
+        let cursor = cursor_of(&cells[1]);
+        let cells = store.get_live_cells(&script1.hash(), Some(&cursor), None, 2, true);
+        assert_eq!(1, cells.len());
+        assert_eq!(capacity_bytes!(1000), cells[0].cell_output.capacity);
+    }
+
+    #[test]
+    fn get_live_cells_with_filter() {
+        let (store, chain, shared) = setup("get_live_cells_with_filter");
+        let script1 = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
+        let type_script = Script::new(Vec::new(), H256::zero(), ScriptHashType::Data);
+        store.insert_lock_hash(&script1.hash(), None);
+
+        let data = Bytes::from(vec![1, 2, 3]);
+        let tx11 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(2000))
+                    .lock(script1.clone())
+                    .type_(Some(type_script.clone()))
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::from_data(&data)
+                    .capacity(capacity_bytes!(3000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .output_data(Bytes::new())
+            .output_data(data)
+            .build();
+
+        let tx21 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(4000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(1u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        let block2 = BlockBuilder::default()
+            .transaction(tx21.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(2u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        chain.process_block(Arc::new(block1), false).unwrap();
+        chain.process_block(Arc::new(block2), false).unwrap();
+        store.sync_index_states();
+
+        let capacities_of = |filter: LiveCellFilter| {
+            store
+                .get_live_cells(&script1.hash(), None, Some(&filter), 100, false)
+                .into_iter()
+                .map(|live_cell| live_cell.cell_output.capacity)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![
+                capacity_bytes!(1000),
+                capacity_bytes!(2000),
+                capacity_bytes!(3000),
+                capacity_bytes!(4000)
+            ],
+            capacities_of(LiveCellFilter::default())
+        );
+        assert_eq!(
+            vec![capacity_bytes!(4000)],
+            capacities_of(LiveCellFilter {
+                from_block_number: Some(2),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec![
+                capacity_bytes!(1000),
+                capacity_bytes!(2000),
+                capacity_bytes!(3000)
+            ],
+            capacities_of(LiveCellFilter {
+                to_block_number: Some(1),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec![capacity_bytes!(2000)],
+            capacities_of(LiveCellFilter {
+                has_type_script: Some(true),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec![capacity_bytes!(1000), capacity_bytes!(4000)],
+            capacities_of(LiveCellFilter {
+                has_type_script: Some(false),
+                empty_data_only: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            vec![capacity_bytes!(2000), capacity_bytes!(3000)],
+            capacities_of(LiveCellFilter {
+                min_capacity: Some(capacity_bytes!(1500)),
+                max_capacity: Some(capacity_bytes!(3000)),
+                ..Default::default()
+            })
+        );
+
+        // the filtered out cells should not be counted in the page size
+        let cells = store.get_live_cells(
+            &script1.hash(),
+            None,
+            Some(&LiveCellFilter {
+                empty_data_only: true,
+                ..Default::default()
+            }),
+            2,
+            true,
+        );
+        assert_eq!(2, cells.len());
+        assert_eq!(capacity_bytes!(4000), cells[0].cell_output.capacity);
+        assert_eq!(capacity_bytes!(2000), cells[1].cell_output.capacity);
+    }
+
+    #[test]
+    fn get_capacity() {
+        let (store, chain, shared) = setup("get_capacity");
+        let script1 = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
+        let script2 = Script::default();
+        assert!(store.get_capacity(&script1.hash()).is_none());
+        store.insert_lock_hash(&script1.hash(), None);
+        let lock_hash_capacity = store.get_capacity(&script1.hash()).unwrap();
+        assert_eq!(Capacity::zero(), lock_hash_capacity.capacity);
+        assert_eq!(0, lock_hash_capacity.cells_count);
+
+        let tx11 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(2000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .output_data(Bytes::new())
+            .build();
+
+        let tx21 = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new_cell(tx11.hash().to_owned(), 0),
+                0,
+            ))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(900))
+                    .lock(script2.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11)
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(1u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        let block2 = BlockBuilder::default()
+            .transaction(tx21)
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(2u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        let block2_fork = BlockBuilder::default()
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(20u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        chain.process_block(Arc::new(block1), false).unwrap();
+        chain.process_block(Arc::new(block2), false).unwrap();
+        store.sync_index_states();
+        let lock_hash_capacity = store.get_capacity(&script1.hash()).unwrap();
+        assert_eq!(capacity_bytes!(2000), lock_hash_capacity.capacity);
+        assert_eq!(1, lock_hash_capacity.cells_count);
+
+        // the consumed cell should be counted again after fork switching
+        chain.process_block(Arc::new(block2_fork), false).unwrap();
+        store.sync_index_states();
+        let lock_hash_capacity = store.get_capacity(&script1.hash()).unwrap();
+        assert_eq!(capacity_bytes!(3000), lock_hash_capacity.capacity);
+        assert_eq!(2, lock_hash_capacity.cells_count);
+
+        store.remove_lock_hash(&script1.hash());
+        assert!(store.get_capacity(&script1.hash()).is_none());
+    }
+
+    #[test]
+    fn get_capacity_without_capacity_row() {
+        let (store, chain, shared) = setup("get_capacity_without_capacity_row");
+        let script1 = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
+        let script2 = Script::default();
+        store.insert_lock_hash(&script1.hash(), None);
+
+        let tx11 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(2000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .output_data(Bytes::new())
+            .build();
+
+        let tx21 = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new_cell(tx11.hash().to_owned(), 0),
+                0,
+            ))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(900))
+                    .lock(script2.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11)
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(1u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        let block2 = BlockBuilder::default()
+            .transaction(tx21)
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(2u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        chain.process_block(Arc::new(block1), false).unwrap();
+        store.sync_index_states();
+
+        // the lock hashes indexed before the capacity is tracked have no capacity row
+        store.commit_txn(|txn| txn.delete_lock_hash_capacity(&script1.hash()));
+        let lock_hash_capacity = store.get_capacity(&script1.hash()).unwrap();
+        assert_eq!(capacity_bytes!(3000), lock_hash_capacity.capacity);
+        assert_eq!(2, lock_hash_capacity.cells_count);
+
+        // consuming a cell recomputes the missing row instead of underflowing
+        chain.process_block(Arc::new(block2), false).unwrap();
+        store.sync_index_states();
+        let lock_hash_capacity: LockHashCapacity = store
+            .db
+            .get_pinned(COLUMN_LOCK_HASH_CAPACITY, script1.hash().as_bytes())
+            .unwrap()
+            .map(|value| deserialize(&value).unwrap())
+            .expect("capacity row recomputed");
+        assert_eq!(capacity_bytes!(2000), lock_hash_capacity.capacity);
+        assert_eq!(1, lock_hash_capacity.cells_count);
     }
 
     #[test]
@@ -913,25 +2478,25 @@ mod tests {
         chain.process_block(Arc::new(block2), false).unwrap();
         store.sync_index_states();
 
-        let transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx11.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(tx21.hash().to_owned(), transactions[1].created_by.tx_hash);
 
         // test reverse order
-        let transactions = store.get_transactions(&script1.hash(), 0, 100, true);
+        let transactions = store.get_transactions(&script1.hash(), None, 100, true);
         assert_eq!(2, transactions.len());
         assert_eq!(tx21.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(tx11.hash().to_owned(), transactions[1].created_by.tx_hash);
 
-        let transactions = store.get_transactions(&script2.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script2.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx12.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(tx22.hash().to_owned(), transactions[1].created_by.tx_hash);
 
         chain.process_block(Arc::new(block2_fork), false).unwrap();
         store.sync_index_states();
-        let transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx11.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(
@@ -943,16 +2508,16 @@ mod tests {
         );
         assert_eq!(tx31.hash().to_owned(), transactions[1].created_by.tx_hash);
 
-        let transactions = store.get_transactions(&script2.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script2.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx12.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(tx32.hash().to_owned(), transactions[1].created_by.tx_hash);
 
         // remove script1's lock hash should remove its indexed data also
         store.remove_lock_hash(&script1.hash());
-        let transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(0, transactions.len());
-        let transactions = store.get_transactions(&script2.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script2.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
     }
 
@@ -1079,12 +2644,12 @@ mod tests {
 
         store.sync_index_states();
 
-        let transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx11.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(tx21.hash().to_owned(), transactions[1].created_by.tx_hash);
 
-        let transactions = store.get_transactions(&script2.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script2.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx12.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(tx22.hash().to_owned(), transactions[1].created_by.tx_hash);
@@ -1093,7 +2658,7 @@ mod tests {
         chain.process_block(Arc::new(block3), false).unwrap();
 
         store.sync_index_states();
-        let transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx11.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(
@@ -1105,7 +2670,7 @@ mod tests {
         );
         assert_eq!(tx31.hash().to_owned(), transactions[1].created_by.tx_hash);
 
-        let transactions = store.get_transactions(&script2.hash(), 0, 100, false);
+        let transactions = store.get_transactions(&script2.hash(), None, 100, false);
         assert_eq!(2, transactions.len());
         assert_eq!(tx12.hash().to_owned(), transactions[0].created_by.tx_hash);
         assert_eq!(tx32.hash().to_owned(), transactions[1].created_by.tx_hash);
@@ -1117,7 +2682,7 @@ mod tests {
         let script1 = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
         let script2 = Script::default();
         store.insert_lock_hash(&script1.hash(), None);
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(0, cells.len());
 
         let tx11 = TransactionBuilder::default()
@@ -1181,16 +2746,16 @@ mod tests {
 
         chain.process_block(Arc::new(block1), false).unwrap();
         store.sync_index_states();
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(0, cells.len());
-        let cell_transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let cell_transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(2, cell_transactions.len());
 
         chain.process_block(Arc::new(block1_fork), false).unwrap();
         store.sync_index_states();
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(0, cells.len());
-        let cell_transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let cell_transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(0, cell_transactions.len());
     }
 
@@ -1200,7 +2765,7 @@ mod tests {
         let script1 = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
         let script2 = Script::default();
         store.insert_lock_hash(&script1.hash(), None);
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(0, cells.len());
 
         let tx11 = TransactionBuilder::default()
@@ -1274,16 +2839,387 @@ mod tests {
         chain.process_block(Arc::new(block1), false).unwrap();
         chain.process_block(Arc::new(block2), false).unwrap();
         store.sync_index_states();
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(0, cells.len());
-        let cell_transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let cell_transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(2, cell_transactions.len());
 
         chain.process_block(Arc::new(block1_fork), false).unwrap();
         store.sync_index_states();
-        let cells = store.get_live_cells(&script1.hash(), 0, 100, false);
+        let cells = store.get_live_cells(&script1.hash(), None, None, 100, false);
         assert_eq!(0, cells.len());
-        let cell_transactions = store.get_transactions(&script1.hash(), 0, 100, false);
+        let cell_transactions = store.get_transactions(&script1.hash(), None, 100, false);
         assert_eq!(0, cell_transactions.len());
     }
+
+    #[test]
+    fn type_hash_index() {
+        let (store, chain, shared) = setup("type_hash_index");
+        let type_script = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
+        let lock_script = Script::default();
+        store.insert_type_hash(&type_script.hash(), None);
+        assert_eq!(1, store.get_type_hash_index_states().len());
+        assert_eq!(0, store.get_lock_hash_index_states().len());
+
+        let tx11 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000))
+                    .lock(lock_script.clone())
+                    .type_(Some(type_script.clone()))
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(2000))
+                    .lock(lock_script.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .output_data(Bytes::new())
+            .build();
+
+        let tx21 = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new_cell(tx11.hash().to_owned(), 0),
+                0,
+            ))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(900))
+                    .lock(lock_script.clone())
+                    .type_(Some(type_script.clone()))
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(1u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        let block2 = BlockBuilder::default()
+            .transaction(tx21.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(2u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        let block2_fork = BlockBuilder::default()
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(20u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        chain.process_block(Arc::new(block1), false).unwrap();
+        chain.process_block(Arc::new(block2), false).unwrap();
+        store.sync_index_states();
+
+        let cells = store.get_live_cells_by_type_hash(&type_script.hash(), None, None, 100, false);
+        assert_eq!(1, cells.len());
+        assert_eq!(capacity_bytes!(900), cells[0].cell_output.capacity);
+        let transactions =
+            store.get_transactions_by_type_hash(&type_script.hash(), None, 100, false);
+        assert_eq!(2, transactions.len());
+        assert_eq!(tx11.hash().to_owned(), transactions[0].created_by.tx_hash);
+        assert_eq!(
+            Some(tx21.hash().to_owned()),
+            transactions[0]
+                .consumed_by
+                .as_ref()
+                .map(|transaction_point| transaction_point.tx_hash.clone())
+        );
+        assert_eq!(tx21.hash().to_owned(), transactions[1].created_by.tx_hash);
+
+        // the consumed cell should be live again after fork switching
+        chain.process_block(Arc::new(block2_fork), false).unwrap();
+        store.sync_index_states();
+        let cells = store.get_live_cells_by_type_hash(&type_script.hash(), None, None, 100, false);
+        assert_eq!(1, cells.len());
+        assert_eq!(capacity_bytes!(1000), cells[0].cell_output.capacity);
+        let transactions =
+            store.get_transactions_by_type_hash(&type_script.hash(), None, 100, false);
+        assert_eq!(1, transactions.len());
+        assert!(transactions[0].consumed_by.is_none());
+
+        store.remove_type_hash(&type_script.hash());
+        assert_eq!(0, store.get_type_hash_index_states().len());
+        let cells = store.get_live_cells_by_type_hash(&type_script.hash(), None, None, 100, false);
+        assert_eq!(0, cells.len());
+    }
+
+    #[test]
+    fn lock_pattern_index() {
+        let (store, chain, shared) = setup("lock_pattern_index");
+        let lock_script1 = Script::new(
+            vec![Bytes::from(vec![1, 2, 3])],
+            CODE_HASH_DAO,
+            ScriptHashType::Data,
+        );
+        let lock_script2 = Script::new(
+            vec![Bytes::from(vec![1, 2]), Bytes::from(vec![4])],
+            CODE_HASH_DAO,
+            ScriptHashType::Data,
+        );
+        let lock_script3 = Script::new(
+            vec![Bytes::from(vec![1, 2, 3])],
+            CODE_HASH_DAO,
+            ScriptHashType::Type,
+        );
+        let lock_pattern1 = LockPattern {
+            code_hash: CODE_HASH_DAO,
+            hash_type: ScriptHashType::Data,
+            args_prefix: Bytes::from(vec![1, 2]),
+        };
+        let lock_pattern2 = LockPattern {
+            code_hash: CODE_HASH_DAO,
+            hash_type: ScriptHashType::Data,
+            args_prefix: Bytes::from(vec![1, 2, 3]),
+        };
+        store.insert_lock_pattern(&lock_pattern1, None);
+        store.insert_lock_pattern(&lock_pattern2, None);
+        assert_eq!(2, store.get_lock_pattern_index_states().len());
+        assert_eq!(2, store.get_lock_patterns().len());
+
+        let tx11 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000))
+                    .lock(lock_script1.clone())
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(2000))
+                    .lock(lock_script2.clone())
+                    .build(),
+            )
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(3000))
+                    .lock(lock_script3.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .output_data(Bytes::new())
+            .output_data(Bytes::new())
+            .build();
+
+        let tx21 = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new_cell(tx11.hash().to_owned(), 0),
+                0,
+            ))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(900))
+                    .lock(lock_script2.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(1u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        let block2 = BlockBuilder::default()
+            .transaction(tx21.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(2u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        let block2_fork = BlockBuilder::default()
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(20u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        chain.process_block(Arc::new(block1), false).unwrap();
+        chain.process_block(Arc::new(block2), false).unwrap();
+        store.sync_index_states();
+
+        let cells =
+            store.get_live_cells_by_lock_pattern(&lock_pattern1.hash(), None, None, 100, false);
+        assert_eq!(2, cells.len());
+        assert_eq!(capacity_bytes!(2000), cells[0].cell_output.capacity);
+        assert_eq!(capacity_bytes!(900), cells[1].cell_output.capacity);
+        let transactions =
+            store.get_transactions_by_lock_pattern(&lock_pattern1.hash(), None, 100, false);
+        assert_eq!(3, transactions.len());
+        assert_eq!(
+            Some(tx21.hash().to_owned()),
+            transactions[0]
+                .consumed_by
+                .as_ref()
+                .map(|transaction_point| transaction_point.tx_hash.clone())
+        );
+
+        let cells =
+            store.get_live_cells_by_lock_pattern(&lock_pattern2.hash(), None, None, 100, false);
+        assert_eq!(0, cells.len());
+        let transactions =
+            store.get_transactions_by_lock_pattern(&lock_pattern2.hash(), None, 100, false);
+        assert_eq!(1, transactions.len());
+
+        // the consumed cell should be live again after fork switching
+        chain.process_block(Arc::new(block2_fork), false).unwrap();
+        store.sync_index_states();
+        let cells =
+            store.get_live_cells_by_lock_pattern(&lock_pattern1.hash(), None, None, 100, false);
+        assert_eq!(2, cells.len());
+        assert_eq!(capacity_bytes!(1000), cells[0].cell_output.capacity);
+        assert_eq!(capacity_bytes!(2000), cells[1].cell_output.capacity);
+        let cells =
+            store.get_live_cells_by_lock_pattern(&lock_pattern2.hash(), None, None, 100, false);
+        assert_eq!(1, cells.len());
+        assert_eq!(capacity_bytes!(1000), cells[0].cell_output.capacity);
+
+        // removing a lock pattern should not affect the others matching the same cells
+        store.remove_lock_pattern(&lock_pattern1.hash());
+        assert_eq!(1, store.get_lock_pattern_index_states().len());
+        let cells =
+            store.get_live_cells_by_lock_pattern(&lock_pattern1.hash(), None, None, 100, false);
+        assert_eq!(0, cells.len());
+        let cells =
+            store.get_live_cells_by_lock_pattern(&lock_pattern2.hash(), None, None, 100, false);
+        assert_eq!(1, cells.len());
+    }
+    #[test]
+    fn subscribe_events() {
+        let (store, chain, shared) = setup("subscribe_events");
+        let script1 = Script::new(Vec::new(), CODE_HASH_DAO, ScriptHashType::Data);
+        let script2 = Script::default();
+        store.insert_lock_hash(&script1.hash(), None);
+        let receiver = store.subscribe("test");
+
+        let tx11 = TransactionBuilder::default()
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(1000))
+                    .lock(script1.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .build();
+
+        let tx21 = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new_cell(tx11.hash().to_owned(), 0),
+                0,
+            ))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(900))
+                    .lock(script2.clone())
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .build();
+
+        let block1 = BlockBuilder::default()
+            .transaction(tx11.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(1u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        let block2 = BlockBuilder::default()
+            .transaction(tx21.clone())
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(2u64))
+                    .number(2)
+                    .parent_hash(block1.header().hash().to_owned()),
+            )
+            .build();
+
+        let block1_fork = BlockBuilder::default()
+            .header_builder(
+                HeaderBuilder::default()
+                    .difficulty(U256::from(20u64))
+                    .number(1)
+                    .parent_hash(shared.genesis_hash().to_owned()),
+            )
+            .build();
+
+        chain.process_block(Arc::new(block1), false).unwrap();
+        chain.process_block(Arc::new(block2), false).unwrap();
+        store.sync_index_states();
+        let events: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(2, events.len());
+        match &events[0] {
+            IndexerEvent::CellCreated {
+                lock_hash,
+                live_cell,
+            } => {
+                assert_eq!(&script1.hash(), lock_hash);
+                assert_eq!(1, live_cell.created_by.block_number);
+                assert_eq!(tx11.hash(), &live_cell.created_by.tx_hash);
+                assert_eq!(capacity_bytes!(1000), live_cell.cell_output.capacity);
+            }
+            _ => panic!("expect cell created event"),
+        }
+        match &events[1] {
+            IndexerEvent::CellConsumed {
+                lock_hash,
+                created_by,
+                consumed_by,
+            } => {
+                assert_eq!(&script1.hash(), lock_hash);
+                assert_eq!(tx11.hash(), &created_by.tx_hash);
+                assert_eq!(2, consumed_by.block_number);
+                assert_eq!(tx21.hash(), &consumed_by.tx_hash);
+            }
+            _ => panic!("expect cell consumed event"),
+        }
+
+        chain.process_block(Arc::new(block1_fork), false).unwrap();
+        store.sync_index_states();
+        let events: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(1, events.len());
+        match &events[0] {
+            IndexerEvent::Rollback {
+                lock_hash,
+                block_number,
+                block_hash,
+            } => {
+                assert_eq!(&script1.hash(), lock_hash);
+                assert_eq!(0, *block_number);
+                assert_eq!(shared.genesis_hash(), block_hash);
+            }
+            _ => panic!("expect rollback event"),
+        }
+
+        store.unsubscribe("test");
+        assert!(receiver.recv().is_err());
+    }
 }
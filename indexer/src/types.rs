@@ -1,8 +1,12 @@
+use ckb_core::script::{Script, ScriptHashType};
 use ckb_core::transaction::{CellOutPoint, CellOutput};
-use ckb_core::BlockNumber;
+use ckb_core::{BlockNumber, Bytes, Capacity};
+use ckb_hash::new_blake2b;
 use ckb_jsonrpc_types::{
     BlockNumber as JsonBlockNumber, CellTransaction as JsonCellTransaction,
-    LiveCell as JsonLiveCell, TransactionPoint as JsonTransactionPoint, Unsigned,
+    IndexerEvent as JsonIndexerEvent, JsonBytes, LiveCell as JsonLiveCell,
+    LiveCellFilter as JsonLiveCellFilter, LockPattern as JsonLockPattern,
+    TransactionPoint as JsonTransactionPoint, Unsigned,
 };
 use numext_fixed_hash::H256;
 use serde_derive::{Deserialize, Serialize};
@@ -14,6 +18,19 @@ pub struct LockHashIndex {
     pub cell_out_point: CellOutPoint,
 }
 
+pub struct TypeHashIndex {
+    pub type_hash: H256,
+    pub block_number: BlockNumber,
+    pub cell_out_point: CellOutPoint,
+}
+
+pub struct LockPatternIndex {
+    pub pattern_hash: H256,
+    pub block_number: BlockNumber,
+    pub cell_out_point: CellOutPoint,
+}
+
+#[derive(Clone)]
 pub struct LiveCell {
     pub created_by: TransactionPoint,
     pub cell_output: CellOutput,
@@ -24,7 +41,37 @@ pub struct CellTransaction {
     pub consumed_by: Option<TransactionPoint>,
 }
 
-#[derive(Serialize, Deserialize)]
+// Published to the subscribers when the index of a lock hash is synced with the chain store
+#[derive(Clone)]
+pub enum IndexerEvent {
+    CellCreated {
+        lock_hash: H256,
+        live_cell: LiveCell,
+    },
+    CellConsumed {
+        lock_hash: H256,
+        created_by: TransactionPoint,
+        consumed_by: TransactionPoint,
+    },
+    // the index is rolled back to the block due to fork, the events after it are reverted
+    Rollback {
+        lock_hash: H256,
+        block_number: BlockNumber,
+        block_hash: H256,
+    },
+}
+
+impl IndexerEvent {
+    pub fn lock_hash(&self) -> &H256 {
+        match self {
+            IndexerEvent::CellCreated { lock_hash, .. } => lock_hash,
+            IndexerEvent::CellConsumed { lock_hash, .. } => lock_hash,
+            IndexerEvent::Rollback { lock_hash, .. } => lock_hash,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct TransactionPoint {
     pub block_number: BlockNumber,
     pub tx_hash: H256,
@@ -40,12 +87,75 @@ pub struct LockHashCellOutput {
     pub cell_output: Option<CellOutput>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TypeHashCellOutput {
+    pub type_hash: H256,
+    pub block_number: BlockNumber,
+    // Cache the `CellOutput` when `LiveCell` is deleted, it's required for fork switching.
+    pub cell_output: Option<CellOutput>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LockPatternCellOutput {
+    pub block_number: BlockNumber,
+    // Cache the `CellOutput` when `LiveCell` is deleted, it's required for fork switching.
+    pub cell_output: Option<CellOutput>,
+}
+
+// Matches the lock scripts which have the same `code_hash` and `hash_type`, and whose args
+// (concatenated in order, the same as the script hash calculation) start with `args_prefix`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockPattern {
+    pub code_hash: H256,
+    pub hash_type: ScriptHashType,
+    pub args_prefix: Bytes,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LockHashIndexState {
     pub block_number: BlockNumber,
     pub block_hash: H256,
 }
 
+pub type TypeHashIndexState = LockHashIndexState;
+
+pub type LockPatternIndexState = LockHashIndexState;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LockHashCapacity {
+    pub capacity: Capacity,
+    pub cells_count: u64,
+}
+
+// Conditions of live cells query, all of them are optional and the block number range is inclusive
+#[derive(Debug, Clone, Default)]
+pub struct LiveCellFilter {
+    pub from_block_number: Option<BlockNumber>,
+    pub to_block_number: Option<BlockNumber>,
+    pub has_type_script: Option<bool>,
+    pub empty_data_only: bool,
+    pub min_capacity: Option<Capacity>,
+    pub max_capacity: Option<Capacity>,
+}
+
+impl LiveCellFilter {
+    pub fn matches(&self, block_number: BlockNumber, cell_output: &CellOutput) -> bool {
+        self.from_block_number
+            .map_or(true, |from| block_number >= from)
+            && self.to_block_number.map_or(true, |to| block_number <= to)
+            && self.has_type_script.map_or(true, |has_type_script| {
+                cell_output.type_.is_some() == has_type_script
+            })
+            && (!self.empty_data_only || cell_output.data_hash == H256::zero())
+            && self
+                .min_capacity
+                .map_or(true, |min| cell_output.capacity >= min)
+            && self
+                .max_capacity
+                .map_or(true, |max| cell_output.capacity <= max)
+    }
+}
+
 impl LockHashIndex {
     pub fn new(lock_hash: H256, block_number: BlockNumber, tx_hash: H256, index: u32) -> Self {
         LockHashIndex {
@@ -79,6 +189,96 @@ impl LockHashIndex {
     }
 }
 
+impl TypeHashIndex {
+    pub fn new(type_hash: H256, block_number: BlockNumber, tx_hash: H256, index: u32) -> Self {
+        TypeHashIndex {
+            type_hash,
+            block_number,
+            cell_out_point: CellOutPoint { tx_hash, index },
+        }
+    }
+
+    pub fn from_slice(slice: &[u8]) -> Self {
+        debug_assert!(slice.len() == 76);
+        let type_hash = H256::from_slice(&slice[0..32]).unwrap();
+        let block_number = BlockNumber::from_be_bytes(slice[32..40].try_into().unwrap());
+        let tx_hash = H256::from_slice(&slice[40..72]).unwrap();
+        let index = u32::from_be_bytes(slice[72..76].try_into().unwrap());
+
+        Self {
+            type_hash,
+            block_number,
+            cell_out_point: CellOutPoint { tx_hash, index },
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(76);
+        result.extend_from_slice(self.type_hash.as_bytes());
+        result.extend_from_slice(&self.block_number.to_be_bytes());
+        result.extend_from_slice(self.cell_out_point.tx_hash.as_bytes());
+        result.extend_from_slice(&self.cell_out_point.index.to_be_bytes());
+        result
+    }
+}
+
+impl LockPatternIndex {
+    pub fn new(pattern_hash: H256, block_number: BlockNumber, tx_hash: H256, index: u32) -> Self {
+        LockPatternIndex {
+            pattern_hash,
+            block_number,
+            cell_out_point: CellOutPoint { tx_hash, index },
+        }
+    }
+
+    pub fn from_slice(slice: &[u8]) -> Self {
+        debug_assert!(slice.len() == 76);
+        let pattern_hash = H256::from_slice(&slice[0..32]).unwrap();
+        let block_number = BlockNumber::from_be_bytes(slice[32..40].try_into().unwrap());
+        let tx_hash = H256::from_slice(&slice[40..72]).unwrap();
+        let index = u32::from_be_bytes(slice[72..76].try_into().unwrap());
+
+        Self {
+            pattern_hash,
+            block_number,
+            cell_out_point: CellOutPoint { tx_hash, index },
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(76);
+        result.extend_from_slice(self.pattern_hash.as_bytes());
+        result.extend_from_slice(&self.block_number.to_be_bytes());
+        result.extend_from_slice(self.cell_out_point.tx_hash.as_bytes());
+        result.extend_from_slice(&self.cell_out_point.index.to_be_bytes());
+        result
+    }
+}
+
+impl LockPattern {
+    pub fn hash(&self) -> H256 {
+        let mut ret = [0u8; 32];
+        let mut blake2b = new_blake2b();
+        blake2b.update(self.code_hash.as_bytes());
+        blake2b.update(&[self.hash_type.to_owned() as u8]);
+        blake2b.update(&(self.args_prefix.len() as u64).to_le_bytes());
+        blake2b.update(&self.args_prefix);
+        blake2b.finalize(&mut ret);
+        ret.into()
+    }
+
+    pub fn matches(&self, lock: &Script) -> bool {
+        lock.code_hash == self.code_hash
+            && lock.hash_type == self.hash_type
+            && lock
+                .args
+                .iter()
+                .flat_map(|arg| arg.iter())
+                .take(self.args_prefix.len())
+                .eq(self.args_prefix.iter())
+    }
+}
+
 impl From<LockHashIndex> for TransactionPoint {
     fn from(lock_hash_index: LockHashIndex) -> Self {
         TransactionPoint {
@@ -89,6 +289,77 @@ impl From<LockHashIndex> for TransactionPoint {
     }
 }
 
+impl From<TypeHashIndex> for TransactionPoint {
+    fn from(type_hash_index: TypeHashIndex) -> Self {
+        TransactionPoint {
+            block_number: type_hash_index.block_number,
+            tx_hash: type_hash_index.cell_out_point.tx_hash,
+            index: type_hash_index.cell_out_point.index,
+        }
+    }
+}
+
+impl From<LockPatternIndex> for TransactionPoint {
+    fn from(lock_pattern_index: LockPatternIndex) -> Self {
+        TransactionPoint {
+            block_number: lock_pattern_index.block_number,
+            tx_hash: lock_pattern_index.cell_out_point.tx_hash,
+            index: lock_pattern_index.cell_out_point.index,
+        }
+    }
+}
+
+impl From<JsonLockPattern> for LockPattern {
+    fn from(json: JsonLockPattern) -> LockPattern {
+        let JsonLockPattern {
+            code_hash,
+            hash_type,
+            args_prefix,
+        } = json;
+        LockPattern {
+            code_hash,
+            hash_type: hash_type.into(),
+            args_prefix: args_prefix.into_bytes(),
+        }
+    }
+}
+
+impl From<LockPattern> for JsonLockPattern {
+    fn from(lock_pattern: LockPattern) -> JsonLockPattern {
+        let LockPattern {
+            code_hash,
+            hash_type,
+            args_prefix,
+        } = lock_pattern;
+        JsonLockPattern {
+            code_hash,
+            hash_type: hash_type.into(),
+            args_prefix: JsonBytes::from_bytes(args_prefix),
+        }
+    }
+}
+
+impl From<JsonLiveCellFilter> for LiveCellFilter {
+    fn from(json: JsonLiveCellFilter) -> LiveCellFilter {
+        let JsonLiveCellFilter {
+            from_block_number,
+            to_block_number,
+            has_type_script,
+            empty_data_only,
+            min_capacity,
+            max_capacity,
+        } = json;
+        LiveCellFilter {
+            from_block_number: from_block_number.map(|number| number.0),
+            to_block_number: to_block_number.map(|number| number.0),
+            has_type_script,
+            empty_data_only: empty_data_only.unwrap_or_default(),
+            min_capacity: min_capacity.map(|capacity| capacity.0),
+            max_capacity: max_capacity.map(|capacity| capacity.0),
+        }
+    }
+}
+
 impl From<LiveCell> for JsonLiveCell {
     fn from(live_cell: LiveCell) -> JsonLiveCell {
         let LiveCell {
@@ -129,3 +400,35 @@ impl From<TransactionPoint> for JsonTransactionPoint {
         }
     }
 }
+
+impl From<IndexerEvent> for JsonIndexerEvent {
+    fn from(event: IndexerEvent) -> JsonIndexerEvent {
+        match event {
+            IndexerEvent::CellCreated {
+                lock_hash,
+                live_cell,
+            } => JsonIndexerEvent::CellCreated {
+                lock_hash,
+                live_cell: live_cell.into(),
+            },
+            IndexerEvent::CellConsumed {
+                lock_hash,
+                created_by,
+                consumed_by,
+            } => JsonIndexerEvent::CellConsumed {
+                lock_hash,
+                created_by: created_by.into(),
+                consumed_by: consumed_by.into(),
+            },
+            IndexerEvent::Rollback {
+                lock_hash,
+                block_number,
+                block_hash,
+            } => JsonIndexerEvent::Rollback {
+                lock_hash,
+                block_number: JsonBlockNumber(block_number),
+                block_hash,
+            },
+        }
+    }
+}
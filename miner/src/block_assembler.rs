@@ -33,12 +33,15 @@ use std::cmp;
 use std::collections::HashSet;
 use std::sync::{atomic::AtomicU64, atomic::AtomicUsize, atomic::Ordering, Arc};
 use std::thread;
+use std::time::{Duration, Instant};
 
 type BlockTemplateParams = (Option<u64>, Option<u64>, Option<Version>);
 type BlockTemplateResult = Result<BlockTemplate, FailureError>;
 const BLOCK_ASSEMBLER_SUBSCRIBER: &str = "block_assembler";
 const BLOCK_TEMPLATE_TIMEOUT: u64 = 3000;
 const TEMPLATE_CACHE_SIZE: usize = 10;
+// The interval to check whether the template is changed when long polling
+const LONG_POLL_INTERVAL: Duration = Duration::from_millis(100);
 
 struct TemplateCache {
     pub time: u64,
@@ -87,6 +90,27 @@ impl BlockAssemblerController {
         )
         .expect("get_block_template() failed")
     }
+
+    // Waits until the template is changed from the one identified by `long_poll_id`, or the
+    // timeout elapses. The cached template is reused for `BLOCK_TEMPLATE_TIMEOUT` if the tip is
+    // not changed, so the txs entering the pool may take as long to be noticed.
+    pub fn wait_block_template(
+        &self,
+        bytes_limit: Option<u64>,
+        proposals_limit: Option<u64>,
+        max_version: Option<Version>,
+        long_poll_id: &str,
+        timeout: Duration,
+    ) -> BlockTemplateResult {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let template = self.get_block_template(bytes_limit, proposals_limit, max_version)?;
+            if template.long_poll_id != long_poll_id || Instant::now() >= deadline {
+                return Ok(template);
+            }
+            thread::sleep(LONG_POLL_INTERVAL);
+        }
+    }
 }
 
 pub struct BlockAssembler {
@@ -333,6 +357,8 @@ impl BlockAssembler {
         let txs_size_limit =
             self.calculate_txs_size_limit(cellbase_size, bytes_limit, &uncles, &proposals)?;
 
+        // The entries are packed in the order of the fee rate, and a tx always comes after its
+        // ancestors, so the txs are resolved in order below.
         let (entries, size, cycles) = chain_state.get_proposed_txs(txs_size_limit, cycles_limit);
         if !entries.is_empty() {
             info!(
@@ -397,6 +423,10 @@ impl BlockAssembler {
             proposals: proposals.into_iter().map(Into::into).collect(),
             cellbase: Self::transform_cellbase(&cellbase, None),
             work_id: Unsigned(self.work_id.fetch_add(1, Ordering::SeqCst) as u64),
+            long_poll_id: format!(
+                "{:x}-{:x}-{:x}",
+                tip_hash, last_txs_updated_at, last_uncles_updated_at
+            ),
             dao: JsonBytes::from_bytes(dao),
         };
 
@@ -595,6 +625,54 @@ mod tests {
         assert!(block_verify.verify(&block).is_ok());
     }
 
+    #[test]
+    fn test_long_poll_block_template() {
+        let (chain_controller, shared, notify) = start_chain(None, None);
+        let config = BlockAssemblerConfig {
+            code_hash: H256::zero(),
+            args: vec![],
+            data: JsonBytes::default(),
+            hash_type: ScriptHashType::Data,
+        };
+        let block_assembler = setup_block_assembler(shared.clone(), config);
+        let block_assembler_controller = block_assembler.start(Some("test"), &notify);
+
+        let template = block_assembler_controller
+            .get_block_template(None, None, None)
+            .unwrap();
+        // nothing is changed until the timeout
+        let unchanged = block_assembler_controller
+            .wait_block_template(
+                None,
+                None,
+                None,
+                &template.long_poll_id,
+                Duration::from_millis(200),
+            )
+            .unwrap();
+        assert_eq!(unchanged.long_poll_id, template.long_poll_id);
+
+        let genesis = shared
+            .store()
+            .get_block_header(&shared.store().get_block_hash(0).unwrap())
+            .unwrap();
+        let epoch = shared.consensus().genesis_epoch_ext().clone();
+        chain_controller
+            .process_block(Arc::new(gen_block(&genesis, 10, &epoch)), false)
+            .unwrap();
+        let changed = block_assembler_controller
+            .wait_block_template(
+                None,
+                None,
+                None,
+                &template.long_poll_id,
+                Duration::from_secs(60),
+            )
+            .unwrap();
+        assert_ne!(changed.long_poll_id, template.long_poll_id);
+        assert_eq!(changed.number.0, 2);
+    }
+
     fn gen_block(parent_header: &Header, nonce: u64, epoch: &EpochExt) -> Block {
         let number = parent_header.number() + 1;
         let cellbase = create_cellbase(number, epoch);
@@ -10,7 +10,7 @@ use crossbeam_channel::Sender;
 use failure::Error;
 use futures::sync::{mpsc, oneshot};
 use hyper::error::Error as HyperError;
-use hyper::header::{HeaderValue, CONTENT_TYPE};
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
 use hyper::rt::{self, Future, Stream};
 use hyper::Uri;
 use hyper::{Body, Chunk, Client as HttpClient, Method, Request};
@@ -38,7 +38,7 @@ pub struct Rpc {
 }
 
 impl Rpc {
-    pub fn new(url: Uri) -> Rpc {
+    pub fn new(url: Uri, authorization: Option<HeaderValue>) -> Rpc {
         let (sender, receiver) = mpsc::channel(65_535);
         let (stop, stop_rx) = oneshot::channel::<()>();
 
@@ -54,6 +54,9 @@ impl Rpc {
                 *req.uri_mut() = req_url;
                 req.headers_mut()
                     .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                if let Some(authorization) = authorization.clone() {
+                    req.headers_mut().insert(AUTHORIZATION, authorization);
+                }
 
                 let request = client
                     .request(req)
@@ -106,6 +109,7 @@ impl Drop for Rpc {
 #[derive(Debug, Clone)]
 pub struct Client {
     pub current_work_id: Option<u64>,
+    pub current_long_poll_id: Option<String>,
     pub new_work_tx: Sender<Work>,
     pub config: ClientConfig,
     pub rpc: Rpc,
@@ -114,10 +118,14 @@ pub struct Client {
 impl Client {
     pub fn new(new_work_tx: Sender<Work>, config: ClientConfig) -> Client {
         let uri: Uri = config.rpc_url.parse().expect("valid rpc url");
+        let authorization = config.rpc_authorization.as_ref().map(|authorization| {
+            HeaderValue::from_str(authorization).expect("valid rpc authorization")
+        });
 
         Client {
             current_work_id: None,
-            rpc: Rpc::new(uri),
+            current_long_poll_id: None,
+            rpc: Rpc::new(uri, authorization),
             new_work_tx,
             config,
         }
@@ -155,23 +163,33 @@ impl Client {
         }
     }
 
+    // Long polls the block template, the node responds when the template is changed or the long
+    // polling times out. Retries after `poll_interval` on errors.
     pub fn poll_block_template(&mut self) {
         loop {
             debug!("poll block template...");
-            self.try_update_block_template();
-            thread::sleep(time::Duration::from_millis(self.config.poll_interval));
+            let long_poll_id = self.current_long_poll_id.clone();
+            if !self.update_block_template(long_poll_id) {
+                thread::sleep(time::Duration::from_millis(self.config.poll_interval));
+            }
         }
     }
 
     pub fn try_update_block_template(&mut self) {
-        match self.get_block_template().wait() {
+        self.update_block_template(None);
+    }
+
+    fn update_block_template(&mut self, long_poll_id: Option<String>) -> bool {
+        match self.get_block_template(long_poll_id).wait() {
             Ok(block_template) => {
+                self.current_long_poll_id = Some(block_template.long_poll_id.clone());
                 if self.current_work_id != Some(block_template.work_id.0) {
                     self.current_work_id = Some(block_template.work_id.0);
                     if let Err(e) = self.notify_new_work(block_template) {
                         error!("notify_new_block error: {:?}", e);
                     }
                 }
+                true
             }
             Err(ref err) => {
                 let is_method_not_found = if let RpcError::Fail(RpcFail { code, .. }) = err {
@@ -190,13 +208,20 @@ impl Client {
                 } else {
                     error!("rpc call get_block_template error: {:?}", err);
                 }
+                false
             }
         }
     }
 
-    fn get_block_template(&self) -> impl Future<Item = BlockTemplate, Error = RpcError> {
+    fn get_block_template(
+        &self,
+        long_poll_id: Option<String>,
+    ) -> impl Future<Item = BlockTemplate, Error = RpcError> {
         let method = "get_block_template".to_owned();
-        let params = vec![];
+        let params = match long_poll_id {
+            Some(long_poll_id) => vec![Value::Null, Value::Null, Value::Null, json!(long_poll_id)],
+            None => vec![],
+        };
 
         self.rpc.request(method, params).and_then(parse_response)
     }
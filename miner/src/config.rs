@@ -1,3 +1,4 @@
+use crate::stratum::STRATUM_SCHEME;
 use crate::worker::{CuckooSimpleConfig, DummyConfig};
 use ckb_core::script::ScriptHashType;
 use ckb_jsonrpc_types::JsonBytes;
@@ -15,6 +16,19 @@ pub struct ClientConfig {
     pub rpc_url: String,
     pub poll_interval: u64,
     pub block_on_submit: bool,
+    // The `Authorization` header sent to the node, such as `Bearer <token>`
+    pub rpc_authorization: Option<String>,
+}
+
+impl ClientConfig {
+    // The address of the stratum server if `rpc_url` is like `stratum+tcp://127.0.0.1:18116`
+    pub fn stratum_address(&self) -> Option<&str> {
+        if self.rpc_url.starts_with(STRATUM_SCHEME) {
+            Some(&self.rpc_url[STRATUM_SCHEME.len()..])
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -5,6 +5,7 @@ mod client;
 mod config;
 mod error;
 mod miner;
+mod stratum;
 mod worker;
 
 pub use crate::block_assembler::{BlockAssembler, BlockAssemblerController};
@@ -12,14 +13,46 @@ pub use crate::client::Client;
 pub use crate::config::{BlockAssemblerConfig, ClientConfig, MinerConfig, WorkerConfig};
 pub use crate::error::Error;
 pub use crate::miner::Miner;
+pub use crate::stratum::{
+    StratumClient, StratumConnection, STRATUM_NOTIFY, STRATUM_SCHEME, STRATUM_SUBMIT,
+    STRATUM_SUBSCRIBE,
+};
 
 use ckb_core::block::{Block, BlockBuilder};
 use ckb_jsonrpc_types::BlockTemplate;
+use numext_fixed_hash::H256;
+use numext_fixed_uint::U256;
 use std::convert::From;
 
-pub struct Work {
-    work_id: u64,
-    block: Block,
+// A block template polled through RPC, or a job pushed by the stratum server
+pub enum Work {
+    Template {
+        work_id: u64,
+        block: Block,
+    },
+    Job {
+        job_id: String,
+        pow_hash: H256,
+        // the share difficulty, lower than the block difficulty
+        difficulty: U256,
+        connection: StratumConnection,
+    },
+}
+
+impl Work {
+    pub fn pow_hash(&self) -> H256 {
+        match self {
+            Work::Template { block, .. } => block.header().pow_hash(),
+            Work::Job { pow_hash, .. } => pow_hash.clone(),
+        }
+    }
+
+    pub fn difficulty(&self) -> U256 {
+        match self {
+            Work::Template { block, .. } => block.header().difficulty().clone(),
+            Work::Job { difficulty, .. } => difficulty.clone(),
+        }
+    }
 }
 
 impl From<BlockTemplate> for Work {
@@ -28,7 +61,7 @@ impl From<BlockTemplate> for Work {
         let block: BlockBuilder = block_template.into();
         let block = block.build();
 
-        Work {
+        Work::Template {
             work_id: work_id.0,
             block,
         }
@@ -18,20 +18,22 @@ const WORK_CACHE_SIZE: usize = 32;
 
 pub struct Miner {
     pub pow: Arc<dyn PowEngine>,
-    pub client: Client,
+    // None if the works are pushed by the stratum server
+    pub client: Option<Client>,
     pub works: Mutex<LruCache<H256, Work>>,
     pub worker_controllers: Vec<WorkerController>,
     pub work_rx: Receiver<Work>,
     pub seal_rx: Receiver<(H256, Seal)>,
     pub pb: ProgressBar,
     pub seals_found: u64,
+    pub shares_found: u64,
     pub stderr_is_tty: bool,
 }
 
 impl Miner {
     pub fn new(
         pow: Arc<dyn PowEngine>,
-        client: Client,
+        client: Option<Client>,
         work_rx: Receiver<Work>,
         workers: &[WorkerConfig],
     ) -> Miner {
@@ -55,6 +57,7 @@ impl Miner {
         Miner {
             works: Mutex::new(LruCache::new(WORK_CACHE_SIZE)),
             seals_found: 0,
+            shares_found: 0,
             pow,
             client,
             worker_controllers,
@@ -72,7 +75,7 @@ impl Miner {
             select! {
                 recv(self.work_rx) -> msg => match msg {
                     Ok(work) => {
-                        let pow_hash = work.block.header().pow_hash();
+                        let pow_hash = work.pow_hash();
                         self.works.lock().insert(pow_hash.clone(), work);
                         self.notify_workers(WorkerMessage::NewWork(pow_hash));
                     },
@@ -93,14 +96,22 @@ impl Miner {
     }
 
     fn check_seal(&mut self, pow_hash: H256, seal: Seal) {
-        if let Some(work) = self.works.lock().get_refresh(&pow_hash) {
-            if self
-                .pow
-                .verify_proof_difficulty(&seal.proof(), &work.block.header().difficulty())
-            {
+        let mut works = self.works.lock();
+        let work = match works.get_refresh(&pow_hash) {
+            Some(work) => work,
+            None => return,
+        };
+        if !self
+            .pow
+            .verify_proof_difficulty(&seal.proof(), &work.difficulty())
+        {
+            return;
+        }
+        match work {
+            Work::Template { work_id, block } => {
                 self.notify_workers(WorkerMessage::Stop);
-                let raw_header = work.block.header().raw().to_owned();
-                let block = BlockBuilder::from_block(work.block.clone())
+                let raw_header = block.header().raw().to_owned();
+                let block = BlockBuilder::from_block(block.clone())
                     .header(raw_header.with_seal(seal))
                     .build();
 
@@ -119,11 +130,11 @@ impl Miner {
                 }
 
                 // submit block and poll new work
-                {
-                    self.client.submit_block(&work.work_id.to_string(), &block);
-                    self.client.try_update_block_template();
-                    self.notify_workers(WorkerMessage::Start);
+                if let Some(client) = self.client.as_mut() {
+                    client.submit_block(&work_id.to_string(), &block);
+                    client.try_update_block_template();
                 }
+                self.notify_workers(WorkerMessage::Start);
 
                 // draw progress bar
                 {
@@ -138,6 +149,19 @@ impl Miner {
                     self.pb.inc(1);
                 }
             }
+            // the server submits the block if the share reaches the block difficulty, and pushes
+            // the new job, so the workers keep mining the current one
+            Work::Job {
+                job_id, connection, ..
+            } => {
+                debug!("Found share of job {} {:#x}", job_id, pow_hash);
+                connection.submit(job_id, seal);
+
+                self.shares_found += 1;
+                self.pb
+                    .set_message(&format!("Total shares found: {:>3}", self.shares_found));
+                self.pb.inc(1);
+            }
         }
     }
 
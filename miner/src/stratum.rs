@@ -0,0 +1,135 @@
+//! The stratum protocol between the node and the workers, which is line delimited JSON-RPC over
+//! TCP.
+//!
+//! * `mining.subscribe` without params, the node replies `true` and pushes the jobs to the worker
+//!   from then on.
+//! * `mining.notify` notification with params `[job_id, pow_hash, difficulty, clean_jobs]`, the
+//!   seals reaching `difficulty` are accepted as shares. `clean_jobs` is true if the previous
+//!   jobs are stale because the tip is changed.
+//! * `mining.submit` with params `[job_id, nonce, proof]`, the node replies `true` if the share is
+//!   accepted, and submits the block if the seal reaches the block difficulty as well.
+use crate::Work;
+use ckb_core::header::Seal;
+use ckb_jsonrpc_types::{
+    id::Id, params::Params, request::MethodCall, response::Output, version::Version, JsonBytes,
+    Unsigned,
+};
+use ckb_logger::{debug, error, info, warn};
+use ckb_util::Mutex;
+use crossbeam_channel::Sender;
+use failure::{format_err, Error};
+use numext_fixed_hash::H256;
+use numext_fixed_uint::U256;
+use serde_json::{self, json, Value};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+pub const STRATUM_SCHEME: &str = "stratum+tcp://";
+pub const STRATUM_SUBSCRIBE: &str = "mining.subscribe";
+pub const STRATUM_NOTIFY: &str = "mining.notify";
+pub const STRATUM_SUBMIT: &str = "mining.submit";
+
+const SUBSCRIBE_ID: u64 = 0;
+const SUBMIT_ID: u64 = 1;
+
+// The connection to the stratum server, shared by the jobs received from it
+#[derive(Clone)]
+pub struct StratumConnection {
+    stream: Arc<Mutex<TcpStream>>,
+}
+
+impl StratumConnection {
+    fn send(&self, id: u64, method: &str, params: Vec<Value>) -> io::Result<()> {
+        let call = MethodCall {
+            method: method.to_owned(),
+            params: Params::Array(params),
+            jsonrpc: Some(Version::V2),
+            id: Id::Num(id),
+        };
+        let mut raw = serde_json::to_vec(&call).expect("valid stratum call");
+        raw.push(b'\n');
+        self.stream.lock().write_all(&raw)
+    }
+
+    pub(crate) fn submit(&self, job_id: &str, seal: Seal) {
+        let (nonce, proof) = seal.destruct();
+        let params = vec![
+            json!(job_id),
+            json!(Unsigned(nonce)),
+            json!(JsonBytes::from_bytes(proof)),
+        ];
+        if let Err(err) = self.send(SUBMIT_ID, STRATUM_SUBMIT, params) {
+            error!("stratum submit error: {:?}", err);
+        }
+    }
+}
+
+// Receives the jobs from the stratum server instead of polling the block templates
+pub struct StratumClient {
+    address: String,
+    retry_interval: u64,
+    new_work_tx: Sender<Work>,
+}
+
+impl StratumClient {
+    pub fn new(new_work_tx: Sender<Work>, address: String, retry_interval: u64) -> StratumClient {
+        StratumClient {
+            address,
+            retry_interval,
+            new_work_tx,
+        }
+    }
+
+    // Reconnects after `retry_interval` milliseconds when the connection is lost
+    pub fn run(&self) {
+        loop {
+            if let Err(err) = self.subscribe() {
+                error!("stratum server {} error: {}", self.address, err);
+            }
+            thread::sleep(Duration::from_millis(self.retry_interval));
+        }
+    }
+
+    fn subscribe(&self) -> Result<(), Error> {
+        let stream = TcpStream::connect(&self.address)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let connection = StratumConnection {
+            stream: Arc::new(Mutex::new(stream)),
+        };
+        connection.send(SUBSCRIBE_ID, STRATUM_SUBSCRIBE, Vec::new())?;
+
+        for line in reader.lines() {
+            let message: Value = serde_json::from_str(&line?)?;
+            if message.get("method").is_some() {
+                if message["method"] == STRATUM_NOTIFY {
+                    let (job_id, pow_hash, difficulty, _clean_jobs): (String, H256, U256, bool) =
+                        serde_json::from_value(message["params"].clone())?;
+                    debug!("new stratum job {} {:#x}", job_id, pow_hash);
+                    self.new_work_tx.send(Work::Job {
+                        job_id,
+                        pow_hash,
+                        difficulty,
+                        connection: connection.clone(),
+                    })?;
+                }
+                continue;
+            }
+            match serde_json::from_value(message)? {
+                Output::Success(ref success) if success.id == Id::Num(SUBSCRIBE_ID) => {
+                    info!("subscribed to stratum server {}", self.address);
+                }
+                Output::Success(_) => debug!("stratum share accepted"),
+                Output::Failure(failure) => {
+                    if failure.id == Id::Num(SUBSCRIBE_ID) {
+                        return Err(format_err!("subscribe failed: {}", failure.error.message));
+                    }
+                    warn!("stratum share rejected: {}", failure.error.message);
+                }
+            }
+        }
+        Err(format_err!("connection closed"))
+    }
+}
@@ -1,10 +1,12 @@
 #![allow(clippy::needless_pass_by_value)]
 
+use ckb_core::block::Block;
 use ckb_core::service::Request;
+use ckb_core::transaction::Transaction;
 use ckb_core::uncle::UncleBlock;
 use ckb_logger::{debug, trace, warn};
 use ckb_stop_handler::{SignalSender, StopHandler};
-use crossbeam_channel::{select, Receiver, Sender};
+use crossbeam_channel::{select, Receiver, Sender, TrySendError};
 use fnv::FnvHashMap;
 use std::sync::Arc;
 use std::thread;
@@ -13,26 +15,52 @@ pub const SIGNAL_CHANNEL_SIZE: usize = 1;
 pub const REGISTER_CHANNEL_SIZE: usize = 2;
 pub const NOTIFY_CHANNEL_SIZE: usize = 128;
 
-pub type MsgNewTransaction = ();
-// pub type MsgNewTip = Arc<Block>;
+pub type MsgNewTransaction = Arc<Transaction>;
+// The tx rejected by the pool, with the reason
+pub type MsgRejectedTransaction = Arc<(Transaction, String)>;
+pub type MsgNewTip = Arc<Block>;
 pub type MsgNewUncle = Arc<UncleBlock>;
-// pub type MsgSwitchFork = Arc<ForkBlocks>;
+pub type MsgSwitchFork = Arc<ForkBlocks>;
 pub type NotifyRegister<M> = Sender<Request<(String, usize), Receiver<M>>>;
 
+// The blocks detached from and attached to the main chain when the tip is switched, both in the
+// ascending order of the block number. The detached ones are empty if the main chain is extended.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct ForkBlocks {
+    detached: Vec<Block>,
+    attached: Vec<Block>,
+}
+
+impl ForkBlocks {
+    pub fn new(detached: Vec<Block>, attached: Vec<Block>) -> Self {
+        ForkBlocks { detached, attached }
+    }
+
+    pub fn detached(&self) -> &[Block] {
+        &self.detached
+    }
+
+    pub fn attached(&self) -> &[Block] {
+        &self.attached
+    }
+}
+
 #[derive(Default)]
 pub struct NotifyService {}
 
 #[derive(Clone)]
 pub struct NotifyController {
     stop: StopHandler<()>,
-    // new_transaction_register: NotifyRegister<MsgNewTransaction>,
-    // new_tip_register: NotifyRegister<MsgNewTip>,
+    new_transaction_register: NotifyRegister<MsgNewTransaction>,
+    rejected_transaction_register: NotifyRegister<MsgRejectedTransaction>,
+    new_tip_register: NotifyRegister<MsgNewTip>,
     new_uncle_register: NotifyRegister<MsgNewUncle>,
-    // switch_fork_register: NotifyRegister<MsgSwitchFork>,
-    // new_transaction_notifier: Sender<MsgNewTransaction>,
-    // new_tip_notifier: Sender<MsgNewTip>,
+    switch_fork_register: NotifyRegister<MsgSwitchFork>,
+    new_transaction_notifier: Sender<MsgNewTransaction>,
+    rejected_transaction_notifier: Sender<MsgRejectedTransaction>,
+    new_tip_notifier: Sender<MsgNewTip>,
     new_uncle_notifier: Sender<MsgNewUncle>,
-    // switch_fork_notifier: Sender<MsgSwitchFork>,
+    switch_fork_notifier: Sender<MsgSwitchFork>,
 }
 
 impl Drop for NotifyController {
@@ -47,28 +75,33 @@ impl NotifyService {
     pub fn start<S: ToString>(self, thread_name: Option<S>) -> NotifyController {
         let (signal_sender, signal_receiver) =
             crossbeam_channel::bounded::<()>(SIGNAL_CHANNEL_SIZE);
-        // let (new_transaction_register, new_transaction_register_receiver) =
-        //     crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
-        // let (new_tip_register, new_tip_register_receiver) =
-        //     crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
+        let (new_transaction_register, new_transaction_register_receiver) =
+            crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
+        let (rejected_transaction_register, rejected_transaction_register_receiver) =
+            crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
+        let (new_tip_register, new_tip_register_receiver) =
+            crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
         let (new_uncle_register, new_uncle_register_receiver) =
             crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
-        // let (switch_fork_register, switch_fork_register_receiver) =
-        //     crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
+        let (switch_fork_register, switch_fork_register_receiver) =
+            crossbeam_channel::bounded(REGISTER_CHANNEL_SIZE);
 
-        // let (new_transaction_sender, new_transaction_receiver) =
-        //     crossbeam_channel::bounded::<MsgNewTransaction>(NOTIFY_CHANNEL_SIZE);
-        // let (new_tip_sender, new_tip_receiver) =
-        //     crossbeam_channel::bounded::<MsgNewTip>(NOTIFY_CHANNEL_SIZE);
+        let (new_transaction_sender, new_transaction_receiver) =
+            crossbeam_channel::bounded::<MsgNewTransaction>(NOTIFY_CHANNEL_SIZE);
+        let (rejected_transaction_sender, rejected_transaction_receiver) =
+            crossbeam_channel::bounded::<MsgRejectedTransaction>(NOTIFY_CHANNEL_SIZE);
+        let (new_tip_sender, new_tip_receiver) =
+            crossbeam_channel::bounded::<MsgNewTip>(NOTIFY_CHANNEL_SIZE);
         let (new_uncle_sender, new_uncle_receiver) =
             crossbeam_channel::bounded::<MsgNewUncle>(NOTIFY_CHANNEL_SIZE);
-        // let (switch_fork_sender, switch_fork_receiver) =
-        //     crossbeam_channel::bounded::<MsgSwitchFork>(NOTIFY_CHANNEL_SIZE);
+        let (switch_fork_sender, switch_fork_receiver) =
+            crossbeam_channel::bounded::<MsgSwitchFork>(NOTIFY_CHANNEL_SIZE);
 
-        // let mut new_transaction_subscribers = FnvHashMap::default();
-        // let mut new_tip_subscribers = FnvHashMap::default();
+        let mut new_transaction_subscribers = FnvHashMap::default();
+        let mut rejected_transaction_subscribers = FnvHashMap::default();
+        let mut new_tip_subscribers = FnvHashMap::default();
         let mut new_uncle_subscribers = FnvHashMap::default();
-        // let mut switch_fork_subscribers = FnvHashMap::default();
+        let mut switch_fork_subscribers = FnvHashMap::default();
 
         let mut thread_builder = thread::Builder::new();
         // Mainly for test: give a empty thread_name
@@ -82,86 +115,116 @@ impl NotifyService {
                         break;
                     }
 
-                    // recv(new_transaction_register_receiver) -> msg => Self::handle_register_new_transaction(
-                    //     &mut new_transaction_subscribers, msg
-                    // ),
-                    // recv(new_tip_register_receiver) -> msg => Self::handle_register_new_tip(
-                    //     &mut new_tip_subscribers, msg
-                    // ),
+                    recv(new_transaction_register_receiver) -> msg => Self::handle_register_new_transaction(
+                        &mut new_transaction_subscribers, msg
+                    ),
+                    recv(rejected_transaction_register_receiver) -> msg => Self::handle_register_rejected_transaction(
+                        &mut rejected_transaction_subscribers, msg
+                    ),
+                    recv(new_tip_register_receiver) -> msg => Self::handle_register_new_tip(
+                        &mut new_tip_subscribers, msg
+                    ),
                     recv(new_uncle_register_receiver) -> msg => Self::handle_register_new_uncle(
                         &mut new_uncle_subscribers, msg
                     ),
-                    // recv(switch_fork_register_receiver) -> msg => Self::handle_register_switch_fork(
-                    //     &mut switch_fork_subscribers, msg
-                    // ),
-
-                    // recv(new_transaction_receiver) -> msg => Self::handle_notify_new_transaction(
-                    //     &new_transaction_subscribers, msg
-                    // ),
-                    // recv(new_tip_receiver) -> msg => Self::handle_notify_new_tip(
-                    //     &new_tip_subscribers, msg
-                    // ),
+                    recv(switch_fork_register_receiver) -> msg => Self::handle_register_switch_fork(
+                        &mut switch_fork_subscribers, msg
+                    ),
+
+                    recv(new_transaction_receiver) -> msg => Self::handle_notify_new_transaction(
+                        &mut new_transaction_subscribers, msg
+                    ),
+                    recv(rejected_transaction_receiver) -> msg => Self::handle_notify_rejected_transaction(
+                        &mut rejected_transaction_subscribers, msg
+                    ),
+                    recv(new_tip_receiver) -> msg => Self::handle_notify_new_tip(
+                        &mut new_tip_subscribers, msg
+                    ),
                     recv(new_uncle_receiver) -> msg => Self::handle_notify_new_uncle(
-                        &new_uncle_subscribers, msg
+                        &mut new_uncle_subscribers, msg
+                    ),
+                    recv(switch_fork_receiver) -> msg => Self::handle_notify_switch_fork(
+                        &mut switch_fork_subscribers, msg
                     ),
-                    // recv(switch_fork_receiver) -> msg => Self::handle_notify_switch_fork(
-                    //     &switch_fork_subscribers, msg
-                    // )
                 }
             })
             .expect("Start notify service failed");
 
         NotifyController {
-            // new_transaction_register,
-            // new_tip_register,
+            new_transaction_register,
+            rejected_transaction_register,
+            new_tip_register,
             new_uncle_register,
-            // switch_fork_register,
-            // new_transaction_notifier: new_transaction_sender,
-            // new_tip_notifier: new_tip_sender,
+            switch_fork_register,
+            new_transaction_notifier: new_transaction_sender,
+            rejected_transaction_notifier: rejected_transaction_sender,
+            new_tip_notifier: new_tip_sender,
             new_uncle_notifier: new_uncle_sender,
-            // switch_fork_notifier: switch_fork_sender,
+            switch_fork_notifier: switch_fork_sender,
             stop: StopHandler::new(SignalSender::Crossbeam(signal_sender), join_handle),
         }
     }
 
-    // fn handle_register_new_transaction(
-    //     subscribers: &mut FnvHashMap<String, Sender<MsgNewTransaction>>,
-    //     msg: Result<
-    //         Request<(String, usize), Receiver<MsgNewTransaction>>,
-    //         crossbeam_channel::RecvError,
-    //     >,
-    // ) {
-    //     match msg {
-    //         Ok(Request {
-    //             responder,
-    //             arguments: (name, capacity),
-    //         }) => {
-    //             debug!("Register new_transaction {:?}", name);
-    //             let (sender, receiver) = crossbeam_channel::bounded::<MsgNewTransaction>(capacity);
-    //             subscribers.insert(name, sender);
-    //             let _ = responder.send(receiver);
-    //         }
-    //         _ => warn!("Register new_transaction channel is closed"),
-    //     }
-    // }
-
-    // fn handle_register_new_tip(
-    //     subscribers: &mut FnvHashMap<String, Sender<MsgNewTip>>,
-    //     msg: Result<Request<(String, usize), Receiver<MsgNewTip>>, crossbeam_channel::RecvError>,
-    // ) {
-    //     match msg {
-    //         Ok(Request {
-    //             responder,
-    //             arguments: (name, capacity),
-    //         }) => {
-    //             debug!("Register new_tip {:?}", name);
-    //             let (sender, receiver) = crossbeam_channel::bounded::<MsgNewTip>(capacity);
-    //             subscribers.insert(name, sender);
-    //             let _ = responder.send(receiver);
-    //         }
-    //         _ => warn!("Register new_tip channel is closed"),
-    //     }
-    // }
+    fn handle_register_new_transaction(
+        subscribers: &mut FnvHashMap<String, Sender<MsgNewTransaction>>,
+        msg: Result<
+            Request<(String, usize), Receiver<MsgNewTransaction>>,
+            crossbeam_channel::RecvError,
+        >,
+    ) {
+        match msg {
+            Ok(Request {
+                responder,
+                arguments: (name, capacity),
+            }) => {
+                debug!("Register new_transaction {:?}", name);
+                let (sender, receiver) = crossbeam_channel::bounded::<MsgNewTransaction>(capacity);
+                subscribers.insert(name, sender);
+                let _ = responder.send(receiver);
+            }
+            _ => warn!("Register new_transaction channel is closed"),
+        }
+    }
+
+    fn handle_register_rejected_transaction(
+        subscribers: &mut FnvHashMap<String, Sender<MsgRejectedTransaction>>,
+        msg: Result<
+            Request<(String, usize), Receiver<MsgRejectedTransaction>>,
+            crossbeam_channel::RecvError,
+        >,
+    ) {
+        match msg {
+            Ok(Request {
+                responder,
+                arguments: (name, capacity),
+            }) => {
+                debug!("Register rejected_transaction {:?}", name);
+                let (sender, receiver) =
+                    crossbeam_channel::bounded::<MsgRejectedTransaction>(capacity);
+                subscribers.insert(name, sender);
+                let _ = responder.send(receiver);
+            }
+            _ => warn!("Register rejected_transaction channel is closed"),
+        }
+    }
+
+    fn handle_register_new_tip(
+        subscribers: &mut FnvHashMap<String, Sender<MsgNewTip>>,
+        msg: Result<Request<(String, usize), Receiver<MsgNewTip>>, crossbeam_channel::RecvError>,
+    ) {
+        match msg {
+            Ok(Request {
+                responder,
+                arguments: (name, capacity),
+            }) => {
+                debug!("Register new_tip {:?}", name);
+                let (sender, receiver) = crossbeam_channel::bounded::<MsgNewTip>(capacity);
+                subscribers.insert(name, sender);
+                let _ = responder.send(receiver);
+            }
+            _ => warn!("Register new_tip channel is closed"),
+        }
+    }
 
     fn handle_register_new_uncle(
         subscribers: &mut FnvHashMap<String, Sender<MsgNewUncle>>,
@@ -181,153 +244,211 @@ impl NotifyService {
         }
     }
 
-    // fn handle_register_switch_fork(
-    //     subscribers: &mut FnvHashMap<String, Sender<MsgSwitchFork>>,
-    //     msg: Result<
-    //         Request<(String, usize), Receiver<MsgSwitchFork>>,
-    //         crossbeam_channel::RecvError,
-    //     >,
-    // ) {
-    //     match msg {
-    //         Ok(Request {
-    //             responder,
-    //             arguments: (name, capacity),
-    //         }) => {
-    //             debug!("Register switch_fork {:?}", name);
-    //             let (sender, receiver) = crossbeam_channel::bounded::<MsgSwitchFork>(capacity);
-    //             subscribers.insert(name, sender);
-    //             let _ = responder.send(receiver);
-    //         }
-    //         _ => warn!("Register switch_fork channel is closed"),
-    //     }
-    // }
-
-    // fn handle_notify_new_transaction(
-    //     subscribers: &FnvHashMap<String, Sender<MsgNewTransaction>>,
-    //     msg: Result<MsgNewTransaction, crossbeam_channel::RecvError>,
-    // ) {
-    //     match msg {
-    //         Ok(()) => {
-    //             trace!("event new transaction {:?}", msg);
-    //             for subscriber in subscribers.values() {
-    //                 let _ = subscriber.send(());
-    //             }
-    //         }
-    //         _ => warn!("new transaction channel is closed"),
-    //     }
-    // }
-
-    // fn handle_notify_new_tip(
-    //     subscribers: &FnvHashMap<String, Sender<MsgNewTip>>,
-    //     msg: Result<MsgNewTip, crossbeam_channel::RecvError>,
-    // ) {
-    //     match msg {
-    //         Ok(msg) => {
-    //             trace!("event new tip {:?}", msg);
-    //             for subscriber in subscribers.values() {
-    //                 let _ = subscriber.send(Arc::clone(&msg));
-    //             }
-    //         }
-    //         _ => warn!("new tip channel is closed"),
-    //     }
-    // }
+    fn handle_register_switch_fork(
+        subscribers: &mut FnvHashMap<String, Sender<MsgSwitchFork>>,
+        msg: Result<
+            Request<(String, usize), Receiver<MsgSwitchFork>>,
+            crossbeam_channel::RecvError,
+        >,
+    ) {
+        match msg {
+            Ok(Request {
+                responder,
+                arguments: (name, capacity),
+            }) => {
+                debug!("Register switch_fork {:?}", name);
+                let (sender, receiver) = crossbeam_channel::bounded::<MsgSwitchFork>(capacity);
+                subscribers.insert(name, sender);
+                let _ = responder.send(receiver);
+            }
+            _ => warn!("Register switch_fork channel is closed"),
+        }
+    }
+
+    fn handle_notify_new_transaction(
+        subscribers: &mut FnvHashMap<String, Sender<MsgNewTransaction>>,
+        msg: Result<MsgNewTransaction, crossbeam_channel::RecvError>,
+    ) {
+        match msg {
+            Ok(msg) => {
+                trace!("event new transaction {:?}", msg);
+                Self::publish("new_transaction", subscribers, msg);
+            }
+            _ => warn!("new transaction channel is closed"),
+        }
+    }
+
+    fn handle_notify_rejected_transaction(
+        subscribers: &mut FnvHashMap<String, Sender<MsgRejectedTransaction>>,
+        msg: Result<MsgRejectedTransaction, crossbeam_channel::RecvError>,
+    ) {
+        match msg {
+            Ok(msg) => {
+                trace!("event rejected transaction {:?}", msg);
+                Self::publish("rejected_transaction", subscribers, msg);
+            }
+            _ => warn!("rejected transaction channel is closed"),
+        }
+    }
+
+    fn handle_notify_new_tip(
+        subscribers: &mut FnvHashMap<String, Sender<MsgNewTip>>,
+        msg: Result<MsgNewTip, crossbeam_channel::RecvError>,
+    ) {
+        match msg {
+            Ok(msg) => {
+                trace!("event new tip {:?}", msg);
+                Self::publish("new_tip", subscribers, msg);
+            }
+            _ => warn!("new tip channel is closed"),
+        }
+    }
 
     fn handle_notify_new_uncle(
-        subscribers: &FnvHashMap<String, Sender<MsgNewUncle>>,
+        subscribers: &mut FnvHashMap<String, Sender<MsgNewUncle>>,
         msg: Result<MsgNewUncle, crossbeam_channel::RecvError>,
     ) {
         match msg {
             Ok(msg) => {
                 trace!("event new uncle {:?}", msg);
-                for subscriber in subscribers.values() {
-                    let _ = subscriber.send(Arc::clone(&msg));
-                }
+                Self::publish("new_uncle", subscribers, msg);
             }
             _ => warn!("new uncle channel is closed"),
         }
     }
 
-    // fn handle_notify_switch_fork(
-    //     subscribers: &FnvHashMap<String, Sender<MsgSwitchFork>>,
-    //     msg: Result<MsgSwitchFork, crossbeam_channel::RecvError>,
-    // ) {
-    //     match msg {
-    //         Ok(msg) => {
-    //             trace!("event switch fork {:?}", msg);
-    //             for subscriber in subscribers.values() {
-    //                 let _ = subscriber.send(Arc::clone(&msg));
-    //             }
-    //         }
-    //         _ => warn!("event 3 channel is closed"),
-    //     }
-    // }
+    fn handle_notify_switch_fork(
+        subscribers: &mut FnvHashMap<String, Sender<MsgSwitchFork>>,
+        msg: Result<MsgSwitchFork, crossbeam_channel::RecvError>,
+    ) {
+        match msg {
+            Ok(msg) => {
+                trace!("event switch fork {:?}", msg);
+                Self::publish("switch_fork", subscribers, msg);
+            }
+            _ => warn!("switch fork channel is closed"),
+        }
+    }
+
+    // Never blocks the service on a slow subscriber: the event is dropped for the subscriber whose
+    // channel is full, and the subscriber is removed once its receiver is dropped
+    fn publish<M: Clone>(event: &str, subscribers: &mut FnvHashMap<String, Sender<M>>, msg: M) {
+        subscribers.retain(|name, subscriber| match subscriber.try_send(msg.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                warn!("{} subscriber {} lags behind, drop event", event, name);
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                debug!("{} subscriber {} is closed", event, name);
+                false
+            }
+        });
+    }
 }
 
 impl NotifyController {
-    // pub fn subscribe_new_transaction<S: ToString>(&self, name: S) -> Receiver<MsgNewTransaction> {
-    //     Request::call(&self.new_transaction_register, (name.to_string(), 128))
-    //         .expect("Subscribe new transaction failed")
-    // }
-    // pub fn subscribe_new_tip<S: ToString>(&self, name: S) -> Receiver<MsgNewTip> {
-    //     Request::call(&self.new_tip_register, (name.to_string(), 128))
-    //         .expect("Subscribe new tip failed")
-    // }
+    pub fn subscribe_new_transaction<S: ToString>(&self, name: S) -> Receiver<MsgNewTransaction> {
+        Request::call(&self.new_transaction_register, (name.to_string(), 128))
+            .expect("Subscribe new transaction failed")
+    }
+    pub fn subscribe_rejected_transaction<S: ToString>(
+        &self,
+        name: S,
+    ) -> Receiver<MsgRejectedTransaction> {
+        Request::call(&self.rejected_transaction_register, (name.to_string(), 128))
+            .expect("Subscribe rejected transaction failed")
+    }
+    pub fn subscribe_new_tip<S: ToString>(&self, name: S) -> Receiver<MsgNewTip> {
+        Request::call(&self.new_tip_register, (name.to_string(), 128))
+            .expect("Subscribe new tip failed")
+    }
     pub fn subscribe_new_uncle<S: ToString>(&self, name: S) -> Receiver<MsgNewUncle> {
         Request::call(&self.new_uncle_register, (name.to_string(), 128))
             .expect("Subscribe new uncle failed")
     }
-    // pub fn subscribe_switch_fork<S: ToString>(&self, name: S) -> Receiver<MsgSwitchFork> {
-    //     Request::call(&self.switch_fork_register, (name.to_string(), 128))
-    //         .expect("Subscribe switch fork failed")
-    // }
-
-    // pub fn notify_new_transaction(&self) {
-    //     let _ = self.new_transaction_notifier.send(());
-    // }
-    // pub fn notify_new_tip(&self, block: MsgNewTip) {
-    //     let _ = self.new_tip_notifier.send(block);
-    // }
+    pub fn subscribe_switch_fork<S: ToString>(&self, name: S) -> Receiver<MsgSwitchFork> {
+        Request::call(&self.switch_fork_register, (name.to_string(), 128))
+            .expect("Subscribe switch fork failed")
+    }
+
+    pub fn notify_new_transaction(&self, tx: MsgNewTransaction) {
+        let _ = self.new_transaction_notifier.send(tx);
+    }
+    pub fn notify_rejected_transaction(&self, rejected: MsgRejectedTransaction) {
+        let _ = self.rejected_transaction_notifier.send(rejected);
+    }
+    pub fn notify_new_tip(&self, block: MsgNewTip) {
+        let _ = self.new_tip_notifier.send(block);
+    }
     pub fn notify_new_uncle(&self, block: MsgNewUncle) {
         let _ = self.new_uncle_notifier.send(block);
     }
-    // pub fn notify_switch_fork(&self, txs: MsgSwitchFork) {
-    //     let _ = self.switch_fork_notifier.send(txs);
-    // }
+    pub fn notify_switch_fork(&self, blocks: MsgSwitchFork) {
+        let _ = self.switch_fork_notifier.send(blocks);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    // use super::*;
-
-    // #[test]
-    // fn test_new_transaction() {
-    //     let notify = NotifyService::default().start::<&str>(None);
-    //     let receiver1 = notify.subscribe_new_transaction("miner1");
-    //     let receiver2 = notify.subscribe_new_transaction("miner2");
-    //     notify.notify_new_transaction();
-    //     assert_eq!(receiver1.recv(), Ok(()));
-    //     assert_eq!(receiver2.recv(), Ok(()));
-    // }
-
-    // #[test]
-    // fn test_new_tip() {
-    //     let tip = Arc::new(Block::default());
-    //     let notify = NotifyService::default().start::<&str>(None);
-    //     let receiver1 = notify.subscribe_new_tip("miner1");
-    //     let receiver2 = notify.subscribe_new_tip("miner2");
-    //     notify.notify_new_tip(Arc::clone(&tip));
-    //     assert_eq!(receiver1.recv(), Ok(Arc::clone(&tip)));
-    //     assert_eq!(receiver2.recv(), Ok(tip));
-    // }
-
-    // #[test]
-    // fn test_switch_fork() {
-    //     let blks = Arc::new(ForkBlocks::default());
-    //     let notify = NotifyService::default().start::<&str>(None);
-    //     let receiver1 = notify.subscribe_switch_fork("miner1");
-    //     let receiver2 = notify.subscribe_switch_fork("miner2");
-    //     notify.notify_switch_fork(Arc::clone(&blks));
-    //     assert_eq!(receiver1.recv(), Ok(Arc::clone(&blks)));
-    //     assert_eq!(receiver2.recv(), Ok(blks));
-    // }
+    use super::*;
+    use ckb_core::block::BlockBuilder;
+    use ckb_core::header::HeaderBuilder;
+    use ckb_core::transaction::TransactionBuilder;
+
+    #[test]
+    fn test_new_transaction() {
+        let tx = Arc::new(TransactionBuilder::default().build());
+        let notify = NotifyService::default().start::<&str>(None);
+        let receiver1 = notify.subscribe_new_transaction("miner1");
+        let receiver2 = notify.subscribe_new_transaction("miner2");
+        notify.notify_new_transaction(Arc::clone(&tx));
+        assert_eq!(receiver1.recv(), Ok(Arc::clone(&tx)));
+        assert_eq!(receiver2.recv(), Ok(tx));
+    }
+
+    #[test]
+    fn test_new_tip() {
+        let tip = Arc::new(BlockBuilder::default().build());
+        let notify = NotifyService::default().start::<&str>(None);
+        let receiver1 = notify.subscribe_new_tip("miner1");
+        let receiver2 = notify.subscribe_new_tip("miner2");
+        notify.notify_new_tip(Arc::clone(&tip));
+        assert_eq!(receiver1.recv(), Ok(Arc::clone(&tip)));
+        assert_eq!(receiver2.recv(), Ok(tip));
+    }
+
+    #[test]
+    fn test_switch_fork() {
+        let blks = Arc::new(ForkBlocks::default());
+        let notify = NotifyService::default().start::<&str>(None);
+        let receiver1 = notify.subscribe_switch_fork("miner1");
+        let receiver2 = notify.subscribe_switch_fork("miner2");
+        notify.notify_switch_fork(Arc::clone(&blks));
+        assert_eq!(receiver1.recv(), Ok(Arc::clone(&blks)));
+        assert_eq!(receiver2.recv(), Ok(blks));
+    }
+
+    #[test]
+    fn test_lagging_subscriber() {
+        let notify = NotifyService::default().start::<&str>(None);
+        let lagging = notify.subscribe_new_tip("lagging");
+        let receiver = notify.subscribe_new_tip("miner");
+        let dropped = notify.subscribe_new_tip("dropped");
+        drop(dropped);
+
+        let tips: Vec<_> = (0..=NOTIFY_CHANNEL_SIZE as u64)
+            .map(|number| {
+                let header = HeaderBuilder::default().number(number).build();
+                Arc::new(BlockBuilder::default().header(header).build())
+            })
+            .collect();
+        for tip in &tips {
+            notify.notify_new_tip(Arc::clone(tip));
+            // the service is not blocked by the lagging subscriber
+            assert_eq!(receiver.recv(), Ok(Arc::clone(tip)));
+        }
+        // the lagging subscriber misses the events after its channel is full
+        assert_eq!(lagging.try_iter().count(), NOTIFY_CHANNEL_SIZE);
+    }
 }
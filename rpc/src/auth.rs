@@ -0,0 +1,77 @@
+use crate::config::AuthConfig;
+use jsonrpc_http_server::hyper::header::{HeaderValue, AUTHORIZATION};
+use jsonrpc_http_server::hyper::{Body, Method, Request, StatusCode};
+use jsonrpc_http_server::{RequestMiddleware, RequestMiddlewareAction, Response};
+
+// Rejects the HTTP requests without the expected `Authorization` header. The CORS preflight
+// requests are passed since browsers never attach the credentials to them.
+pub(crate) struct AuthMiddleware {
+    expected: String,
+}
+
+impl AuthMiddleware {
+    pub fn new(config: &AuthConfig) -> Self {
+        let expected = match config {
+            AuthConfig::Token { token } => format!("Bearer {}", token),
+            AuthConfig::Basic { username, password } => format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", username, password).as_bytes())
+            ),
+        };
+        AuthMiddleware { expected }
+    }
+
+    fn verify(&self, authorization: Option<&HeaderValue>) -> bool {
+        authorization
+            .map(|value| constant_time_eq(value.as_bytes(), self.expected.as_bytes()))
+            .unwrap_or(false)
+    }
+}
+
+impl RequestMiddleware for AuthMiddleware {
+    fn on_request(&self, request: Request<Body>) -> RequestMiddlewareAction {
+        if request.method() == Method::OPTIONS || self.verify(request.headers().get(AUTHORIZATION))
+        {
+            request.into()
+        } else {
+            Response {
+                code: StatusCode::UNAUTHORIZED,
+                content_type: HeaderValue::from_static("text/plain; charset=utf-8"),
+                content: "Unauthorized\n".to_owned(),
+            }
+            .into()
+        }
+    }
+}
+
+// Compares the credentials without leaking the length of the matched prefix through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_token() {
+        let auth = AuthMiddleware::new(&AuthConfig::Token {
+            token: "secret".to_owned(),
+        });
+        assert!(auth.verify(Some(&HeaderValue::from_static("Bearer secret"))));
+        assert!(!auth.verify(Some(&HeaderValue::from_static("Bearer secreT"))));
+        assert!(!auth.verify(Some(&HeaderValue::from_static("secret"))));
+        assert!(!auth.verify(None));
+    }
+
+    #[test]
+    fn test_verify_basic() {
+        let auth = AuthMiddleware::new(&AuthConfig::Basic {
+            username: "ckb".to_owned(),
+            password: "secret".to_owned(),
+        });
+        // base64 of "ckb:secret"
+        assert!(auth.verify(Some(&HeaderValue::from_static("Basic Y2tiOnNlY3JldA=="))));
+        assert!(!auth.verify(Some(&HeaderValue::from_static("Bearer secret"))));
+    }
+}
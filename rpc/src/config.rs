@@ -1,3 +1,4 @@
+use numext_fixed_uint::U256;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Copy, Eq, PartialEq, Serialize, Deserialize)]
@@ -11,50 +12,142 @@ pub enum Module {
     Indexer,
     IntegrationTest,
     Alert,
+    Subscription,
+}
+
+// The credentials required in the `Authorization` header of the HTTP requests
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuthConfig {
+    // `Authorization: Bearer <token>`
+    Token { token: String },
+    // `Authorization: Basic <base64 of username:password>`
+    Basic { username: String, password: String },
+}
+
+// An extra HTTP listener serving its own modules, such as the public Chain module on 0.0.0.0
+// while the Miner and Net modules are only served on localhost
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ListenerConfig {
+    pub listen_address: String,
+    pub modules: Vec<Module>,
+    // No authentication if not set
+    pub auth: Option<AuthConfig>,
+}
+
+// The token bucket of each client, the clients are identified by the peer address on TCP, the
+// connection on WebSocket and the `X-Forwarded-For` header on HTTP. The HTTP server does not
+// expose the peer address, so the rate limit requires the HTTP listeners to be behind a trusted
+// reverse proxy setting the header.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub calls_per_second: u32,
+    // The calls allowed in a burst, each call in a batch counts
+    pub burst: u32,
+    #[serde(default)]
+    pub trust_forwarded_for: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub listen_address: String,
+    // WebSocket transport is required to subscribe events, it's disabled if not set
+    pub ws_listen_address: Option<String>,
+    // Raw TCP transport, the requests and responses are delimited by newlines, it also supports
+    // subscription and is disabled if not set
+    pub tcp_listen_address: Option<String>,
+    // Stratum server for the mining workers, it's disabled if not set and requires the Miner module
+    pub stratum_listen_address: Option<String>,
+    // The difficulty of the shares accepted by the stratum server, the block difficulty if not set
+    pub stratum_share_difficulty: Option<U256>,
     pub max_request_body_size: usize,
     pub threads: Option<usize>,
     pub modules: Vec<Module>,
+    // The authentication of `listen_address`, no authentication if not set. The WebSocket and TCP
+    // transports are not authenticated, so they can not be enabled with it.
+    pub auth: Option<AuthConfig>,
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+    // Only these methods are served if it's not empty
+    #[serde(default)]
+    pub enabled_methods: Vec<String>,
+    // These methods are never served
+    #[serde(default)]
+    pub disabled_methods: Vec<String>,
+    // The max number of calls in a batch request, no limit if not set
+    pub max_batch_size: Option<usize>,
+    // No rate limit if not set
+    pub rate_limit: Option<RateLimitConfig>,
 }
 
 impl Config {
+    // Whether the module is served by any listener
+    pub fn module_enable(&self, module: Module) -> bool {
+        self.modules.contains(&module)
+            || self
+                .listeners
+                .iter()
+                .any(|listener| listener.modules.contains(&module))
+    }
+
+    pub fn method_enable(&self, method: &str) -> bool {
+        (self.enabled_methods.is_empty() || self.enabled_methods.iter().any(|m| m == method))
+            && !self.disabled_methods.iter().any(|m| m == method)
+    }
+
     pub fn net_enable(&self) -> bool {
-        self.modules.contains(&Module::Net)
+        self.module_enable(Module::Net)
     }
 
     pub fn chain_enable(&self) -> bool {
-        self.modules.contains(&Module::Chain)
+        self.module_enable(Module::Chain)
     }
 
     pub fn miner_enable(&self) -> bool {
-        self.modules.contains(&Module::Miner)
+        self.module_enable(Module::Miner)
     }
 
     pub fn pool_enable(&self) -> bool {
-        self.modules.contains(&Module::Pool)
+        self.module_enable(Module::Pool)
     }
 
     pub fn experiment_enable(&self) -> bool {
-        self.modules.contains(&Module::Experiment)
+        self.module_enable(Module::Experiment)
     }
 
     pub fn stats_enable(&self) -> bool {
-        self.modules.contains(&Module::Stats)
+        self.module_enable(Module::Stats)
     }
 
     pub fn indexer_enable(&self) -> bool {
-        self.modules.contains(&Module::Indexer)
+        self.module_enable(Module::Indexer)
     }
 
     pub fn integration_test_enable(&self) -> bool {
-        self.modules.contains(&Module::IntegrationTest)
+        self.module_enable(Module::IntegrationTest)
     }
 
     pub(crate) fn alert_enable(&self) -> bool {
-        self.modules.contains(&Module::Alert)
+        self.module_enable(Module::Alert)
+    }
+
+    pub fn subscription_enable(&self) -> bool {
+        self.module_enable(Module::Subscription)
+    }
+
+    // The WebSocket and TCP transports serve the modules of `listen_address` without
+    // authentication, they must not bypass its `auth`
+    pub fn transports_authenticated(&self) -> bool {
+        self.auth.is_none()
+            || (self.ws_listen_address.is_none() && self.tcp_listen_address.is_none())
+    }
+
+    // The HTTP clients can only be told apart by the `X-Forwarded-For` header, they must not
+    // share one rate limit
+    pub fn rate_limit_per_client(&self) -> bool {
+        self.rate_limit
+            .as_ref()
+            .map(|rate_limit| rate_limit.trust_forwarded_for)
+            .unwrap_or(true)
     }
 }
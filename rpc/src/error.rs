@@ -1,8 +1,9 @@
-use jsonrpc_core::{Error, ErrorCode};
+use jsonrpc_core::{Error, ErrorCode, Value};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum RPCError {
     Invalid = -3,
+    RateLimited = -4,
 }
 
 impl RPCError {
@@ -13,4 +14,12 @@ impl RPCError {
             data: None,
         }
     }
+
+    pub fn custom_with_data(err: RPCError, message: String, data: Value) -> Error {
+        Error {
+            code: ErrorCode::ServerError(err as i64),
+            message,
+            data: Some(data),
+        }
+    }
 }
@@ -1,11 +1,17 @@
+pub(crate) mod auth;
 pub(crate) mod config;
 pub(crate) mod error;
+pub(crate) mod middleware;
 pub(crate) mod module;
 pub(crate) mod server;
 pub(crate) mod service_builder;
+pub(crate) mod stratum;
 #[cfg(test)]
 mod test;
 
-pub use crate::config::{Config, Module};
-pub use crate::server::RpcServer;
-pub use crate::service_builder::ServiceBuilder;
+pub use crate::config::{AuthConfig, Config, ListenerConfig, Module, RateLimitConfig};
+pub use crate::middleware::RpcMiddleware;
+pub use crate::module::openrpc_document;
+pub use crate::server::{RpcIoHandler, RpcMetadata, RpcServer};
+pub use crate::service_builder::{RpcModules, ServiceBuilder};
+pub use crate::stratum::{StratumController, StratumServer};
@@ -0,0 +1,272 @@
+use crate::config::{Config, RateLimitConfig};
+use crate::error::RPCError;
+use crate::server::RpcMetadata;
+use ckb_jsonrpc_types::{RpcMethodStats, RpcStats, Unsigned};
+use ckb_util::Mutex;
+use jsonrpc_core::futures::future::{self, Either, Future};
+use jsonrpc_core::{
+    BoxFuture, Failure, Id, Middleware, Output, Params, Request, Response, RpcMethod, Value,
+    Version,
+};
+use lru_cache::LruCache;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// The least recently seen clients are forgotten when there are more clients than this
+const MAX_RATE_LIMIT_CLIENTS: usize = 65_536;
+
+type FutureResponse = Box<dyn Future<Item = Option<Response>, Error = ()> + Send>;
+type FutureOutput = Box<dyn Future<Item = Option<Output>, Error = ()> + Send>;
+
+#[derive(Default)]
+struct MethodMetrics {
+    calls: u64,
+    errors: u64,
+    total_latency: Duration,
+    max_latency: Duration,
+}
+
+// The call counts and latencies of the methods, and the calls rejected by the middleware
+#[derive(Default)]
+pub(crate) struct RpcMetrics {
+    methods: Mutex<HashMap<String, MethodMetrics>>,
+    rate_limited_calls: AtomicU64,
+    oversized_batches: AtomicU64,
+}
+
+impl RpcMetrics {
+    fn record(&self, method: &str, latency: Duration, is_error: bool) {
+        let mut methods = self.methods.lock();
+        let metrics = methods.entry(method.to_owned()).or_default();
+        metrics.calls += 1;
+        if is_error {
+            metrics.errors += 1;
+        }
+        metrics.total_latency += latency;
+        metrics.max_latency = metrics.max_latency.max(latency);
+    }
+
+    pub fn stats(&self) -> RpcStats {
+        let mut methods: Vec<_> = self
+            .methods
+            .lock()
+            .iter()
+            .map(|(method, metrics)| RpcMethodStats {
+                method: method.to_owned(),
+                calls: Unsigned(metrics.calls),
+                errors: Unsigned(metrics.errors),
+                total_latency: Unsigned(metrics.total_latency.as_micros() as u64),
+                max_latency: Unsigned(metrics.max_latency.as_micros() as u64),
+            })
+            .collect();
+        methods.sort_by(|a, b| a.method.cmp(&b.method));
+        RpcStats {
+            methods,
+            rate_limited_calls: Unsigned(self.rate_limited_calls.load(Ordering::Relaxed)),
+            oversized_batches: Unsigned(self.oversized_batches.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+// Records the latency and the result of each call of the wrapped method
+pub(crate) struct MeteredMethod {
+    name: String,
+    inner: Arc<dyn RpcMethod<RpcMetadata>>,
+    metrics: Arc<RpcMetrics>,
+}
+
+impl MeteredMethod {
+    pub fn new(
+        name: String,
+        inner: Arc<dyn RpcMethod<RpcMetadata>>,
+        metrics: Arc<RpcMetrics>,
+    ) -> Self {
+        MeteredMethod {
+            name,
+            inner,
+            metrics,
+        }
+    }
+}
+
+impl RpcMethod<RpcMetadata> for MeteredMethod {
+    fn call(&self, params: Params, meta: RpcMetadata) -> BoxFuture<Value> {
+        let started_at = Instant::now();
+        let name = self.name.clone();
+        let metrics = Arc::clone(&self.metrics);
+        Box::new(self.inner.call(params, meta).then(move |result| {
+            metrics.record(&name, started_at.elapsed(), result.is_err());
+            result
+        }))
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+// Token buckets of the clients, each call takes a token
+struct RateLimiter {
+    calls_per_second: f64,
+    burst: f64,
+    buckets: Mutex<LruCache<String, Bucket>>,
+}
+
+impl RateLimiter {
+    fn new(config: &RateLimitConfig) -> Self {
+        RateLimiter {
+            calls_per_second: f64::from(config.calls_per_second),
+            burst: f64::from(config.burst.max(config.calls_per_second)),
+            buckets: Mutex::new(LruCache::new(MAX_RATE_LIMIT_CLIENTS)),
+        }
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now.duration_since(bucket.updated_at);
+        let refilled = elapsed.as_secs() as f64 * self.calls_per_second
+            + f64::from(elapsed.subsec_nanos()) * self.calls_per_second / 1e9;
+        bucket.tokens = (bucket.tokens + refilled).min(self.burst);
+        bucket.updated_at = now;
+    }
+
+    fn acquire(&self, client: &str, calls: usize) -> bool {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        if !buckets.contains_key(client) {
+            buckets.insert(
+                client.to_owned(),
+                Bucket {
+                    tokens: self.burst,
+                    updated_at: now,
+                },
+            );
+        }
+        let bucket = buckets.get_mut(client).expect("bucket inserted");
+        self.refill(bucket, now);
+        if bucket.tokens >= calls as f64 {
+            bucket.tokens -= calls as f64;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Rejects the oversized batches and the calls beyond the rate limit of the client before they are
+// executed
+#[derive(Clone)]
+pub struct RpcMiddleware {
+    max_batch_size: Option<usize>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    metrics: Arc<RpcMetrics>,
+}
+
+impl RpcMiddleware {
+    pub(crate) fn new(config: &Config, metrics: Arc<RpcMetrics>) -> Self {
+        RpcMiddleware {
+            max_batch_size: config.max_batch_size,
+            rate_limiter: config
+                .rate_limit
+                .as_ref()
+                .map(|rate_limit| Arc::new(RateLimiter::new(rate_limit))),
+            metrics,
+        }
+    }
+
+    fn check(&self, request: &Request, meta: &RpcMetadata) -> Result<(), jsonrpc_core::Error> {
+        let calls = match request {
+            Request::Single(_) => 1,
+            Request::Batch(calls) => calls.len(),
+        };
+        if let (Request::Batch(_), Some(max_batch_size)) = (request, self.max_batch_size) {
+            if calls > max_batch_size {
+                self.metrics
+                    .oversized_batches
+                    .fetch_add(1, Ordering::Relaxed);
+                return Err(RPCError::custom(
+                    RPCError::Invalid,
+                    format!("Batch size {} exceeds {}", calls, max_batch_size),
+                ));
+            }
+        }
+        if let Some(rate_limiter) = &self.rate_limiter {
+            if !rate_limiter.acquire(&meta.client, calls) {
+                self.metrics
+                    .rate_limited_calls
+                    .fetch_add(calls as u64, Ordering::Relaxed);
+                return Err(RPCError::custom(
+                    RPCError::RateLimited,
+                    "Too many requests".to_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Middleware<RpcMetadata> for RpcMiddleware {
+    type Future = FutureResponse;
+    type CallFuture = FutureOutput;
+
+    fn on_request<F, X>(
+        &self,
+        request: Request,
+        meta: RpcMetadata,
+        next: F,
+    ) -> Either<Self::Future, X>
+    where
+        F: FnOnce(Request, RpcMetadata) -> X + Send,
+        X: Future<Item = Option<Response>, Error = ()> + Send + 'static,
+    {
+        match self.check(&request, &meta) {
+            Ok(()) => Either::B(next(request, meta)),
+            Err(error) => Either::A(Box::new(future::ok(Some(Response::Single(
+                Output::Failure(Failure {
+                    jsonrpc: Some(Version::V2),
+                    error,
+                    id: Id::Null,
+                }),
+            ))))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = RateLimiter::new(&RateLimitConfig {
+            calls_per_second: 1,
+            burst: 10,
+            trust_forwarded_for: false,
+        });
+        assert!(rate_limiter.acquire("a", 8));
+        assert!(!rate_limiter.acquire("a", 3));
+        assert!(rate_limiter.acquire("a", 2));
+        assert!(!rate_limiter.acquire("a", 1));
+        // the clients are limited separately
+        assert!(rate_limiter.acquire("b", 10));
+    }
+
+    #[test]
+    fn test_rate_limiter_forgets_least_recent_clients() {
+        let rate_limiter = RateLimiter::new(&RateLimitConfig {
+            calls_per_second: 1,
+            burst: 10,
+            trust_forwarded_for: false,
+        });
+        assert!(rate_limiter.acquire("a", 10));
+        assert!(!rate_limiter.acquire("a", 1));
+        for client in 0..MAX_RATE_LIMIT_CLIENTS {
+            assert!(rate_limiter.acquire(&client.to_string(), 1));
+        }
+        assert_eq!(rate_limiter.buckets.lock().len(), MAX_RATE_LIMIT_CLIENTS);
+        // the bucket of `a` has been dropped
+        assert!(rate_limiter.acquire("a", 10));
+    }
+}
@@ -0,0 +1,68 @@
+use crate::config::Module;
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use serde_json::Value;
+
+// The OpenRPC document of all the methods, generated from the rpc traits and the types in
+// ckb-jsonrpc-types by `make gen-rpc-doc`
+const OPENRPC_DOCUMENT: &str = include_str!("../../json/openrpc.json");
+
+// Returns the OpenRPC document of the methods accepted by the filter. The methods served
+// regardless of the modules, such as `rpc_discover`, are passed to the filter without a module.
+pub fn openrpc_document<F>(filter: F) -> Value
+where
+    F: Fn(Option<Module>, &str) -> bool,
+{
+    let mut document: Value =
+        serde_json::from_str(OPENRPC_DOCUMENT).expect("deserialize openrpc document");
+    if let Some(methods) = document["methods"].as_array_mut() {
+        methods.retain(|method| {
+            let module = method["tags"][0]["name"]
+                .as_str()
+                .and_then(|name| serde_json::from_value(Value::String(name.to_owned())).ok());
+            filter(module, method["name"].as_str().unwrap_or_default())
+        });
+    }
+    document
+}
+
+#[rpc]
+pub trait DiscoverRpc {
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"rpc_discover","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "rpc_discover")]
+    fn rpc_discover(&self) -> Result<Value>;
+}
+
+pub(crate) struct DiscoverRpcImpl {
+    pub document: Value,
+}
+
+impl DiscoverRpc for DiscoverRpcImpl {
+    fn rpc_discover(&self) -> Result<Value> {
+        Ok(self.document.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openrpc_document() {
+        let document =
+            openrpc_document(|module, _| module.is_none() || module == Some(Module::Chain));
+        let names: Vec<_> = document["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|method| method["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"get_tip_block_number"));
+        assert!(names.contains(&"rpc_discover"));
+        assert!(!names.contains(&"send_transaction"));
+
+        let document = openrpc_document(|_, method| method == "send_transaction");
+        assert_eq!(document["methods"].as_array().unwrap().len(), 1);
+        assert!(document["components"]["schemas"]["Transaction"].is_object());
+    }
+}
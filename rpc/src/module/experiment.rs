@@ -3,12 +3,16 @@ use ckb_core::cell::{resolve_transaction, CellProvider, CellStatus, HeaderProvid
 use ckb_core::script::Script as CoreScript;
 use ckb_core::transaction::{OutPoint as CoreOutPoint, Transaction as CoreTransaction};
 use ckb_dao::DaoCalculator;
-use ckb_jsonrpc_types::{Capacity, Cycle, DryRunResult, OutPoint, Script, Transaction};
+use ckb_jsonrpc_types::{
+    Capacity, Cycle, DryRunResult, OutPoint, Script, ScriptGroupResult, ScriptGroupType,
+    Transaction, Unsigned,
+};
 use ckb_logger::error;
+use ckb_script::{ScriptGroupReport, ScriptGroupType as CoreScriptGroupType};
 use ckb_shared::chain_state::ChainState;
 use ckb_shared::shared::Shared;
 use ckb_store::ChainStore;
-use ckb_verification::ScriptVerifier;
+use ckb_verification::{ScriptVerifier, TransactionError};
 use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
 use numext_fixed_hash::H256;
@@ -111,16 +115,60 @@ impl<'a> DryRunner<'a> {
                 let consensus = self.chain_state.consensus();
                 let max_cycles = consensus.max_block_cycles;
                 let script_config = self.chain_state.script_config();
-                match ScriptVerifier::new(&resolved, self.chain_state.store(), script_config)
-                    .verify(max_cycles)
-                {
-                    Ok(cycles) => Ok(DryRunResult {
+                let reports =
+                    ScriptVerifier::new(&resolved, self.chain_state.store(), script_config)
+                        .verify_script_groups(max_cycles)
+                        .map_err(|err| RPCError::custom(RPCError::Invalid, format!("{:?}", err)))?;
+                let cycles = reports.iter().map(|report| report.cycles).sum();
+                let error = reports.iter().find_map(|report| report.error);
+                let script_groups: Vec<ScriptGroupResult> =
+                    reports.into_iter().map(script_group_result).collect();
+                match error {
+                    Some(err) => Err(RPCError::custom_with_data(
+                        RPCError::Invalid,
+                        format!("{:?}", TransactionError::ScriptFailure(err)),
+                        // reports all the script groups to tell which ones failed
+                        serde_json::to_value(script_groups).expect("serialize script groups"),
+                    )),
+                    None => Ok(DryRunResult {
                         cycles: Cycle(cycles),
+                        script_groups,
                     }),
-                    Err(err) => Err(RPCError::custom(RPCError::Invalid, format!("{:?}", err))),
                 }
             }
             Err(err) => Err(RPCError::custom(RPCError::Invalid, format!("{:?}", err))),
         }
     }
 }
+
+fn script_group_result(report: ScriptGroupReport) -> ScriptGroupResult {
+    let ScriptGroupReport {
+        script_hash,
+        group_type,
+        input_indices,
+        output_indices,
+        cycles,
+        exit_code,
+        error,
+        debug_messages,
+    } = report;
+    ScriptGroupResult {
+        script_hash,
+        group_type: match group_type {
+            CoreScriptGroupType::Lock => ScriptGroupType::Lock,
+            CoreScriptGroupType::Type => ScriptGroupType::Type,
+        },
+        input_indices: input_indices
+            .into_iter()
+            .map(|index| Unsigned(index as u64))
+            .collect(),
+        output_indices: output_indices
+            .into_iter()
+            .map(|index| Unsigned(index as u64))
+            .collect(),
+        cycles: Cycle(cycles),
+        exit_code,
+        error: error.map(|err| format!("{:?}", err)),
+        debug_messages,
+    }
+}
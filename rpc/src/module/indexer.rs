@@ -1,8 +1,32 @@
-use ckb_indexer::IndexerStore;
-use ckb_jsonrpc_types::{BlockNumber, CellTransaction, LiveCell, LockHashIndexState, Unsigned};
+use crate::error::RPCError;
+use crate::module::subscription::try_notify;
+use crate::RpcMetadata;
+use ckb_indexer::{
+    DefaultIndexerStore, IndexerStore, LockHashIndex, LockPattern as CoreLockPattern,
+    LockPatternIndex, TransactionPoint, TypeHashIndex,
+};
+use ckb_jsonrpc_types::{
+    BlockNumber, Capacity, CellTransactionsPage, IndexerEvent, JsonBytes, LiveCellFilter,
+    LiveCellsPage, LockHashCapacity, LockHashIndexState, LockPattern, LockPatternIndexState,
+    TypeHashIndexState, Unsigned,
+};
+use ckb_logger::debug;
+use ckb_util::{Mutex, RwLock};
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::typed::{Sink, Subscriber};
+use jsonrpc_pubsub::SubscriptionId;
 use numext_fixed_hash::H256;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+// cursor is the serialized `LockHashIndex`, `TypeHashIndex` or `LockPatternIndex` key:
+// hash (32 bytes) | block number (8 bytes) | tx hash (32 bytes) | index (4 bytes)
+const CURSOR_LENGTH: usize = 76;
+// The larger `per_page` is capped to it
+const MAX_PER_PAGE: usize = 50;
 
 #[rpc]
 pub trait IndexerRpc {
@@ -10,19 +34,20 @@ pub trait IndexerRpc {
     fn get_live_cells_by_lock_hash(
         &self,
         _lock_hash: H256,
-        _page: Unsigned,
         _per_page: Unsigned,
         _reverse_order: Option<bool>,
-    ) -> Result<Vec<LiveCell>>;
+        _cursor: Option<JsonBytes>,
+        _filter: Option<LiveCellFilter>,
+    ) -> Result<LiveCellsPage>;
 
     #[rpc(name = "get_transactions_by_lock_hash")]
     fn get_transactions_by_lock_hash(
         &self,
         _lock_hash: H256,
-        _page: Unsigned,
         _per_page: Unsigned,
         _reverse_order: Option<bool>,
-    ) -> Result<Vec<CellTransaction>>;
+        _cursor: Option<JsonBytes>,
+    ) -> Result<CellTransactionsPage>;
 
     #[rpc(name = "index_lock_hash")]
     fn index_lock_hash(
@@ -36,6 +61,99 @@ pub trait IndexerRpc {
 
     #[rpc(name = "get_lock_hash_index_states")]
     fn get_lock_hash_index_states(&self) -> Result<Vec<LockHashIndexState>>;
+
+    #[rpc(name = "get_capacity_by_lock_hash")]
+    fn get_capacity_by_lock_hash(&self, _lock_hash: H256) -> Result<Option<LockHashCapacity>>;
+
+    #[rpc(name = "get_live_cells_by_type_hash")]
+    fn get_live_cells_by_type_hash(
+        &self,
+        _type_hash: H256,
+        _per_page: Unsigned,
+        _reverse_order: Option<bool>,
+        _cursor: Option<JsonBytes>,
+        _filter: Option<LiveCellFilter>,
+    ) -> Result<LiveCellsPage>;
+
+    #[rpc(name = "get_transactions_by_type_hash")]
+    fn get_transactions_by_type_hash(
+        &self,
+        _type_hash: H256,
+        _per_page: Unsigned,
+        _reverse_order: Option<bool>,
+        _cursor: Option<JsonBytes>,
+    ) -> Result<CellTransactionsPage>;
+
+    #[rpc(name = "index_type_hash")]
+    fn index_type_hash(
+        &self,
+        _type_hash: H256,
+        _index_from: Option<BlockNumber>,
+    ) -> Result<TypeHashIndexState>;
+
+    #[rpc(name = "deindex_type_hash")]
+    fn deindex_type_hash(&self, _type_hash: H256) -> Result<()>;
+
+    #[rpc(name = "get_type_hash_index_states")]
+    fn get_type_hash_index_states(&self) -> Result<Vec<TypeHashIndexState>>;
+
+    #[rpc(name = "get_live_cells_by_lock_pattern")]
+    fn get_live_cells_by_lock_pattern(
+        &self,
+        _pattern_hash: H256,
+        _per_page: Unsigned,
+        _reverse_order: Option<bool>,
+        _cursor: Option<JsonBytes>,
+        _filter: Option<LiveCellFilter>,
+    ) -> Result<LiveCellsPage>;
+
+    #[rpc(name = "get_transactions_by_lock_pattern")]
+    fn get_transactions_by_lock_pattern(
+        &self,
+        _pattern_hash: H256,
+        _per_page: Unsigned,
+        _reverse_order: Option<bool>,
+        _cursor: Option<JsonBytes>,
+    ) -> Result<CellTransactionsPage>;
+
+    #[rpc(name = "index_lock_pattern")]
+    fn index_lock_pattern(
+        &self,
+        _lock_pattern: LockPattern,
+        _index_from: Option<BlockNumber>,
+    ) -> Result<LockPatternIndexState>;
+
+    #[rpc(name = "deindex_lock_pattern")]
+    fn deindex_lock_pattern(&self, _pattern_hash: H256) -> Result<()>;
+
+    #[rpc(name = "get_lock_pattern_index_states")]
+    fn get_lock_pattern_index_states(&self) -> Result<Vec<LockPatternIndexState>>;
+}
+
+// Pushes the events of an indexed lock hash to the client, only available on the transports
+// which support subscription, such as WebSocket
+#[rpc]
+pub trait IndexerSubscriptionRpc {
+    type Metadata;
+
+    #[pubsub(
+        subscription = "indexer_event",
+        subscribe,
+        name = "subscribe_lock_hash"
+    )]
+    fn subscribe_lock_hash(
+        &self,
+        _meta: Self::Metadata,
+        _subscriber: Subscriber<IndexerEvent>,
+        _lock_hash: H256,
+    );
+
+    #[pubsub(
+        subscription = "indexer_event",
+        unsubscribe,
+        name = "unsubscribe_lock_hash"
+    )]
+    fn unsubscribe_lock_hash(&self, _id: SubscriptionId) -> Result<bool>;
 }
 
 pub(crate) struct IndexerRpcImpl<WS> {
@@ -46,43 +164,62 @@ impl<WS: IndexerStore + 'static> IndexerRpc for IndexerRpcImpl<WS> {
     fn get_live_cells_by_lock_hash(
         &self,
         lock_hash: H256,
-        page: Unsigned,
         per_page: Unsigned,
         reverse_order: Option<bool>,
-    ) -> Result<Vec<LiveCell>> {
-        let per_page = (per_page.0 as usize).min(50);
-        Ok(self
-            .store
-            .get_live_cells(
-                &lock_hash,
-                (page.0 as usize).saturating_mul(per_page),
-                per_page,
-                reverse_order.unwrap_or_default(),
-            )
-            .into_iter()
-            .map(Into::into)
-            .collect())
+        cursor: Option<JsonBytes>,
+        filter: Option<LiveCellFilter>,
+    ) -> Result<LiveCellsPage> {
+        let per_page = (per_page.0 as usize).min(MAX_PER_PAGE);
+        let cursor = parse_cursor(&lock_hash, cursor)?.map(|key| LockHashIndex::from_slice(&key));
+        let filter = filter.map(Into::into);
+        let live_cells = self.store.get_live_cells(
+            &lock_hash,
+            cursor.as_ref(),
+            filter.as_ref(),
+            per_page,
+            reverse_order.unwrap_or_default(),
+        );
+        // a partial page is the last one
+        let cursor = if live_cells.len() < per_page {
+            None
+        } else {
+            live_cells
+                .last()
+                .map(|live_cell| lock_hash_cursor(&lock_hash, &live_cell.created_by))
+        };
+        Ok(LiveCellsPage {
+            live_cells: live_cells.into_iter().map(Into::into).collect(),
+            cursor,
+        })
     }
 
     fn get_transactions_by_lock_hash(
         &self,
         lock_hash: H256,
-        page: Unsigned,
         per_page: Unsigned,
         reverse_order: Option<bool>,
-    ) -> Result<Vec<CellTransaction>> {
-        let per_page = (per_page.0 as usize).min(50);
-        Ok(self
-            .store
-            .get_transactions(
-                &lock_hash,
-                (page.0 as usize).saturating_mul(per_page),
-                per_page,
-                reverse_order.unwrap_or_default(),
-            )
-            .into_iter()
-            .map(Into::into)
-            .collect())
+        cursor: Option<JsonBytes>,
+    ) -> Result<CellTransactionsPage> {
+        let per_page = (per_page.0 as usize).min(MAX_PER_PAGE);
+        let cursor = parse_cursor(&lock_hash, cursor)?.map(|key| LockHashIndex::from_slice(&key));
+        let transactions = self.store.get_transactions(
+            &lock_hash,
+            cursor.as_ref(),
+            per_page,
+            reverse_order.unwrap_or_default(),
+        );
+        // a partial page is the last one
+        let cursor = if transactions.len() < per_page {
+            None
+        } else {
+            transactions
+                .last()
+                .map(|transaction| lock_hash_cursor(&lock_hash, &transaction.created_by))
+        };
+        Ok(CellTransactionsPage {
+            transactions: transactions.into_iter().map(Into::into).collect(),
+            cursor,
+        })
     }
 
     fn index_lock_hash(
@@ -118,4 +255,359 @@ impl<WS: IndexerStore + 'static> IndexerRpc for IndexerRpcImpl<WS> {
             .collect();
         Ok(states)
     }
+
+    fn get_capacity_by_lock_hash(&self, lock_hash: H256) -> Result<Option<LockHashCapacity>> {
+        Ok(self
+            .store
+            .get_capacity(&lock_hash)
+            .map(|lock_hash_capacity| LockHashCapacity {
+                capacity: Capacity(lock_hash_capacity.capacity),
+                cells_count: Unsigned(lock_hash_capacity.cells_count),
+            }))
+    }
+
+    fn get_live_cells_by_type_hash(
+        &self,
+        type_hash: H256,
+        per_page: Unsigned,
+        reverse_order: Option<bool>,
+        cursor: Option<JsonBytes>,
+        filter: Option<LiveCellFilter>,
+    ) -> Result<LiveCellsPage> {
+        let per_page = (per_page.0 as usize).min(MAX_PER_PAGE);
+        let cursor = parse_cursor(&type_hash, cursor)?.map(|key| TypeHashIndex::from_slice(&key));
+        let filter = filter.map(Into::into);
+        let live_cells = self.store.get_live_cells_by_type_hash(
+            &type_hash,
+            cursor.as_ref(),
+            filter.as_ref(),
+            per_page,
+            reverse_order.unwrap_or_default(),
+        );
+        // a partial page is the last one
+        let cursor = if live_cells.len() < per_page {
+            None
+        } else {
+            live_cells
+                .last()
+                .map(|live_cell| type_hash_cursor(&type_hash, &live_cell.created_by))
+        };
+        Ok(LiveCellsPage {
+            live_cells: live_cells.into_iter().map(Into::into).collect(),
+            cursor,
+        })
+    }
+
+    fn get_transactions_by_type_hash(
+        &self,
+        type_hash: H256,
+        per_page: Unsigned,
+        reverse_order: Option<bool>,
+        cursor: Option<JsonBytes>,
+    ) -> Result<CellTransactionsPage> {
+        let per_page = (per_page.0 as usize).min(MAX_PER_PAGE);
+        let cursor = parse_cursor(&type_hash, cursor)?.map(|key| TypeHashIndex::from_slice(&key));
+        let transactions = self.store.get_transactions_by_type_hash(
+            &type_hash,
+            cursor.as_ref(),
+            per_page,
+            reverse_order.unwrap_or_default(),
+        );
+        // a partial page is the last one
+        let cursor = if transactions.len() < per_page {
+            None
+        } else {
+            transactions
+                .last()
+                .map(|transaction| type_hash_cursor(&type_hash, &transaction.created_by))
+        };
+        Ok(CellTransactionsPage {
+            transactions: transactions.into_iter().map(Into::into).collect(),
+            cursor,
+        })
+    }
+
+    fn index_type_hash(
+        &self,
+        type_hash: H256,
+        index_from: Option<BlockNumber>,
+    ) -> Result<TypeHashIndexState> {
+        let state = self
+            .store
+            .insert_type_hash(&type_hash, index_from.map(|number| number.0));
+        Ok(TypeHashIndexState {
+            type_hash,
+            block_number: BlockNumber(state.block_number),
+            block_hash: state.block_hash,
+        })
+    }
+
+    fn deindex_type_hash(&self, type_hash: H256) -> Result<()> {
+        self.store.remove_type_hash(&type_hash);
+        Ok(())
+    }
+
+    fn get_type_hash_index_states(&self) -> Result<Vec<TypeHashIndexState>> {
+        let states = self
+            .store
+            .get_type_hash_index_states()
+            .into_iter()
+            .map(|(type_hash, state)| TypeHashIndexState {
+                type_hash,
+                block_number: BlockNumber(state.block_number),
+                block_hash: state.block_hash,
+            })
+            .collect();
+        Ok(states)
+    }
+
+    fn get_live_cells_by_lock_pattern(
+        &self,
+        pattern_hash: H256,
+        per_page: Unsigned,
+        reverse_order: Option<bool>,
+        cursor: Option<JsonBytes>,
+        filter: Option<LiveCellFilter>,
+    ) -> Result<LiveCellsPage> {
+        let per_page = (per_page.0 as usize).min(MAX_PER_PAGE);
+        let cursor =
+            parse_cursor(&pattern_hash, cursor)?.map(|key| LockPatternIndex::from_slice(&key));
+        let filter = filter.map(Into::into);
+        let live_cells = self.store.get_live_cells_by_lock_pattern(
+            &pattern_hash,
+            cursor.as_ref(),
+            filter.as_ref(),
+            per_page,
+            reverse_order.unwrap_or_default(),
+        );
+        // a partial page is the last one
+        let cursor = if live_cells.len() < per_page {
+            None
+        } else {
+            live_cells
+                .last()
+                .map(|live_cell| lock_pattern_cursor(&pattern_hash, &live_cell.created_by))
+        };
+        Ok(LiveCellsPage {
+            live_cells: live_cells.into_iter().map(Into::into).collect(),
+            cursor,
+        })
+    }
+
+    fn get_transactions_by_lock_pattern(
+        &self,
+        pattern_hash: H256,
+        per_page: Unsigned,
+        reverse_order: Option<bool>,
+        cursor: Option<JsonBytes>,
+    ) -> Result<CellTransactionsPage> {
+        let per_page = (per_page.0 as usize).min(MAX_PER_PAGE);
+        let cursor =
+            parse_cursor(&pattern_hash, cursor)?.map(|key| LockPatternIndex::from_slice(&key));
+        let transactions = self.store.get_transactions_by_lock_pattern(
+            &pattern_hash,
+            cursor.as_ref(),
+            per_page,
+            reverse_order.unwrap_or_default(),
+        );
+        // a partial page is the last one
+        let cursor = if transactions.len() < per_page {
+            None
+        } else {
+            transactions
+                .last()
+                .map(|transaction| lock_pattern_cursor(&pattern_hash, &transaction.created_by))
+        };
+        Ok(CellTransactionsPage {
+            transactions: transactions.into_iter().map(Into::into).collect(),
+            cursor,
+        })
+    }
+
+    fn index_lock_pattern(
+        &self,
+        lock_pattern: LockPattern,
+        index_from: Option<BlockNumber>,
+    ) -> Result<LockPatternIndexState> {
+        let core_lock_pattern: CoreLockPattern = lock_pattern.clone().into();
+        let state = self
+            .store
+            .insert_lock_pattern(&core_lock_pattern, index_from.map(|number| number.0));
+        Ok(LockPatternIndexState {
+            pattern_hash: core_lock_pattern.hash(),
+            lock_pattern,
+            block_number: BlockNumber(state.block_number),
+            block_hash: state.block_hash,
+        })
+    }
+
+    fn deindex_lock_pattern(&self, pattern_hash: H256) -> Result<()> {
+        self.store.remove_lock_pattern(&pattern_hash);
+        Ok(())
+    }
+
+    fn get_lock_pattern_index_states(&self) -> Result<Vec<LockPatternIndexState>> {
+        let mut lock_patterns = self.store.get_lock_patterns();
+        let states = self
+            .store
+            .get_lock_pattern_index_states()
+            .into_iter()
+            .filter_map(|(pattern_hash, state)| {
+                lock_patterns
+                    .remove(&pattern_hash)
+                    .map(|lock_pattern| LockPatternIndexState {
+                        pattern_hash,
+                        lock_pattern: lock_pattern.into(),
+                        block_number: BlockNumber(state.block_number),
+                        block_hash: state.block_hash,
+                    })
+            })
+            .collect();
+        Ok(states)
+    }
+}
+
+// Distinguishes the subscribers of the instances, `subscribe` replaces the one with the same name
+static NEXT_SUBSCRIBER_ID: AtomicUsize = AtomicUsize::new(0);
+
+type IndexerSinks = Arc<RwLock<HashMap<SubscriptionId, (H256, Arc<Mutex<Sink<IndexerEvent>>>)>>>;
+
+pub(crate) struct IndexerSubscriptionRpcImpl {
+    store: DefaultIndexerStore,
+    sinks: IndexerSinks,
+    next_id: AtomicUsize,
+}
+
+impl IndexerSubscriptionRpcImpl {
+    pub fn new(store: DefaultIndexerStore) -> Self {
+        let subscriber_name = format!(
+            "IndexerSubscriptionRpc-{}",
+            NEXT_SUBSCRIBER_ID.fetch_add(1, Ordering::SeqCst)
+        );
+        let receiver = store.subscribe(&subscriber_name);
+        let sinks: IndexerSinks = Default::default();
+        let thread_sinks = Arc::clone(&sinks);
+        thread::Builder::new()
+            .name("IndexerSubscriptionRpc".to_string())
+            .spawn(move || {
+                for event in receiver.iter() {
+                    // clones the sinks out, so that the subscriptions are not blocked by the
+                    // sending
+                    let event_sinks: Vec<_> = thread_sinks
+                        .read()
+                        .iter()
+                        .filter(|(_, (lock_hash, _))| lock_hash == event.lock_hash())
+                        .map(|(id, (_, sink))| (id.clone(), Arc::clone(sink)))
+                        .collect();
+                    let closed_ids: Vec<_> = event_sinks
+                        .into_iter()
+                        .filter(|(id, sink)| !try_notify(id, sink, event.clone().into()))
+                        .map(|(id, _)| id)
+                        .collect();
+                    if !closed_ids.is_empty() {
+                        let mut sinks = thread_sinks.write();
+                        for id in closed_ids {
+                            debug!("Indexer subscription {:?} is closed", id);
+                            sinks.remove(&id);
+                        }
+                    }
+                }
+            })
+            .expect("Start IndexerSubscriptionRpc failed");
+
+        IndexerSubscriptionRpcImpl {
+            store,
+            sinks,
+            next_id: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl IndexerSubscriptionRpc for IndexerSubscriptionRpcImpl {
+    type Metadata = RpcMetadata;
+
+    fn subscribe_lock_hash(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<IndexerEvent>,
+        lock_hash: H256,
+    ) {
+        if !self
+            .store
+            .get_lock_hash_index_states()
+            .contains_key(&lock_hash)
+        {
+            let _ = subscriber.reject(RPCError::custom(
+                RPCError::Invalid,
+                format!("lock hash {:#x} is not indexed", lock_hash),
+            ));
+            return;
+        }
+
+        let id = SubscriptionId::Number(self.next_id.fetch_add(1, Ordering::SeqCst) as u64);
+        if let Ok(sink) = subscriber.assign_id(id.clone()) {
+            self.sinks
+                .write()
+                .insert(id, (lock_hash, Arc::new(Mutex::new(sink))));
+        }
+    }
+
+    fn unsubscribe_lock_hash(&self, id: SubscriptionId) -> Result<bool> {
+        if self.sinks.write().remove(&id).is_some() {
+            Ok(true)
+        } else {
+            Err(RPCError::custom(
+                RPCError::Invalid,
+                "invalid subscription id".to_owned(),
+            ))
+        }
+    }
+}
+
+// a cursor is only valid for the queried hash, reject the one generated by other queries
+fn parse_cursor(hash: &H256, cursor: Option<JsonBytes>) -> Result<Option<Vec<u8>>> {
+    match cursor {
+        Some(cursor) => {
+            let key = cursor.as_bytes();
+            if key.len() != CURSOR_LENGTH || !key.starts_with(hash.as_bytes()) {
+                Err(RPCError::custom(
+                    RPCError::Invalid,
+                    "invalid cursor".to_owned(),
+                ))
+            } else {
+                Ok(Some(key.to_vec()))
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+fn lock_hash_cursor(lock_hash: &H256, created_by: &TransactionPoint) -> JsonBytes {
+    let lock_hash_index = LockHashIndex::new(
+        lock_hash.to_owned(),
+        created_by.block_number,
+        created_by.tx_hash.to_owned(),
+        created_by.index,
+    );
+    JsonBytes::from_vec(lock_hash_index.to_vec())
+}
+
+fn type_hash_cursor(type_hash: &H256, created_by: &TransactionPoint) -> JsonBytes {
+    let type_hash_index = TypeHashIndex::new(
+        type_hash.to_owned(),
+        created_by.block_number,
+        created_by.tx_hash.to_owned(),
+        created_by.index,
+    );
+    JsonBytes::from_vec(type_hash_index.to_vec())
+}
+
+fn lock_pattern_cursor(pattern_hash: &H256, created_by: &TransactionPoint) -> JsonBytes {
+    let lock_pattern_index = LockPatternIndex::new(
+        pattern_hash.to_owned(),
+        created_by.block_number,
+        created_by.tx_hash.to_owned(),
+        created_by.index,
+    );
+    JsonBytes::from_vec(lock_pattern_index.to_vec())
 }
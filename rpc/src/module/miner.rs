@@ -15,7 +15,47 @@ use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
 use numext_fixed_hash::H256;
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
+
+// The max time to wait for the template to change when long polling
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Caps the concurrent long polls, each of which blocks a server thread until the template is
+// changed, so that there are always threads left for the other requests
+#[derive(Clone)]
+pub(crate) struct LongPolls {
+    pending: Arc<AtomicUsize>,
+    max: usize,
+}
+
+impl LongPolls {
+    pub fn new(max: usize) -> Self {
+        LongPolls {
+            pending: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    // Returns None if there are already `max` pending long polls
+    fn acquire(&self) -> Option<LongPollGuard> {
+        let guard = LongPollGuard(Arc::clone(&self.pending));
+        if self.pending.fetch_add(1, Ordering::SeqCst) < self.max {
+            Some(guard)
+        } else {
+            None
+        }
+    }
+}
+
+struct LongPollGuard(Arc<AtomicUsize>);
+
+impl Drop for LongPollGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
 
 #[rpc]
 pub trait MinerRpc {
@@ -26,6 +66,7 @@ pub trait MinerRpc {
         bytes_limit: Option<Unsigned>,
         proposals_limit: Option<Unsigned>,
         max_version: Option<Version>,
+        long_poll_id: Option<String>,
     ) -> Result<BlockTemplate>;
 
     // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"submit_block","params": [{"header":{}, "uncles":[], "transactions":[], "proposals":[]}]}' -H 'content-type:application/json' 'http://localhost:8114'
@@ -38,6 +79,7 @@ pub(crate) struct MinerRpcImpl {
     pub shared: Shared,
     pub block_assembler: BlockAssemblerController,
     pub chain: ChainController,
+    pub long_polls: LongPolls,
 }
 
 impl MinerRpc for MinerRpcImpl {
@@ -46,6 +88,7 @@ impl MinerRpc for MinerRpcImpl {
         bytes_limit: Option<Unsigned>,
         proposals_limit: Option<Unsigned>,
         max_version: Option<Version>,
+        long_poll_id: Option<String>,
     ) -> Result<BlockTemplate> {
         let bytes_limit = match bytes_limit {
             Some(b) => Some(b.0),
@@ -57,12 +100,28 @@ impl MinerRpc for MinerRpcImpl {
             None => None,
         };
 
-        self.block_assembler
-            .get_block_template(bytes_limit, proposals_limit, max_version.map(|v| v.0))
-            .map_err(|err| {
-                error!("get_block_template error {}", err);
-                Error::internal_error()
-            })
+        let max_version = max_version.map(|v| v.0);
+        // blocks until the template is changed from the one of `long_poll_id`, the current template
+        // is returned at once when there are too many pending long polls
+        let long_poll =
+            long_poll_id.and_then(|id| self.long_polls.acquire().map(|guard| (id, guard)));
+        match long_poll {
+            Some((long_poll_id, _guard)) => self.block_assembler.wait_block_template(
+                bytes_limit,
+                proposals_limit,
+                max_version,
+                &long_poll_id,
+                LONG_POLL_TIMEOUT,
+            ),
+            None => {
+                self.block_assembler
+                    .get_block_template(bytes_limit, proposals_limit, max_version)
+            }
+        }
+        .map_err(|err| {
+            error!("get_block_template error {}", err);
+            Error::internal_error()
+        })
     }
 
     fn submit_block(&self, work_id: String, data: Block) -> Result<Option<H256>> {
@@ -1,19 +1,27 @@
 mod alert;
 mod chain;
+mod discover;
 mod experiment;
 mod indexer;
 mod miner;
 mod net;
 mod pool;
 mod stats;
+mod subscription;
 mod test;
 
+pub use self::discover::openrpc_document;
+
 pub(crate) use self::alert::{AlertRpc, AlertRpcImpl};
 pub(crate) use self::chain::{ChainRpc, ChainRpcImpl};
+pub(crate) use self::discover::{DiscoverRpc, DiscoverRpcImpl};
 pub(crate) use self::experiment::{ExperimentRpc, ExperimentRpcImpl};
-pub(crate) use self::indexer::{IndexerRpc, IndexerRpcImpl};
-pub(crate) use self::miner::{MinerRpc, MinerRpcImpl};
+pub(crate) use self::indexer::{
+    IndexerRpc, IndexerRpcImpl, IndexerSubscriptionRpc, IndexerSubscriptionRpcImpl,
+};
+pub(crate) use self::miner::{LongPolls, MinerRpc, MinerRpcImpl};
 pub(crate) use self::net::{NetworkRpc, NetworkRpcImpl};
 pub(crate) use self::pool::{PoolRpc, PoolRpcImpl};
 pub(crate) use self::stats::{StatsRpc, StatsRpcImpl};
+pub(crate) use self::subscription::{SubscriptionRpc, SubscriptionRpcImpl};
 pub(crate) use self::test::{IntegrationTestRpc, IntegrationTestRpcImpl};
@@ -1,10 +1,15 @@
 use crate::error::RPCError;
+use ckb_core::transaction::ProposalShortId;
 use ckb_core::transaction::Transaction as CoreTransaction;
-use ckb_jsonrpc_types::{Timestamp, Transaction, TxPoolInfo, Unsigned};
+use ckb_jsonrpc_types::{
+    Capacity, EstimateResult, PoolTransaction, PoolTransactionStatus, RawTxPool, Timestamp,
+    Transaction, TxPoolEntry, TxPoolInfo, Unsigned,
+};
 use ckb_logger::error;
 use ckb_network::NetworkController;
 use ckb_protocol::RelayMessage;
 use ckb_shared::shared::Shared;
+use ckb_shared::tx_pool::{TxEntryInfo, TxStatus, MAX_CONFIRM_BLOCKS};
 use ckb_sync::NetworkProtocol;
 use ckb_tx_pool_executor::TxPoolExecutor;
 use flatbuffers::FlatBufferBuilder;
@@ -19,9 +24,25 @@ pub trait PoolRpc {
     #[rpc(name = "send_transaction")]
     fn send_transaction(&self, _tx: Transaction) -> Result<H256>;
 
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"send_transactions","params": [[{"version":2, "deps":[], "inputs":[], "outputs":[]}]]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "send_transactions")]
+    fn send_transactions(&self, _txs: Vec<Transaction>) -> Result<Vec<H256>>;
+
     // curl -d '{"params": [], "method": "tx_pool_info", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
     #[rpc(name = "tx_pool_info")]
     fn tx_pool_info(&self) -> Result<TxPoolInfo>;
+
+    // curl -d '{"params": ["3"], "method": "estimate_fee_rate", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
+    #[rpc(name = "estimate_fee_rate")]
+    fn estimate_fee_rate(&self, _expect_confirm_blocks: Unsigned) -> Result<EstimateResult>;
+
+    // curl -d '{"params": [], "method": "get_raw_tx_pool", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
+    #[rpc(name = "get_raw_tx_pool")]
+    fn get_raw_tx_pool(&self) -> Result<RawTxPool>;
+
+    // curl -d '{"params": ["0xa0ef4eb5f4ceeb08a4c8524d84c5da95dce2f608e0ca2ec8091191b0f330c6e3"], "method": "get_pool_transaction", "jsonrpc": "2.0", "id": 2}' -H 'content-type:application/json' http://localhost:8114
+    #[rpc(name = "get_pool_transaction")]
+    fn get_pool_transaction(&self, _hash: H256) -> Result<Option<PoolTransaction>>;
 }
 
 pub(crate) struct PoolRpcImpl {
@@ -67,6 +88,39 @@ impl PoolRpc for PoolRpcImpl {
         }
     }
 
+    fn send_transactions(&self, txs: Vec<Transaction>) -> Result<Vec<H256>> {
+        if txs.is_empty() {
+            return Err(RPCError::custom(
+                RPCError::Invalid,
+                "the package is empty".to_owned(),
+            ));
+        }
+        let txs: Vec<CoreTransaction> = txs.into_iter().map(Into::into).collect();
+
+        let result = self
+            .tx_pool_executor
+            .verify_and_add_package_to_pool(txs.clone());
+
+        match result {
+            Ok(cycles_vec) => {
+                let fbb = &mut FlatBufferBuilder::new();
+                let hashes = txs.iter().map(|tx| tx.hash().to_owned()).collect();
+                let relay_txs: Vec<_> = txs.into_iter().zip(cycles_vec).collect();
+                let message = RelayMessage::build_transactions(fbb, &relay_txs);
+                fbb.finish(message, None);
+                let data = fbb.finished_data().into();
+                if let Err(err) = self
+                    .network_controller
+                    .broadcast(NetworkProtocol::RELAY.into(), data)
+                {
+                    error!("Broadcast transactions failed: {:?}", err);
+                }
+                Ok(hashes)
+            }
+            Err(e) => Err(RPCError::custom(RPCError::Invalid, e.to_string())),
+        }
+    }
+
     fn tx_pool_info(&self) -> Result<TxPoolInfo> {
         let chain_state = self.shared.lock_chain_state();
         let tx_pool = chain_state.tx_pool();
@@ -77,6 +131,86 @@ impl PoolRpc for PoolRpcImpl {
             total_tx_size: Unsigned(tx_pool.total_tx_size() as u64),
             total_tx_cycles: Unsigned(tx_pool.total_tx_cycles()),
             last_txs_updated_at: Timestamp(chain_state.get_last_txs_updated_at()),
+            evicted: Unsigned(tx_pool.evicted_count()),
         })
     }
+
+    fn estimate_fee_rate(&self, expect_confirm_blocks: Unsigned) -> Result<EstimateResult> {
+        let target = expect_confirm_blocks.0;
+        if target == 0 || target > MAX_CONFIRM_BLOCKS {
+            return Err(RPCError::custom(
+                RPCError::Invalid,
+                format!(
+                    "expect_confirm_blocks should be between 1 and {}",
+                    MAX_CONFIRM_BLOCKS
+                ),
+            ));
+        }
+        match self.shared.lock_chain_state().estimate_fee_rate(target) {
+            Some(fee_rate) => Ok(EstimateResult {
+                fee_rate: Unsigned(fee_rate.as_u64()),
+            }),
+            None => Err(RPCError::custom(
+                RPCError::Invalid,
+                "insufficient data to estimate the fee rate".to_owned(),
+            )),
+        }
+    }
+
+    fn get_raw_tx_pool(&self) -> Result<RawTxPool> {
+        let chain_state = self.shared.lock_chain_state();
+        let mut raw_tx_pool = RawTxPool::default();
+        for (hash, info) in chain_state.tx_pool().entries_info() {
+            let entries = match info.status {
+                TxStatus::Pending => &mut raw_tx_pool.pending,
+                TxStatus::Gap => &mut raw_tx_pool.gap,
+                TxStatus::Proposed => &mut raw_tx_pool.proposed,
+                TxStatus::Orphan => &mut raw_tx_pool.orphan,
+                // the conflict txs are not listed
+                TxStatus::Conflict => continue,
+            };
+            entries.push(tx_pool_entry(hash, &info));
+        }
+        Ok(raw_tx_pool)
+    }
+
+    fn get_pool_transaction(&self, hash: H256) -> Result<Option<PoolTransaction>> {
+        let chain_state = self.shared.lock_chain_state();
+        let tx_pool = chain_state.tx_pool();
+        let id = ProposalShortId::from_tx_hash(&hash);
+        if let Some((tx, info)) = tx_pool.get_entry_info(&id) {
+            let (status, reason) = match info.status {
+                TxStatus::Pending => (PoolTransactionStatus::Pending, None),
+                TxStatus::Gap => (PoolTransactionStatus::Gap, None),
+                TxStatus::Proposed => (PoolTransactionStatus::Proposed, None),
+                TxStatus::Orphan => (PoolTransactionStatus::Orphan, None),
+                TxStatus::Conflict => (
+                    PoolTransactionStatus::Conflict,
+                    Some("the inputs are spent by the proposed or committed txs".to_owned()),
+                ),
+            };
+            return Ok(Some(PoolTransaction {
+                status,
+                transaction: Some((&tx).into()),
+                entry: Some(tx_pool_entry(hash, &info)),
+                reason,
+            }));
+        }
+        Ok(tx_pool.get_rejected(&hash).map(|err| PoolTransaction {
+            status: PoolTransactionStatus::Rejected,
+            transaction: None,
+            entry: None,
+            reason: Some(err.to_string()),
+        }))
+    }
+}
+
+fn tx_pool_entry(hash: H256, info: &TxEntryInfo) -> TxPoolEntry {
+    TxPoolEntry {
+        hash,
+        size: Unsigned(info.size as u64),
+        cycles: info.cycles.map(Unsigned),
+        fee: info.fee.map(Capacity),
+        timestamp: Timestamp(info.timestamp),
+    }
 }
@@ -1,4 +1,5 @@
-use ckb_jsonrpc_types::{AlertMessage, ChainInfo, EpochNumber, PeerState, Timestamp};
+use crate::middleware::RpcMetrics;
+use ckb_jsonrpc_types::{AlertMessage, ChainInfo, EpochNumber, PeerState, RpcStats, Timestamp};
 use ckb_network_alert::notifier::Notifier as AlertNotifier;
 use ckb_shared::shared::Shared;
 use ckb_sync::Synchronizer;
@@ -15,12 +16,17 @@ pub trait StatsRpc {
 
     #[rpc(name = "get_peers_state")]
     fn get_peers_state(&self) -> Result<Vec<PeerState>>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"get_rpc_stats","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "get_rpc_stats")]
+    fn get_rpc_stats(&self) -> Result<RpcStats>;
 }
 
 pub(crate) struct StatsRpcImpl {
     pub shared: Shared,
     pub synchronizer: Synchronizer,
     pub alert_notifier: Arc<Mutex<AlertNotifier>>,
+    pub metrics: Arc<RpcMetrics>,
 }
 
 impl StatsRpc for StatsRpcImpl {
@@ -66,4 +72,8 @@ impl StatsRpc for StatsRpcImpl {
             .map(|(peer, blocks)| PeerState::new(peer.value(), 0, blocks.len()))
             .collect())
     }
+
+    fn get_rpc_stats(&self) -> Result<RpcStats> {
+        Ok(self.metrics.stats())
+    }
 }
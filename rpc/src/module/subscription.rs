@@ -0,0 +1,218 @@
+use crate::error::RPCError;
+use crate::RpcMetadata;
+use ckb_jsonrpc_types::{BlockView, HeaderView, RejectedTransaction, TransactionView};
+use ckb_logger::{debug, error, warn};
+use ckb_notify::NotifyController;
+use ckb_util::{Mutex, RwLock};
+use crossbeam_channel::select;
+use jsonrpc_core::futures::{future, AsyncSink, Future, Sink as _};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::typed::{Sink, Subscriber};
+use jsonrpc_pubsub::SubscriptionId;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+const SUBSCRIBER_NAME: &str = "SubscriptionRpc";
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    NewTipHeader,
+    NewTipBlock,
+    NewTransaction,
+    RejectedTransaction,
+}
+
+// Pushes the events of the topic to the client as JSON strings, only available on the transports
+// which support subscription, such as WebSocket and TCP
+#[rpc]
+pub trait SubscriptionRpc {
+    type Metadata;
+
+    #[pubsub(subscription = "subscribe", subscribe, name = "subscribe")]
+    fn subscribe(&self, _meta: Self::Metadata, _subscriber: Subscriber<String>, _topic: Topic);
+
+    #[pubsub(subscription = "subscribe", unsubscribe, name = "unsubscribe")]
+    fn unsubscribe(&self, _id: SubscriptionId) -> Result<bool>;
+}
+
+type SubscriptionSinks =
+    Arc<RwLock<HashMap<Topic, HashMap<SubscriptionId, Arc<Mutex<Sink<String>>>>>>>;
+
+pub(crate) struct SubscriptionRpcImpl {
+    sinks: SubscriptionSinks,
+    next_id: AtomicUsize,
+}
+
+impl SubscriptionRpcImpl {
+    // remove `allow` tag when https://github.com/crossbeam-rs/crossbeam/issues/404 is solved
+    #[allow(clippy::zero_ptr, clippy::drop_copy)]
+    pub fn new(notify: &NotifyController) -> Self {
+        let new_tip_receiver = notify.subscribe_new_tip(SUBSCRIBER_NAME);
+        let new_transaction_receiver = notify.subscribe_new_transaction(SUBSCRIBER_NAME);
+        let rejected_transaction_receiver = notify.subscribe_rejected_transaction(SUBSCRIBER_NAME);
+
+        let sinks: SubscriptionSinks = Default::default();
+        let thread_sinks = Arc::clone(&sinks);
+        thread::Builder::new()
+            .name("SubscriptionRpc".to_string())
+            .spawn(move || loop {
+                select! {
+                    recv(new_tip_receiver) -> msg => match msg {
+                        Ok(block) => {
+                            let header: HeaderView = block.header().into();
+                            publish(&thread_sinks, Topic::NewTipHeader, &header);
+                            let block: BlockView = (&*block).into();
+                            publish(&thread_sinks, Topic::NewTipBlock, &block);
+                        }
+                        _ => {
+                            error!("new_tip_receiver closed");
+                            break;
+                        }
+                    },
+                    recv(new_transaction_receiver) -> msg => match msg {
+                        Ok(tx) => {
+                            let tx: TransactionView = (&*tx).into();
+                            publish(&thread_sinks, Topic::NewTransaction, &tx);
+                        }
+                        _ => {
+                            error!("new_transaction_receiver closed");
+                            break;
+                        }
+                    },
+                    recv(rejected_transaction_receiver) -> msg => match msg {
+                        Ok(rejected) => {
+                            let (tx, reason) = &*rejected;
+                            let rejected = RejectedTransaction {
+                                transaction: tx.into(),
+                                reason: reason.to_owned(),
+                            };
+                            publish(&thread_sinks, Topic::RejectedTransaction, &rejected);
+                        }
+                        _ => {
+                            error!("rejected_transaction_receiver closed");
+                            break;
+                        }
+                    },
+                }
+            })
+            .expect("Start SubscriptionRpc failed");
+
+        SubscriptionRpcImpl {
+            sinks,
+            next_id: AtomicUsize::new(0),
+        }
+    }
+}
+
+// Sends the event to the subscriber without blocking, the event is dropped if the buffer of the
+// sink is full, as the client does not read fast enough. Returns false if the sink is closed.
+pub(crate) fn try_notify<T: serde::Serialize>(
+    id: &SubscriptionId,
+    sink: &Mutex<Sink<T>>,
+    event: T,
+) -> bool {
+    // `start_send` parks the current task when the buffer is full, so it has to run in a task
+    match future::lazy(|| sink.lock().start_send(Ok(event))).wait() {
+        Ok(AsyncSink::Ready) => true,
+        Ok(AsyncSink::NotReady(_)) => {
+            warn!("Subscription {:?} is full, the event is dropped", id);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+// Sends the event to the subscribers of the topic, and removes the closed ones
+fn publish<T: serde::Serialize>(sinks: &SubscriptionSinks, topic: Topic, event: &T) {
+    // clones the sinks out, so that the subscriptions are not blocked by the sending
+    let topic_sinks: Vec<_> = match sinks.read().get(&topic) {
+        Some(topic_sinks) if !topic_sinks.is_empty() => topic_sinks
+            .iter()
+            .map(|(id, sink)| (id.clone(), Arc::clone(sink)))
+            .collect(),
+        _ => return,
+    };
+    let event = serde_json::to_string(event).expect("serialize subscription event");
+    let closed_ids: Vec<_> = topic_sinks
+        .into_iter()
+        .filter(|(id, sink)| !try_notify(id, sink, event.clone()))
+        .map(|(id, _)| id)
+        .collect();
+    if !closed_ids.is_empty() {
+        let mut sinks = sinks.write();
+        if let Some(topic_sinks) = sinks.get_mut(&topic) {
+            for id in closed_ids {
+                debug!("Subscription {:?} is closed", id);
+                topic_sinks.remove(&id);
+            }
+        }
+    }
+}
+
+impl SubscriptionRpc for SubscriptionRpcImpl {
+    type Metadata = RpcMetadata;
+
+    fn subscribe(&self, _meta: Self::Metadata, subscriber: Subscriber<String>, topic: Topic) {
+        let id = SubscriptionId::Number(self.next_id.fetch_add(1, Ordering::SeqCst) as u64);
+        if let Ok(sink) = subscriber.assign_id(id.clone()) {
+            self.sinks
+                .write()
+                .entry(topic)
+                .or_insert_with(HashMap::default)
+                .insert(id, Arc::new(Mutex::new(sink)));
+        }
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) -> Result<bool> {
+        let removed = self
+            .sinks
+            .write()
+            .values_mut()
+            .any(|topic_sinks| topic_sinks.remove(&id).is_some());
+        if removed {
+            Ok(true)
+        } else {
+            Err(RPCError::custom(
+                RPCError::Invalid,
+                "invalid subscription id".to_owned(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_core::block::BlockBuilder;
+    use ckb_notify::NotifyService;
+    use jsonrpc_core::futures::Stream;
+
+    #[test]
+    fn test_subscribe_new_tip_header() {
+        let notify = NotifyService::default().start(Some("subscription"));
+        let rpc = SubscriptionRpcImpl::new(&notify);
+        let (subscriber, _id_receiver, receiver) = Subscriber::new_test("subscribe");
+        rpc.subscribe(RpcMetadata::default(), subscriber, Topic::NewTipHeader);
+
+        let block = Arc::new(BlockBuilder::default().build());
+        notify.notify_new_tip(Arc::clone(&block));
+
+        let notification = receiver
+            .wait()
+            .next()
+            .expect("notification")
+            .expect("receive notification");
+        let notification: serde_json::Value =
+            serde_json::from_str(&notification).expect("parse notification");
+        let header: HeaderView = block.header().into();
+        assert_eq!(
+            notification["params"]["result"],
+            serde_json::to_string(&header).expect("serialize header")
+        );
+    }
+}
@@ -1,34 +1,168 @@
-use crate::config::Config;
-use jsonrpc_core::IoHandler;
+use crate::auth::AuthMiddleware;
+use crate::config::{AuthConfig, Config};
+use crate::middleware::RpcMiddleware;
+use crate::service_builder::RpcModules;
+use jsonrpc_core::{MetaIoHandler, Metadata};
+use jsonrpc_http_server::hyper::{Body, Request};
 use jsonrpc_http_server::{Server, ServerBuilder};
+use jsonrpc_pubsub::{PubSubMetadata, Session};
 use jsonrpc_server_utils::cors::AccessControlAllowOrigin;
 use jsonrpc_server_utils::hosts::DomainsValidation;
+use jsonrpc_tcp_server::{
+    RequestContext as TcpRequestContext, Server as TcpServer, ServerBuilder as TcpServerBuilder,
+};
+use jsonrpc_ws_server::{RequestContext, Server as WsServer, ServerBuilder as WsServerBuilder};
+use std::sync::Arc;
+
+#[derive(Clone, Default)]
+pub struct RpcMetadata {
+    // Only the requests from the transports which support subscription have a session
+    pub(crate) session: Option<Arc<Session>>,
+    // Identifies the caller for the rate limit
+    pub(crate) client: String,
+}
+
+impl Metadata for RpcMetadata {}
+
+impl PubSubMetadata for RpcMetadata {
+    fn session(&self) -> Option<Arc<Session>> {
+        self.session.clone()
+    }
+}
+
+pub type RpcIoHandler = MetaIoHandler<RpcMetadata, RpcMiddleware>;
 
 pub struct RpcServer {
     pub(crate) server: Server,
+    pub(crate) listeners: Vec<Server>,
+    pub(crate) ws_server: Option<WsServer>,
+    pub(crate) tcp_server: Option<TcpServer>,
 }
 
 impl RpcServer {
-    pub fn new(config: Config, io_handler: IoHandler) -> RpcServer {
-        let server = ServerBuilder::new(io_handler)
-            .cors(DomainsValidation::AllowOnly(vec![
-                AccessControlAllowOrigin::Null,
-                AccessControlAllowOrigin::Any,
-            ]))
-            .threads(config.threads.unwrap_or_else(num_cpus::get))
-            .max_request_body_size(config.max_request_body_size)
-            .start_http(
-                &config
-                    .listen_address
+    pub fn new(config: Config, modules: RpcModules) -> RpcServer {
+        assert!(
+            config.transports_authenticated(),
+            "The rpc WebSocket and TCP transports are not authenticated, they can not be enabled with auth"
+        );
+        assert!(
+            config.rate_limit_per_client(),
+            "The rpc HTTP clients can not be told apart, rate_limit requires trust_forwarded_for"
+        );
+
+        let io_handler = modules.io_handler(&config.modules);
+        let server = start_http(
+            &config,
+            &config.listen_address,
+            io_handler.clone(),
+            config.auth.as_ref(),
+        );
+        let listeners = config
+            .listeners
+            .iter()
+            .map(|listener| {
+                start_http(
+                    &config,
+                    &listener.listen_address,
+                    modules.io_handler(&listener.modules),
+                    listener.auth.as_ref(),
+                )
+            })
+            .collect();
+
+        let ws_server = config.ws_listen_address.as_ref().map(|ws_listen_address| {
+            WsServerBuilder::with_meta_extractor(io_handler.clone(), |context: &RequestContext| {
+                RpcMetadata {
+                    session: Some(Arc::new(Session::new(context.sender()))),
+                    client: format!("ws-{}", context.session_id),
+                }
+            })
+            .start(
+                &ws_listen_address
                     .parse()
-                    .expect("config listen_address parsed"),
+                    .expect("config ws_listen_address parsed"),
             )
-            .expect("Jsonrpc initialize");
+            .expect("Start Jsonrpc WebSocket service")
+        });
+
+        let tcp_server = config
+            .tcp_listen_address
+            .as_ref()
+            .map(|tcp_listen_address| {
+                TcpServerBuilder::with_meta_extractor(io_handler, |context: &TcpRequestContext| {
+                    RpcMetadata {
+                        session: Some(Arc::new(Session::new(context.sender.clone()))),
+                        client: context.peer_addr.ip().to_string(),
+                    }
+                })
+                .start(
+                    &tcp_listen_address
+                        .parse()
+                        .expect("config tcp_listen_address parsed"),
+                )
+                .expect("Start Jsonrpc TCP service")
+            });
 
-        RpcServer { server }
+        RpcServer {
+            server,
+            listeners,
+            ws_server,
+            tcp_server,
+        }
     }
 
     pub fn close(self) {
-        self.server.close()
+        self.server.close();
+        for listener in self.listeners {
+            listener.close();
+        }
+        if let Some(ws_server) = self.ws_server {
+            ws_server.close();
+        }
+        if let Some(tcp_server) = self.tcp_server {
+            tcp_server.close();
+        }
     }
 }
+
+fn start_http(
+    config: &Config,
+    listen_address: &str,
+    io_handler: RpcIoHandler,
+    auth: Option<&AuthConfig>,
+) -> Server {
+    // the requests not forwarded by the reverse proxy share the limit of the listener
+    let listener_client = format!("http-{}", listen_address);
+    let meta_extractor = move |request: &Request<Body>| RpcMetadata {
+        session: None,
+        client: forwarded_for(request).unwrap_or_else(|| listener_client.clone()),
+    };
+    let mut builder = ServerBuilder::with_meta_extractor(io_handler, meta_extractor)
+        .cors(DomainsValidation::AllowOnly(vec![
+            AccessControlAllowOrigin::Null,
+            AccessControlAllowOrigin::Any,
+        ]))
+        .threads(config.threads.unwrap_or_else(num_cpus::get))
+        .max_request_body_size(config.max_request_body_size);
+    if let Some(auth) = auth {
+        builder = builder.request_middleware(AuthMiddleware::new(auth));
+    }
+    builder
+        .start_http(
+            &listen_address
+                .parse()
+                .expect("config listen_address parsed"),
+        )
+        .expect("Jsonrpc initialize")
+}
+
+// The client address appended first by the reverse proxies
+fn forwarded_for(request: &Request<Body>) -> Option<String> {
+    request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|client| client.trim().to_owned())
+        .filter(|client| !client.is_empty())
+}
@@ -1,45 +1,124 @@
-use crate::config::Config;
+use crate::config::{Config, Module};
+use crate::middleware::{MeteredMethod, RpcMetrics, RpcMiddleware};
 use crate::module::{
-    AlertRpc, AlertRpcImpl, ChainRpc, ChainRpcImpl, ExperimentRpc, ExperimentRpcImpl, IndexerRpc,
-    IndexerRpcImpl, IntegrationTestRpc, IntegrationTestRpcImpl, MinerRpc, MinerRpcImpl, NetworkRpc,
-    NetworkRpcImpl, PoolRpc, PoolRpcImpl, StatsRpc, StatsRpcImpl,
+    openrpc_document, AlertRpc, AlertRpcImpl, ChainRpc, ChainRpcImpl, DiscoverRpc, DiscoverRpcImpl,
+    ExperimentRpc, ExperimentRpcImpl, IndexerRpc, IndexerRpcImpl, IndexerSubscriptionRpc,
+    IndexerSubscriptionRpcImpl, IntegrationTestRpc, IntegrationTestRpcImpl, LongPolls, MinerRpc,
+    MinerRpcImpl, NetworkRpc, NetworkRpcImpl, PoolRpc, PoolRpcImpl, StatsRpc, StatsRpcImpl,
+    SubscriptionRpc, SubscriptionRpcImpl,
 };
+use crate::{RpcIoHandler, RpcMetadata};
 use ckb_chain::chain::ChainController;
 use ckb_db::DBConfig;
 use ckb_indexer::DefaultIndexerStore;
+use ckb_logger::warn;
 use ckb_miner::BlockAssemblerController;
 use ckb_network::NetworkController;
 use ckb_network_alert::{notifier::Notifier as AlertNotifier, verifier::Verifier as AlertVerifier};
+use ckb_notify::NotifyController;
 use ckb_shared::shared::Shared;
 use ckb_sync::Synchronizer;
 use ckb_util::Mutex;
-use jsonrpc_core::IoHandler;
+use jsonrpc_core::RemoteProcedure;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+type RpcMethods = HashMap<String, RemoteProcedure<RpcMetadata>>;
+
+// The methods of the enabled modules, each listener serves the ones of its own modules
+#[derive(Clone)]
+pub struct RpcModules {
+    methods: Vec<(Module, RpcMethods)>,
+    middleware: RpcMiddleware,
+    discover_enable: bool,
+}
+
+impl RpcModules {
+    // The names of the methods of all the enabled modules
+    pub(crate) fn method_names(&self) -> impl Iterator<Item = &str> {
+        self.methods
+            .iter()
+            .flat_map(|(_, methods)| methods.keys().map(String::as_str))
+    }
+
+    // The `rpc_discover` of each listener only describes the methods served by the listener
+    pub fn io_handler(&self, modules: &[Module]) -> RpcIoHandler {
+        let mut io_handler = RpcIoHandler::with_middleware(self.middleware.clone());
+        let mut names = HashSet::new();
+        for (module, methods) in &self.methods {
+            if modules.contains(module) {
+                names.extend(methods.keys().cloned());
+                io_handler.extend_with(methods.clone());
+            }
+        }
+        if self.discover_enable {
+            let document =
+                openrpc_document(|module, method| module.is_none() || names.contains(method));
+            io_handler.extend_with(DiscoverRpcImpl { document }.to_delegate());
+        }
+        io_handler
+    }
+}
+
 pub struct ServiceBuilder<'a> {
     config: &'a Config,
-    io_handler: IoHandler,
+    metrics: Arc<RpcMetrics>,
+    modules: RpcModules,
 }
 
 impl<'a> ServiceBuilder<'a> {
     pub fn new(config: &'a Config) -> Self {
+        let metrics = Arc::new(RpcMetrics::default());
         Self {
             config,
-            io_handler: IoHandler::new(),
+            modules: RpcModules {
+                methods: Vec::new(),
+                middleware: RpcMiddleware::new(config, Arc::clone(&metrics)),
+                discover_enable: config.method_enable("rpc_discover"),
+            },
+            metrics,
         }
     }
+
+    // Adds the methods of the module except the ones disabled in the config, the calls of the
+    // methods are recorded in the metrics
+    fn extend_with<F>(&mut self, module: Module, methods: F)
+    where
+        F: Into<RpcMethods>,
+    {
+        let config = self.config;
+        let metrics = &self.metrics;
+        let methods = methods
+            .into()
+            .into_iter()
+            .filter(|(name, _)| config.method_enable(name))
+            .map(|(name, method)| {
+                let method = match method {
+                    RemoteProcedure::Method(method) => RemoteProcedure::Method(Arc::new(
+                        MeteredMethod::new(name.clone(), method, Arc::clone(metrics)),
+                    )),
+                    method => method,
+                };
+                (name, method)
+            })
+            .collect();
+        self.modules.methods.push((module, methods));
+    }
+
     pub fn enable_chain(mut self, shared: Shared) -> Self {
         if self.config.chain_enable() {
-            self.io_handler
-                .extend_with(ChainRpcImpl { shared }.to_delegate());
+            self.extend_with(Module::Chain, ChainRpcImpl { shared }.to_delegate());
         }
         self
     }
 
     pub fn enable_pool(mut self, shared: Shared, network_controller: NetworkController) -> Self {
         if self.config.pool_enable() {
-            self.io_handler
-                .extend_with(PoolRpcImpl::new(shared, network_controller).to_delegate());
+            self.extend_with(
+                Module::Pool,
+                PoolRpcImpl::new(shared, network_controller).to_delegate(),
+            );
         }
         self
     }
@@ -53,12 +132,18 @@ impl<'a> ServiceBuilder<'a> {
     ) -> Self {
         if let Some(block_assembler) = block_assembler {
             if self.config.miner_enable() {
-                self.io_handler.extend_with(
+                self.extend_with(
+                    Module::Miner,
                     MinerRpcImpl {
                         shared: shared.clone(),
                         block_assembler,
                         chain: chain.clone(),
                         network_controller: network_controller.clone(),
+                        // at most half of the server threads are blocked by the long polls
+                        long_polls: LongPolls::new(cmp::max(
+                            self.config.threads.unwrap_or_else(num_cpus::get) / 2,
+                            1,
+                        )),
                     }
                     .to_delegate(),
                 );
@@ -69,8 +154,10 @@ impl<'a> ServiceBuilder<'a> {
 
     pub fn enable_net(mut self, network_controller: NetworkController) -> Self {
         if self.config.net_enable() {
-            self.io_handler
-                .extend_with(NetworkRpcImpl { network_controller }.to_delegate());
+            self.extend_with(
+                Module::Net,
+                NetworkRpcImpl { network_controller }.to_delegate(),
+            );
         }
         self
     }
@@ -82,11 +169,13 @@ impl<'a> ServiceBuilder<'a> {
         alert_notifier: Arc<Mutex<AlertNotifier>>,
     ) -> Self {
         if self.config.stats_enable() {
-            self.io_handler.extend_with(
+            self.extend_with(
+                Module::Stats,
                 StatsRpcImpl {
                     shared,
                     synchronizer,
                     alert_notifier,
+                    metrics: Arc::clone(&self.metrics),
                 }
                 .to_delegate(),
             );
@@ -96,8 +185,10 @@ impl<'a> ServiceBuilder<'a> {
 
     pub fn enable_experiment(mut self, shared: Shared) -> Self {
         if self.config.experiment_enable() {
-            self.io_handler
-                .extend_with(ExperimentRpcImpl { shared }.to_delegate());
+            self.extend_with(
+                Module::Experiment,
+                ExperimentRpcImpl { shared }.to_delegate(),
+            );
         }
         self
     }
@@ -109,7 +200,8 @@ impl<'a> ServiceBuilder<'a> {
         chain: ChainController,
     ) -> Self {
         if self.config.integration_test_enable() {
-            self.io_handler.extend_with(
+            self.extend_with(
+                Module::IntegrationTest,
                 IntegrationTestRpcImpl {
                     shared,
                     network_controller,
@@ -128,7 +220,8 @@ impl<'a> ServiceBuilder<'a> {
         network_controller: NetworkController,
     ) -> Self {
         if self.config.alert_enable() {
-            self.io_handler.extend_with(
+            self.extend_with(
+                Module::Alert,
                 AlertRpcImpl::new(alert_verifier, alert_notifier, network_controller).to_delegate(),
             )
         }
@@ -140,13 +233,33 @@ impl<'a> ServiceBuilder<'a> {
             let store = DefaultIndexerStore::new(db_config, shared);
             store.clone().start(Some("IndexerStore"));
 
-            self.io_handler
-                .extend_with(IndexerRpcImpl { store }.to_delegate())
+            self.extend_with(
+                Module::Indexer,
+                IndexerSubscriptionRpcImpl::new(store.clone()).to_delegate(),
+            );
+            self.extend_with(Module::Indexer, IndexerRpcImpl { store }.to_delegate())
+        }
+        self
+    }
+
+    pub fn enable_subscription(mut self, notify: &NotifyController) -> Self {
+        if self.config.subscription_enable() {
+            self.extend_with(
+                Module::Subscription,
+                SubscriptionRpcImpl::new(notify).to_delegate(),
+            );
         }
         self
     }
 
-    pub fn build(self) -> IoHandler {
-        self.io_handler
+    pub fn build(self) -> RpcModules {
+        for method in &self.config.enabled_methods {
+            let found =
+                method == "rpc_discover" || self.modules.method_names().any(|name| name == method);
+            if !found {
+                warn!("rpc method {} in enabled_methods is not served", method);
+            }
+        }
+        self.modules
     }
 }
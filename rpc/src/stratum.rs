@@ -0,0 +1,535 @@
+use crate::error::RPCError;
+use crate::module::{LongPolls, MinerRpc, MinerRpcImpl};
+use ckb_chain::chain::ChainController;
+use ckb_core::block::{Block, BlockBuilder};
+use ckb_core::header::Seal;
+use ckb_core::Bytes;
+use ckb_jsonrpc_types::{JsonBytes, Unsigned};
+use ckb_logger::{debug, error, info, warn};
+use ckb_miner::{BlockAssemblerController, STRATUM_NOTIFY, STRATUM_SUBMIT, STRATUM_SUBSCRIBE};
+use ckb_network::NetworkController;
+use ckb_notify::MsgNewTip;
+use ckb_pow::{pow_message, PowEngine};
+use ckb_shared::shared::Shared;
+use ckb_stop_handler::{SignalSender, StopHandler};
+use ckb_util::{FnvHashSet, Mutex};
+use crossbeam_channel::{select, Receiver};
+use jsonrpc_core::{
+    Error, Failure, Id, MethodCall, Notification, Output, Params, Success, Version,
+};
+use lru_cache::LruCache;
+use numext_fixed_hash::H256;
+use numext_fixed_uint::U256;
+use serde_json::{json, Value};
+use std::cmp;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const SUBSCRIBER_NAME: &str = "StratumServer";
+// The interval to accept the workers and to check whether the block template is changed. The job
+// of a new tip is pushed at once without waiting for the interval.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// The shares of the recent jobs are still accepted after a new job is pushed
+const JOB_CACHE_SIZE: usize = 8;
+// The connections over the limit are closed at once
+const MAX_SESSIONS: usize = 256;
+// A session is closed if the worker sends nothing in the timeout, or does not read the messages
+// sent to it in the timeout
+const READ_TIMEOUT: Duration = Duration::from_secs(600);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Job {
+    block: Block,
+    share_difficulty: U256,
+    // the nonces submitted, to reject the duplicated shares
+    nonces: FnvHashSet<u64>,
+}
+
+struct Session {
+    address: SocketAddr,
+    stream: Mutex<TcpStream>,
+    subscribed: AtomicBool,
+    closed: AtomicBool,
+}
+
+impl Session {
+    fn send<T: serde::Serialize>(&self, message: &T) {
+        let mut raw = serde_json::to_vec(message).expect("serialize stratum message");
+        raw.push(b'\n');
+        if let Err(err) = self.stream.lock().write_all(&raw) {
+            debug!("stratum send to {} error: {:?}", self.address, err);
+            self.closed.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+struct Stratum {
+    pow: Arc<dyn PowEngine>,
+    miner: MinerRpcImpl,
+    share_difficulty: U256,
+    jobs: Mutex<LruCache<String, Job>>,
+    // the current job id and the parent hash of its block
+    current: Mutex<Option<(String, H256)>>,
+    sessions: Mutex<Vec<Arc<Session>>>,
+}
+
+pub struct StratumController {
+    stop: StopHandler<()>,
+}
+
+impl Drop for StratumController {
+    fn drop(&mut self) {
+        self.stop.try_send();
+    }
+}
+
+// Pushes the jobs built from the block templates to the subscribed workers. The shares are
+// verified against the share difficulty, and the ones reaching the block difficulty are submitted
+// as blocks. See the protocol in `ckb_miner::StratumClient`.
+pub struct StratumServer {
+    listen_address: String,
+    block_assembler: BlockAssemblerController,
+    new_tip_receiver: Receiver<MsgNewTip>,
+    stratum: Arc<Stratum>,
+}
+
+impl StratumServer {
+    pub fn new(
+        listen_address: String,
+        share_difficulty: U256,
+        shared: Shared,
+        network_controller: NetworkController,
+        chain: ChainController,
+        block_assembler: BlockAssemblerController,
+    ) -> StratumServer {
+        let pow = shared.consensus().pow_engine();
+        let new_tip_receiver = shared.notify().subscribe_new_tip(SUBSCRIBER_NAME);
+        let miner = MinerRpcImpl {
+            network_controller,
+            shared,
+            block_assembler: block_assembler.clone(),
+            chain,
+            // the stratum jobs are not long polled
+            long_polls: LongPolls::new(0),
+        };
+        StratumServer {
+            listen_address,
+            block_assembler,
+            new_tip_receiver,
+            stratum: Arc::new(Stratum {
+                pow,
+                miner,
+                share_difficulty,
+                jobs: Mutex::new(LruCache::new(JOB_CACHE_SIZE)),
+                current: Mutex::new(None),
+                sessions: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    // remove `allow` tag when https://github.com/crossbeam-rs/crossbeam/issues/404 is solved
+    #[allow(clippy::zero_ptr, clippy::drop_copy)]
+    pub fn start<S: ToString>(self, thread_name: Option<S>) -> StratumController {
+        let listener = TcpListener::bind(&self.listen_address).expect("Start stratum server");
+        listener
+            .set_nonblocking(true)
+            .expect("Set stratum listener nonblocking");
+        info!("Listen stratum on {}", self.listen_address);
+
+        let (signal_sender, signal_receiver) = crossbeam_channel::bounded::<()>(1);
+        let mut thread_builder = thread::Builder::new();
+        // Mainly for test: give a empty thread_name
+        if let Some(name) = thread_name {
+            thread_builder = thread_builder.name(name.to_string());
+        }
+        let thread = thread_builder
+            .spawn(move || {
+                loop {
+                    select! {
+                        recv(signal_receiver) -> _ => break,
+                        recv(self.new_tip_receiver) -> msg => {
+                            if msg.is_err() {
+                                error!("new_tip_receiver closed");
+                                break;
+                            }
+                            // the pending tips are covered by the coming job
+                            self.new_tip_receiver.try_iter().for_each(drop);
+                        },
+                        default(POLL_INTERVAL) => {},
+                    }
+                    self.accept(&listener);
+                    self.update_job();
+                }
+                for session in self.stratum.sessions.lock().drain(..) {
+                    let _ = session.stream.lock().shutdown(Shutdown::Both);
+                }
+            })
+            .expect("Start StratumServer failed");
+
+        StratumController {
+            stop: StopHandler::new(SignalSender::Crossbeam(signal_sender), thread),
+        }
+    }
+
+    fn accept(&self, listener: &TcpListener) {
+        loop {
+            let (stream, address) = match listener.accept() {
+                Ok(accepted) => accepted,
+                Err(ref err) if err.kind() == ErrorKind::WouldBlock => return,
+                Err(err) => {
+                    error!("stratum accept error: {:?}", err);
+                    return;
+                }
+            };
+            let sessions_count = {
+                let mut sessions = self.stratum.sessions.lock();
+                sessions.retain(|session| !session.closed.load(Ordering::SeqCst));
+                sessions.len()
+            };
+            if sessions_count >= MAX_SESSIONS {
+                warn!(
+                    "stratum session {} refused, reaching {} sessions",
+                    address, MAX_SESSIONS
+                );
+                let _ = stream.shutdown(Shutdown::Both);
+                continue;
+            }
+            let reader = match stream
+                .set_nonblocking(false)
+                .and_then(|_| stream.set_read_timeout(Some(READ_TIMEOUT)))
+                .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+                .and_then(|_| stream.try_clone())
+            {
+                Ok(reader) => reader,
+                Err(err) => {
+                    error!("stratum session {} error: {:?}", address, err);
+                    continue;
+                }
+            };
+            debug!("stratum session {} connected", address);
+            let session = Arc::new(Session {
+                address,
+                stream: Mutex::new(stream),
+                subscribed: AtomicBool::new(false),
+                closed: AtomicBool::new(false),
+            });
+            self.stratum.sessions.lock().push(Arc::clone(&session));
+
+            let stratum = Arc::clone(&self.stratum);
+            let spawned = thread::Builder::new()
+                .name(format!("StratumSession-{}", address))
+                .spawn(move || stratum.serve(&session, reader));
+            if let Err(err) = spawned {
+                error!("stratum session {} error: {:?}", address, err);
+            }
+        }
+    }
+
+    // Pushes a new job when the block template is changed because of the new tip or txs
+    fn update_job(&self) {
+        let template = match self.block_assembler.get_block_template(None, None, None) {
+            Ok(template) => template,
+            Err(err) => {
+                error!("stratum get_block_template error: {}", err);
+                return;
+            }
+        };
+        let job_id = format!("{:x}", template.work_id.0);
+        let parent_hash = template.parent_hash.clone();
+        let clean_jobs = {
+            let mut current = self.stratum.current.lock();
+            match current.as_ref() {
+                Some((current_id, _)) if *current_id == job_id => return,
+                Some((_, current_parent)) if *current_parent == parent_hash => {
+                    *current = Some((job_id.clone(), parent_hash));
+                    false
+                }
+                _ => {
+                    *current = Some((job_id.clone(), parent_hash));
+                    true
+                }
+            }
+        };
+
+        let block = BlockBuilder::from(template).build();
+        let share_difficulty = cmp::min(
+            self.stratum.share_difficulty.clone(),
+            block.header().difficulty().clone(),
+        );
+        let notification = Notification {
+            jsonrpc: Some(Version::V2),
+            method: STRATUM_NOTIFY.to_owned(),
+            params: Params::Array(vec![
+                json!(job_id),
+                json!(block.header().pow_hash()),
+                json!(share_difficulty),
+                json!(clean_jobs),
+            ]),
+        };
+        debug!(
+            "stratum new job {} #{} clean {}",
+            job_id,
+            block.header().number(),
+            clean_jobs
+        );
+        self.stratum.jobs.lock().insert(
+            job_id,
+            Job {
+                block,
+                share_difficulty,
+                nonces: FnvHashSet::default(),
+            },
+        );
+
+        let mut sessions = self.stratum.sessions.lock();
+        sessions.retain(|session| !session.closed.load(Ordering::SeqCst));
+        for session in sessions.iter() {
+            if session.subscribed.load(Ordering::SeqCst) {
+                session.send(&notification);
+            }
+        }
+    }
+}
+
+impl Stratum {
+    fn serve(&self, session: &Session, reader: TcpStream) {
+        for line in BufReader::new(reader).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    debug!("stratum session {} error: {:?}", session.address, err);
+                    break;
+                }
+            };
+            let call: MethodCall = match serde_json::from_str(&line) {
+                Ok(call) => call,
+                Err(_) => {
+                    session.send(&Output::Failure(Failure {
+                        jsonrpc: Some(Version::V2),
+                        error: Error::parse_error(),
+                        id: Id::Null,
+                    }));
+                    continue;
+                }
+            };
+            let result = match call.method.as_str() {
+                STRATUM_SUBSCRIBE => {
+                    session.subscribed.store(true, Ordering::SeqCst);
+                    Ok(Value::Bool(true))
+                }
+                STRATUM_SUBMIT => call
+                    .params
+                    .parse()
+                    .and_then(|(job_id, nonce, proof): (String, Unsigned, JsonBytes)| {
+                        self.submit(&job_id, nonce.0, proof.into_bytes())
+                    })
+                    .map(Value::Bool),
+                _ => Err(Error::method_not_found()),
+            };
+            let output = match result {
+                Ok(result) => Output::Success(Success {
+                    jsonrpc: Some(Version::V2),
+                    result,
+                    id: call.id,
+                }),
+                Err(error) => Output::Failure(Failure {
+                    jsonrpc: Some(Version::V2),
+                    error,
+                    id: call.id,
+                }),
+            };
+            session.send(&output);
+            if call.method == STRATUM_SUBSCRIBE {
+                self.notify_current_job(session);
+            }
+        }
+        session.closed.store(true, Ordering::SeqCst);
+        let _ = session.stream.lock().shutdown(Shutdown::Both);
+        debug!("stratum session {} closed", session.address);
+    }
+
+    fn notify_current_job(&self, session: &Session) {
+        let job_id = match self.current.lock().as_ref() {
+            Some((job_id, _)) => job_id.clone(),
+            None => return,
+        };
+        let mut jobs = self.jobs.lock();
+        if let Some(job) = jobs.get_refresh(&job_id) {
+            session.send(&Notification {
+                jsonrpc: Some(Version::V2),
+                method: STRATUM_NOTIFY.to_owned(),
+                params: Params::Array(vec![
+                    json!(job_id),
+                    json!(job.block.header().pow_hash()),
+                    json!(job.share_difficulty),
+                    json!(true),
+                ]),
+            });
+        }
+    }
+
+    // Accepts the share, and submits the block if the seal reaches the block difficulty
+    fn submit(&self, job_id: &str, nonce: u64, proof: Bytes) -> Result<bool, Error> {
+        let block = match accept_share(&mut self.jobs.lock(), &*self.pow, job_id, nonce, proof)? {
+            Some(block) => block,
+            None => return Ok(true),
+        };
+
+        info!(
+            "stratum found block #{} {:#x}",
+            block.header().number(),
+            block.header().hash()
+        );
+        match self.miner.submit_block(job_id.to_owned(), (&block).into()) {
+            Ok(Some(_)) => Ok(true),
+            Ok(None) => {
+                warn!("stratum block #{} rejected", block.header().number());
+                Err(RPCError::custom(
+                    RPCError::Invalid,
+                    "Block rejected".to_owned(),
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+// Verifies the share of the job, returns the sealed block if the share reaches the block
+// difficulty
+fn accept_share(
+    jobs: &mut LruCache<String, Job>,
+    pow: &dyn PowEngine,
+    job_id: &str,
+    nonce: u64,
+    proof: Bytes,
+) -> Result<Option<Block>, Error> {
+    let job = jobs
+        .get_refresh(job_id)
+        .ok_or_else(|| RPCError::custom(RPCError::Invalid, format!("Stale job {}", job_id)))?;
+    let header = job.block.header();
+    if job.nonces.contains(&nonce) {
+        return Err(RPCError::custom(
+            RPCError::Invalid,
+            "Duplicated share".to_owned(),
+        ));
+    }
+    if !pow.verify_proof_difficulty(&proof, &job.share_difficulty) {
+        return Err(RPCError::custom(
+            RPCError::Invalid,
+            "Low difficulty share".to_owned(),
+        ));
+    }
+    let message = pow_message(&header.pow_hash(), nonce);
+    if !pow.verify(header.number(), &message, &proof) {
+        return Err(RPCError::custom(
+            RPCError::Invalid,
+            "Invalid proof".to_owned(),
+        ));
+    }
+    job.nonces.insert(nonce);
+
+    if !pow.verify_proof_difficulty(&proof, header.difficulty()) {
+        return Ok(None);
+    }
+    let raw_header = header.raw().to_owned();
+    let block = BlockBuilder::from_block(job.block.clone())
+        .header(raw_header.with_seal(Seal::new(nonce, proof)))
+        .build();
+    Ok(Some(block))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_core::header::{BlockNumber, HeaderBuilder};
+
+    // The first byte of the proof is the difficulty it reaches
+    struct MockPowEngine;
+
+    impl PowEngine for MockPowEngine {
+        fn verify_proof_difficulty(&self, proof: &[u8], difficulty: &U256) -> bool {
+            proof
+                .get(0)
+                .map(|reached| U256::from(u64::from(*reached)) >= *difficulty)
+                .unwrap_or(false)
+        }
+
+        fn verify(&self, _number: BlockNumber, _message: &[u8], proof: &[u8]) -> bool {
+            proof.len() == self.proof_size()
+        }
+
+        fn proof_size(&self) -> usize {
+            1
+        }
+    }
+
+    fn jobs() -> LruCache<String, Job> {
+        let block = BlockBuilder::from_header_builder(
+            HeaderBuilder::default().difficulty(U256::from(100u64)),
+        )
+        .build();
+        let mut jobs = LruCache::new(JOB_CACHE_SIZE);
+        jobs.insert(
+            "1".to_owned(),
+            Job {
+                block,
+                share_difficulty: U256::from(10u64),
+                nonces: FnvHashSet::default(),
+            },
+        );
+        jobs
+    }
+
+    fn proof(bytes: &[u8]) -> Bytes {
+        Bytes::from(bytes)
+    }
+
+    fn rejected(result: Result<Option<Block>, Error>, reason: &str) -> bool {
+        match result {
+            Err(err) => err.message.contains(reason),
+            Ok(_) => false,
+        }
+    }
+
+    #[test]
+    fn test_stale_job() {
+        let mut jobs = jobs();
+        let result = accept_share(&mut jobs, &MockPowEngine, "2", 0, proof(&[20]));
+        assert!(rejected(result, "Stale job"));
+    }
+
+    #[test]
+    fn test_low_difficulty_share() {
+        let mut jobs = jobs();
+        let result = accept_share(&mut jobs, &MockPowEngine, "1", 0, proof(&[5]));
+        assert!(rejected(result, "Low difficulty share"));
+        let result = accept_share(&mut jobs, &MockPowEngine, "1", 0, proof(&[20, 0]));
+        assert!(rejected(result, "Invalid proof"));
+    }
+
+    #[test]
+    fn test_duplicated_share() {
+        let mut jobs = jobs();
+        let result = accept_share(&mut jobs, &MockPowEngine, "1", 0, proof(&[20]));
+        assert_eq!(result, Ok(None));
+        let result = accept_share(&mut jobs, &MockPowEngine, "1", 0, proof(&[20]));
+        assert!(rejected(result, "Duplicated share"));
+        let result = accept_share(&mut jobs, &MockPowEngine, "1", 1, proof(&[20]));
+        assert_eq!(result, Ok(None));
+    }
+
+    #[test]
+    fn test_full_solution() {
+        let mut jobs = jobs();
+        let block = accept_share(&mut jobs, &MockPowEngine, "1", 7, proof(&[200]))
+            .expect("accept share")
+            .expect("full solution");
+        assert_eq!(block.header().nonce(), 7);
+        assert_eq!(block.header().proof(), &[200][..]);
+        assert!(MockPowEngine.verify_header(block.header()));
+    }
+}
@@ -1,13 +1,15 @@
+use crate::config::{Config, Module, RateLimitConfig};
 use crate::module::{
-    ChainRpc, ChainRpcImpl, ExperimentRpc, ExperimentRpcImpl, IndexerRpc, IndexerRpcImpl,
-    NetworkRpc, NetworkRpcImpl, PoolRpc, PoolRpcImpl, StatsRpc, StatsRpcImpl,
+    openrpc_document, ChainRpc, ChainRpcImpl, ExperimentRpc, ExperimentRpcImpl, IndexerRpc,
+    IndexerRpcImpl, NetworkRpc, NetworkRpcImpl, PoolRpc, PoolRpcImpl, StatsRpc, StatsRpcImpl,
 };
-use crate::RpcServer;
+use crate::{RpcServer, ServiceBuilder};
 use ckb_chain::chain::{ChainController, ChainService};
 use ckb_chain_spec::consensus::Consensus;
 use ckb_core::block::{Block, BlockBuilder};
 use ckb_core::cell::resolve_transaction;
 use ckb_core::header::{Header, HeaderBuilder};
+use ckb_core::script::ScriptHashType;
 use ckb_core::transaction::{
     CellInput, CellOutputBuilder, OutPoint, Transaction, TransactionBuilder,
 };
@@ -15,8 +17,9 @@ use ckb_core::{alert::AlertBuilder, capacity_bytes, Bytes, Capacity};
 use ckb_dao::DaoCalculator;
 use ckb_dao_utils::genesis_dao_data;
 use ckb_db::DBConfig;
-use ckb_indexer::{DefaultIndexerStore, IndexerStore};
-use ckb_network::{NetworkConfig, NetworkService, NetworkState};
+use ckb_indexer::{DefaultIndexerStore, IndexerStore, LockPattern};
+use ckb_miner::{BlockAssembler, BlockAssemblerConfig};
+use ckb_network::{NetworkConfig, NetworkController, NetworkService, NetworkState};
 use ckb_network_alert::{
     alert_relayer::AlertRelayer, config::SignatureConfig as AlertSignatureConfig,
 };
@@ -36,6 +39,7 @@ use reqwest;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{from_reader, json, to_string, to_string_pretty, Map, Value};
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::fs::File;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -143,6 +147,24 @@ fn next_block(shared: &Shared, parent: &Header) -> Block {
 }
 
 // Setup the running environment
+fn start_network(shared: &Shared, path: PathBuf) -> NetworkController {
+    let mut network_config = NetworkConfig::default();
+    network_config.path = path;
+    network_config.ping_interval_secs = 1;
+    network_config.ping_timeout_secs = 1;
+    network_config.connect_outbound_interval_secs = 1;
+    let network_state =
+        Arc::new(NetworkState::from_config(network_config).expect("Init network state failed"));
+    NetworkService::new(
+        Arc::clone(&network_state),
+        Vec::new(),
+        shared.consensus().identify_name(),
+        "0.1.0".to_string(),
+    )
+    .start::<&str>(Default::default(), None)
+    .expect("Start network service failed")
+}
+
 fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
     let shared = SharedBuilder::default()
         .consensus(always_success_consensus())
@@ -168,23 +190,7 @@ fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
         .expect("create tempdir failed")
         .path()
         .to_path_buf();
-    let network_controller = {
-        let mut network_config = NetworkConfig::default();
-        network_config.path = dir.clone();
-        network_config.ping_interval_secs = 1;
-        network_config.ping_timeout_secs = 1;
-        network_config.connect_outbound_interval_secs = 1;
-        let network_state =
-            Arc::new(NetworkState::from_config(network_config).expect("Init network state failed"));
-        NetworkService::new(
-            Arc::clone(&network_state),
-            Vec::new(),
-            shared.consensus().identify_name(),
-            "0.1.0".to_string(),
-        )
-        .start::<&str>(Default::default(), None)
-        .expect("Start network service failed")
-    };
+    let network_controller = start_network(&shared, dir.clone());
     let synchronizer = {
         let sync_shared_state = Arc::new(SyncSharedState::new(shared.clone()));
         Synchronizer::new(chain_controller.clone(), Arc::clone(&sync_shared_state))
@@ -242,6 +248,7 @@ fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
             shared: shared.clone(),
             synchronizer: synchronizer.clone(),
             alert_notifier,
+            metrics: Default::default(),
         }
         .to_delegate(),
     );
@@ -266,7 +273,12 @@ fn setup_node(height: u64) -> (Shared, ChainController, RpcServer) {
         .max_request_body_size(20_000_000)
         .start_http(&"127.0.0.1:0".parse().unwrap())
         .expect("JsonRpc initialize");
-    let rpc_server = RpcServer { server };
+    let rpc_server = RpcServer {
+        server,
+        listeners: Vec::new(),
+        ws_server: None,
+        tcp_server: None,
+    };
 
     (shared, chain_controller, rpc_server)
 }
@@ -334,6 +346,19 @@ fn params_of(shared: &Shared, method: &str) -> Value {
     let tip_hash = json!(format!("{:#x}", tip.hash()));
     let (_, _, always_success_script) = always_success_cell();
     let always_success_script_hash = json!(format!("{:#x}", always_success_script.hash()));
+    let (always_success_lock_pattern, always_success_lock_pattern_hash) = {
+        let lock_pattern = LockPattern {
+            code_hash: always_success_script.code_hash.clone(),
+            hash_type: always_success_script.hash_type.clone(),
+            args_prefix: Bytes::new(),
+        };
+        let lock_pattern_hash = lock_pattern.hash();
+        let json_lock_pattern: ckb_jsonrpc_types::LockPattern = lock_pattern.into();
+        (
+            json!(json_lock_pattern),
+            json!(format!("{:#x}", lock_pattern_hash)),
+        )
+    };
     let always_success_out_point = {
         let out_point = OutPoint::new_cell(always_success_transaction().hash().to_owned(), 0);
         let json_out_point: ckb_jsonrpc_types::OutPoint = out_point.into();
@@ -357,15 +382,25 @@ fn params_of(shared: &Shared, method: &str) -> Value {
         | "get_banned_addresses"
         | "get_blockchain_info"
         | "tx_pool_info"
+        | "get_raw_tx_pool"
         | "get_peers_state"
-        | "get_lock_hash_index_states" => vec![],
+        | "get_rpc_stats"
+        | "rpc_discover"
+        | "get_lock_hash_index_states"
+        | "get_type_hash_index_states"
+        | "get_lock_pattern_index_states" => vec![],
         "get_epoch_by_number" => vec![json!("0")],
+        "estimate_fee_rate" => vec![json!("3")],
         "get_block_hash" | "get_block_by_number" | "get_header_by_number" => vec![tip_number],
         "get_block" | "get_header" | "get_cellbase_output_capacity_details" => vec![tip_hash],
-        "get_cells_by_lock_hash"
-        | "get_live_cells_by_lock_hash"
-        | "get_transactions_by_lock_hash" => {
-            vec![always_success_script_hash, json!("0"), json!("2")]
+        "get_cells_by_lock_hash" => vec![always_success_script_hash, json!("0"), json!("2")],
+        "get_capacity_by_lock_hash" => vec![always_success_script_hash],
+        "get_live_cells_by_lock_hash"
+        | "get_transactions_by_lock_hash"
+        | "get_live_cells_by_type_hash"
+        | "get_transactions_by_type_hash" => vec![always_success_script_hash, json!("2")],
+        "get_live_cells_by_lock_pattern" | "get_transactions_by_lock_pattern" => {
+            vec![always_success_lock_pattern_hash, json!("2")]
         }
         "get_live_cell" => vec![always_success_out_point],
         "set_ban" => vec![
@@ -378,12 +413,17 @@ fn params_of(shared: &Shared, method: &str) -> Value {
         "send_transaction" | "dry_run_transaction" | "_compute_transaction_hash" => {
             vec![transaction]
         }
-        "get_transaction" => vec![transaction_hash],
-        "index_lock_hash" => vec![
+        "send_transactions" => vec![json!([transaction])],
+        "get_transaction" | "get_pool_transaction" => vec![transaction_hash],
+        "index_lock_hash" | "index_type_hash" => vec![
             json!(format!("{:#x}", always_success_script.hash())),
             json!("1024"),
         ],
-        "deindex_lock_hash" => vec![json!(format!("{:#x}", always_success_script.hash()))],
+        "deindex_lock_hash" | "deindex_type_hash" => {
+            vec![json!(format!("{:#x}", always_success_script.hash()))]
+        }
+        "index_lock_pattern" => vec![always_success_lock_pattern, json!("1024")],
+        "deindex_lock_pattern" => vec![always_success_lock_pattern_hash],
         "_compute_code_hash" => vec![json!("0x123456")],
         "_compute_script_hash" => {
             let script = always_success_script.clone();
@@ -483,3 +523,160 @@ fn test_rpc() {
 
     server.close();
 }
+
+// Every method served by the modules must be described in the OpenRPC document of `rpc_discover`
+#[test]
+fn test_rpc_methods_documented() {
+    let shared = SharedBuilder::default()
+        .consensus(always_success_consensus())
+        .build()
+        .unwrap();
+    let notify = NotifyService::default().start::<&str>(None);
+    let chain_controller = ChainService::new(shared.clone(), notify.clone()).start::<&str>(None);
+    let dir = tempfile::tempdir().expect("create tempdir failed");
+    let network_controller = start_network(&shared, dir.path().to_path_buf());
+    let synchronizer = Synchronizer::new(
+        chain_controller.clone(),
+        Arc::new(SyncSharedState::new(shared.clone())),
+    );
+    let alert_relayer = AlertRelayer::new(
+        "0.1.0".to_string(),
+        Default::default(),
+        AlertSignatureConfig::default(),
+    );
+    let block_assembler = BlockAssembler::new(
+        shared.clone(),
+        BlockAssemblerConfig {
+            code_hash: H256::zero(),
+            hash_type: ScriptHashType::Data,
+            args: Vec::new(),
+            data: Default::default(),
+        },
+    )
+    .start::<&str>(None, &notify);
+    let indexer_db = DBConfig {
+        path: dir.path().join("indexer"),
+        ..Default::default()
+    };
+    let config = Config {
+        listen_address: "127.0.0.1:0".to_owned(),
+        ws_listen_address: None,
+        tcp_listen_address: None,
+        stratum_listen_address: None,
+        stratum_share_difficulty: None,
+        max_request_body_size: 20_000_000,
+        threads: Some(1),
+        modules: vec![
+            Module::Net,
+            Module::Chain,
+            Module::Miner,
+            Module::Pool,
+            Module::Experiment,
+            Module::Stats,
+            Module::Indexer,
+            Module::IntegrationTest,
+            Module::Alert,
+            Module::Subscription,
+        ],
+        auth: None,
+        listeners: Vec::new(),
+        enabled_methods: Vec::new(),
+        disabled_methods: Vec::new(),
+        max_batch_size: None,
+        rate_limit: None,
+    };
+    let modules = ServiceBuilder::new(&config)
+        .enable_chain(shared.clone())
+        .enable_pool(shared.clone(), network_controller.clone())
+        .enable_miner(
+            shared.clone(),
+            network_controller.clone(),
+            chain_controller.clone(),
+            Some(block_assembler),
+        )
+        .enable_net(network_controller.clone())
+        .enable_stats(
+            shared.clone(),
+            synchronizer,
+            Arc::clone(alert_relayer.notifier()),
+        )
+        .enable_experiment(shared.clone())
+        .enable_integration_test(
+            shared.clone(),
+            network_controller.clone(),
+            chain_controller.clone(),
+        )
+        .enable_alert(
+            Arc::clone(alert_relayer.verifier()),
+            Arc::clone(alert_relayer.notifier()),
+            network_controller,
+        )
+        .enable_indexer(&indexer_db, shared.clone())
+        .enable_subscription(&notify)
+        .build();
+
+    let document = openrpc_document(|_, _| true);
+    let documented: HashSet<_> = document["methods"]
+        .as_array()
+        .expect("methods")
+        .iter()
+        .map(|method| method["name"].as_str().expect("method name"))
+        .collect();
+    for method in modules.method_names() {
+        assert!(
+            documented.contains(method),
+            "rpc method {} is not documented",
+            method
+        );
+    }
+}
+
+#[test]
+fn test_rate_limit_http_clients() {
+    let config = Config {
+        listen_address: "127.0.0.1:0".to_owned(),
+        ws_listen_address: None,
+        tcp_listen_address: None,
+        stratum_listen_address: None,
+        stratum_share_difficulty: None,
+        max_request_body_size: 20_000_000,
+        threads: Some(1),
+        modules: Vec::new(),
+        auth: None,
+        listeners: Vec::new(),
+        enabled_methods: Vec::new(),
+        disabled_methods: Vec::new(),
+        max_batch_size: None,
+        rate_limit: Some(RateLimitConfig {
+            calls_per_second: 1,
+            burst: 1,
+            trust_forwarded_for: true,
+        }),
+    };
+    let modules = ServiceBuilder::new(&config).build();
+    let server = RpcServer::new(config, modules);
+    let client = reqwest::Client::new();
+    let uri = format!(
+        "http://{}:{}/",
+        server.server.address().ip(),
+        server.server.address().port()
+    );
+    let call = |forwarded_for: &str| {
+        let response: Value = client
+            .post(&uri)
+            .header("X-Forwarded-For", forwarded_for)
+            .json(&request_of("rpc_discover", json!([])))
+            .send()
+            .expect("send request")
+            .json()
+            .expect("json response");
+        response.get("error").is_none()
+    };
+
+    assert!(call("10.0.0.1"));
+    assert!(!call("10.0.0.1"));
+    // the other client has its own bucket
+    assert!(call("10.0.0.2, 10.0.0.1"));
+    assert!(!call("10.0.0.2"));
+    server.close();
+}
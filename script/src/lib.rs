@@ -1,4 +1,5 @@
 mod cost_model;
+mod profile;
 mod syscalls;
 mod type_id;
 mod verify;
@@ -7,7 +8,10 @@ use ckb_vm::Error as VMInternalError;
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 
-pub use crate::verify::{ScriptGroup, TransactionScriptsVerifier};
+pub use crate::profile::Profile;
+pub use crate::verify::{
+    ScriptGroup, ScriptGroupReport, ScriptGroupType, TransactionScriptsVerifier,
+};
 
 /// re-export DataLoader
 pub use ckb_script_data_loader::DataLoader;
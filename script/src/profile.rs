@@ -0,0 +1,224 @@
+use ckb_core::{Bytes, Cycle};
+use ckb_vm::{
+    decoder::build_imac_decoder, registers::RA, CoreMachine, DefaultMachine, Error as VMError,
+    Register, SupportMachine,
+};
+use goblin::elf::Elf;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+// The cycles consumed by scripts, attributed to the call stacks. A stack starts with the script
+// hash, followed by the names of the called functions, or the addresses when the ELF symbol table
+// is absent, in which case the instruction addresses are appended as the innermost frames.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    stacks: BTreeMap<String, Cycle>,
+}
+
+impl Profile {
+    pub fn stacks(&self) -> &BTreeMap<String, Cycle> {
+        &self.stacks
+    }
+
+    pub fn total_cycles(&self) -> Cycle {
+        self.stacks.values().sum()
+    }
+
+    pub(crate) fn add(&mut self, stack: &str, cycles: Cycle) {
+        if cycles > 0 {
+            *self.stacks.entry(stack.to_owned()).or_insert(0) += cycles;
+        }
+    }
+
+    // Writes the profile in the folded stacks format, each line is a stack with frames separated
+    // by `;` and the cycles, which can be rendered by flamegraph.pl or inferno.
+    pub fn write_folded<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (stack, cycles) in &self.stacks {
+            writeln!(writer, "{} {}", stack, cycles)?;
+        }
+        Ok(())
+    }
+}
+
+// Function symbols sorted by the start address
+struct Symbols {
+    functions: Vec<(u64, u64, String)>,
+}
+
+impl Symbols {
+    fn parse(program: &[u8]) -> Symbols {
+        let mut functions: Vec<_> = match Elf::parse(program) {
+            Ok(elf) => elf
+                .syms
+                .iter()
+                .filter(|sym| sym.is_function() && sym.st_size > 0)
+                .filter_map(|sym| {
+                    elf.strtab
+                        .get(sym.st_name)
+                        .and_then(Result::ok)
+                        .map(|name| (sym.st_value, sym.st_value + sym.st_size, name.to_owned()))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        functions.sort();
+        Symbols { functions }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    fn lookup(&self, pc: u64) -> Option<&str> {
+        let index = match self
+            .functions
+            .binary_search_by_key(&pc, |(start, _, _)| *start)
+        {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let (_, end, name) = &self.functions[index];
+        if pc < *end {
+            Some(name)
+        } else {
+            None
+        }
+    }
+
+    fn label(&self, pc: u64) -> String {
+        self.lookup(pc)
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("{:#x}", pc))
+    }
+}
+
+struct Frame {
+    return_address: u64,
+    // the length of the stack key before entering this frame
+    key_len: usize,
+}
+
+struct Tracer<'a> {
+    symbols: Symbols,
+    frames: Vec<Frame>,
+    key: String,
+    pending_cycles: Cycle,
+    profile: &'a mut Profile,
+}
+
+impl<'a> Tracer<'a> {
+    fn step(&mut self, pc: u64, cycles: Cycle) {
+        if self.symbols.is_empty() {
+            self.profile.add(&format!("{};{:#x}", self.key, pc), cycles);
+        } else {
+            self.pending_cycles += cycles;
+        }
+    }
+
+    fn flush(&mut self) {
+        self.profile.add(&self.key, self.pending_cycles);
+        self.pending_cycles = 0;
+    }
+
+    fn call(&mut self, target: u64, return_address: u64) {
+        self.flush();
+        let key_len = self.key.len();
+        let label = self.symbols.label(target);
+        self.key.push(';');
+        self.key.push_str(&label);
+        self.frames.push(Frame {
+            return_address,
+            key_len,
+        });
+    }
+
+    fn ret(&mut self) {
+        self.flush();
+        if let Some(frame) = self.frames.pop() {
+            self.key.truncate(frame.key_len);
+        }
+    }
+}
+
+// Runs the loaded program step by step, and records the cycles of each step to the current call
+// stack. A step is a call if it sets `ra` to the address following it, and a return if it jumps to
+// the return address of the innermost frame. Tail calls are attributed to the caller.
+pub(crate) fn run_with_profile<Inner: SupportMachine>(
+    machine: &mut DefaultMachine<Inner>,
+    program: &Bytes,
+    root: &str,
+    profile: &mut Profile,
+) -> Result<i8, VMError> {
+    let decoder = build_imac_decoder::<Inner::REG>();
+    let symbols = Symbols::parse(program);
+    let entry = symbols.label(machine.pc().to_u64());
+    let mut tracer = Tracer {
+        symbols,
+        frames: Vec::new(),
+        key: format!("{};{}", root, entry),
+        pending_cycles: 0,
+        profile,
+    };
+
+    machine.set_running(true);
+    let result = loop {
+        if !machine.running() {
+            break Ok(machine.exit_code());
+        }
+        let pc = machine.pc().to_u64();
+        let cycles = machine.cycles();
+        if let Err(err) = machine.step(&decoder) {
+            break Err(err);
+        }
+        tracer.step(pc, machine.cycles() - cycles);
+
+        let next_pc = machine.pc().to_u64();
+        let ra = machine.registers()[RA].to_u64();
+        if ra == pc + 4 || ra == pc + 2 {
+            tracer.call(next_pc, ra);
+        } else if tracer.frames.last().map(|frame| frame.return_address) == Some(next_pc) {
+            tracer.ret();
+        }
+    };
+    tracer.flush();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_folded_stacks() {
+        let mut profile = Profile::default();
+        profile.add("0x01;_start;main", 100);
+        profile.add("0x01;_start", 20);
+        profile.add("0x01;_start;main", 5);
+        profile.add("0x01;_start;exit", 0);
+        assert_eq!(profile.total_cycles(), 125);
+
+        let mut folded = Vec::new();
+        profile.write_folded(&mut folded).unwrap();
+        assert_eq!(
+            String::from_utf8(folded).unwrap(),
+            "0x01;_start 20\n0x01;_start;main 105\n"
+        );
+    }
+
+    #[test]
+    fn lookup_symbols() {
+        let symbols = Symbols {
+            functions: vec![
+                (0x100, 0x180, "_start".to_owned()),
+                (0x200, 0x280, "main".to_owned()),
+            ],
+        };
+        assert_eq!(symbols.lookup(0x80), None);
+        assert_eq!(symbols.lookup(0x100), Some("_start"));
+        assert_eq!(symbols.lookup(0x17e), Some("_start"));
+        assert_eq!(symbols.lookup(0x180), None);
+        assert_eq!(symbols.lookup(0x240), Some("main"));
+        assert_eq!(symbols.label(0x300), "0x300");
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     cost_model::instruction_cycles,
+    profile::{run_with_profile, Profile},
     syscalls::{
         Debugger, LoadCell, LoadCellData, LoadHeader, LoadInput, LoadScriptHash, LoadTxHash,
         LoadWitness,
@@ -13,11 +14,12 @@ use ckb_core::transaction::{CellInput, CellOutPoint, Witness};
 use ckb_core::{Bytes, Cycle};
 use ckb_logger::{debug, info};
 use ckb_vm::{
-    DefaultCoreMachine, DefaultMachineBuilder, SparseMemory, SupportMachine, TraceMachine,
-    WXorXMemory,
+    DefaultCoreMachine, DefaultMachine, DefaultMachineBuilder, SparseMemory, SupportMachine,
+    TraceMachine, WXorXMemory,
 };
 use fnv::FnvHashMap;
 use numext_fixed_hash::H256;
+use std::cell::RefCell;
 
 #[cfg(all(unix, target_pointer_width = "64"))]
 use crate::Runner;
@@ -44,12 +46,34 @@ impl ScriptGroup {
     }
 }
 
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum ScriptGroupType {
+    Lock,
+    Type,
+}
+
+// The verification result of a script group, `exit_code` is None if the script fails before
+// exiting, and `cycles` is 0 in this case. `debug_messages` collects the messages printed by
+// the debug syscall, in the order they are printed.
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct ScriptGroupReport {
+    pub script_hash: H256,
+    pub group_type: ScriptGroupType,
+    pub input_indices: Vec<usize>,
+    pub output_indices: Vec<usize>,
+    pub cycles: Cycle,
+    pub exit_code: Option<i8>,
+    pub error: Option<ScriptError>,
+    pub debug_messages: Vec<String>,
+}
+
 // This struct leverages CKB VM to verify transaction inputs.
 // FlatBufferBuilder owned Vec<u8> that grows as needed, in the
 // future, we might refactor this to share buffer to achive zero-copy
 pub struct TransactionScriptsVerifier<'a, DL> {
     data_loader: &'a DL,
     debug_printer: Option<Box<dyn Fn(&H256, &str)>>,
+    profile: Option<RefCell<Profile>>,
 
     outputs: Vec<CellMeta>,
     rtx: &'a ResolvedTransaction<'a>,
@@ -145,6 +169,7 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
             lock_groups,
             type_groups,
             debug_printer: None,
+            profile: None,
         }
     }
 
@@ -152,6 +177,28 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
         self.debug_printer = Some(Box::new(func));
     }
 
+    // Enables the tracing mode, in which the scripts are run step by step in the Rust VM regardless
+    // of the configured runner, and the consumed cycles are recorded by call stacks. It is much
+    // slower, only for debugging and profiling scripts.
+    pub fn enable_profile(&mut self) {
+        self.profile = Some(RefCell::new(Profile::default()));
+    }
+
+    // Returns the profile of the scripts run so far, None if the tracing mode is not enabled.
+    pub fn profile(&self) -> Option<Profile> {
+        self.profile
+            .as_ref()
+            .map(|profile| profile.borrow().clone())
+    }
+
+    fn print_debug_message(&self, script_hash: &H256, message: &str) {
+        if let Some(ref printer) = self.debug_printer {
+            printer(script_hash, message);
+        } else {
+            debug!("script group: {:x} DEBUG OUTPUT: {}", script_hash, message);
+        }
+    }
+
     #[inline]
     fn inputs(&self) -> &[CellInput] {
         self.rtx.transaction.inputs()
@@ -263,23 +310,21 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
 
         // Now run each script group
         for group in self.lock_groups.values().chain(self.type_groups.values()) {
-            let result = if group.script.code_hash == TYPE_ID_CODE_HASH
-                && group.script.hash_type == ScriptHashType::Type
-            {
-                let verifier = TypeIdSystemScript {
-                    rtx: self.rtx,
-                    script_group: group,
-                    max_cycles,
-                };
-                verifier.verify()
-            } else {
-                let program = self.extract_script(&group.script)?;
-                self.run(&program, &group, max_cycles)
-            };
+            let script_hash = group.script.hash();
+            let debug_printer = |message: &str| self.print_debug_message(&script_hash, message);
+            let result = self
+                .run_script_group(group, max_cycles, &debug_printer)
+                .and_then(|(code, cycle)| {
+                    if code == 0 {
+                        Ok(cycle)
+                    } else {
+                        Err(ScriptError::ValidationFailure(code))
+                    }
+                });
             let cycle = result.map_err(|e| {
                 info!(
                     "Error validating script group {:x} of transaction {:x}: {:?}",
-                    group.script.hash(),
+                    script_hash,
                     self.hash(),
                     e
                 );
@@ -296,22 +341,164 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
         Ok(cycles)
     }
 
+    // Unlike `verify`, keeps running the remaining script groups after a failure, and reports the
+    // result of each group. The script groups share `max_cycles`, a group is run with the cycles
+    // left by the previous ones.
+    pub fn verify_script_groups(
+        &self,
+        max_cycles: Cycle,
+    ) -> Result<Vec<ScriptGroupReport>, ScriptError> {
+        // Check if all inputs are resolved correctly
+        if self
+            .resolved_inputs()
+            .iter()
+            .any(|input| input.cell.is_none())
+        {
+            return Err(ScriptError::NoScript);
+        }
+
+        let lock_groups = self
+            .lock_groups
+            .values()
+            .map(|group| (ScriptGroupType::Lock, group));
+        let type_groups = self
+            .type_groups
+            .values()
+            .map(|group| (ScriptGroupType::Type, group));
+        let mut cycles: Cycle = 0;
+        let mut reports = Vec::new();
+        for (group_type, group) in lock_groups.chain(type_groups) {
+            let script_hash = group.script.hash();
+            let debug_messages = RefCell::new(Vec::new());
+            // the messages are still printed as usual besides being collected
+            let debug_printer = |message: &str| {
+                self.print_debug_message(&script_hash, message);
+                debug_messages.borrow_mut().push(message.to_owned());
+            };
+            let remaining_cycles = max_cycles.saturating_sub(cycles);
+            let (exit_code, group_cycles, error) =
+                match self.run_script_group(group, remaining_cycles, &debug_printer) {
+                    Ok((0, group_cycles)) if group_cycles > remaining_cycles => {
+                        (Some(0), 0, Some(ScriptError::ExceededMaximumCycles))
+                    }
+                    Ok((0, group_cycles)) => (Some(0), group_cycles, None),
+                    Ok((code, group_cycles)) => (
+                        Some(code),
+                        group_cycles,
+                        Some(ScriptError::ValidationFailure(code)),
+                    ),
+                    Err(err) => (None, 0, Some(err)),
+                };
+            cycles = cycles.saturating_add(group_cycles);
+            reports.push(ScriptGroupReport {
+                script_hash,
+                group_type,
+                input_indices: group.input_indices.clone(),
+                output_indices: group.output_indices.clone(),
+                cycles: group_cycles,
+                exit_code,
+                error,
+                debug_messages: debug_messages.into_inner(),
+            });
+        }
+        Ok(reports)
+    }
+
+    // Runs a script group, returns the exit code and the consumed cycles
+    fn run_script_group(
+        &self,
+        group: &ScriptGroup,
+        max_cycles: Cycle,
+        debug_printer: &Fn(&str),
+    ) -> Result<(i8, Cycle), ScriptError> {
+        if group.script.code_hash == TYPE_ID_CODE_HASH
+            && group.script.hash_type == ScriptHashType::Type
+        {
+            let verifier = TypeIdSystemScript {
+                rtx: self.rtx,
+                script_group: group,
+                max_cycles,
+            };
+            let result = verifier.verify();
+            if let (Some(profile), Ok(cycles)) = (&self.profile, &result) {
+                let stack = format!("{:x};type_id", group.script.hash());
+                profile.borrow_mut().add(&stack, *cycles);
+            }
+            result.map(|cycles| (0, cycles))
+        } else {
+            let program = self.extract_script(&group.script)?;
+            match self.profile {
+                Some(ref profile) => self.run_with_profile(
+                    &program,
+                    &group,
+                    max_cycles,
+                    debug_printer,
+                    &mut profile.borrow_mut(),
+                ),
+                None => self.run(&program, &group, max_cycles, debug_printer),
+            }
+        }
+    }
+
+    fn run_with_profile(
+        &self,
+        program: &Bytes,
+        script_group: &ScriptGroup,
+        max_cycles: Cycle,
+        debug_printer: &Fn(&str),
+        profile: &mut Profile,
+    ) -> Result<(i8, Cycle), ScriptError> {
+        let current_script_hash = script_group.script.hash();
+        let current_script_hash_bytes = current_script_hash.as_bytes();
+        let mut args = vec!["verify".into()];
+        args.extend_from_slice(&script_group.script.args);
+        let core_machine =
+            DefaultCoreMachine::<u64, WXorXMemory<u64, SparseMemory<u64>>>::new_with_max_cycles(
+                max_cycles,
+            );
+        let mut machine: DefaultMachine<
+            DefaultCoreMachine<u64, WXorXMemory<u64, SparseMemory<u64>>>,
+        > = DefaultMachineBuilder::new(core_machine)
+            .instruction_cycle_func(Box::new(instruction_cycles))
+            .syscall(Box::new(
+                self.build_load_script_hash(current_script_hash_bytes),
+            ))
+            .syscall(Box::new(self.build_load_tx_hash()))
+            .syscall(Box::new(self.build_load_cell(
+                &script_group.input_indices,
+                &script_group.output_indices,
+            )))
+            .syscall(Box::new(self.build_load_input(&script_group.input_indices)))
+            .syscall(Box::new(
+                self.build_load_header(&script_group.input_indices),
+            ))
+            .syscall(Box::new(
+                self.build_load_witness(&script_group.input_indices),
+            ))
+            .syscall(Box::new(self.build_load_cell_data(
+                &script_group.input_indices,
+                &script_group.output_indices,
+            )))
+            .syscall(Box::new(Debugger::new(debug_printer)))
+            .build();
+        machine
+            .load_program(&program, &args)
+            .map_err(ScriptError::VMError)?;
+        let root = format!("{:x}", current_script_hash);
+        let code = run_with_profile(&mut machine, program, &root, profile)
+            .map_err(ScriptError::VMError)?;
+        Ok((code, machine.cycles()))
+    }
+
     #[cfg(all(unix, target_pointer_width = "64"))]
     fn run(
         &self,
         program: &Bytes,
         script_group: &ScriptGroup,
         max_cycles: Cycle,
-    ) -> Result<Cycle, ScriptError> {
+        debug_printer: &Fn(&str),
+    ) -> Result<(i8, Cycle), ScriptError> {
         let current_script_hash = script_group.script.hash();
-        let prefix = format!("script group: {:x}", current_script_hash);
-        let debug_printer = |message: &str| {
-            if let Some(ref printer) = self.debug_printer {
-                printer(&current_script_hash, message);
-            } else {
-                debug!("{} DEBUG OUTPUT: {}", prefix, message);
-            };
-        };
         let current_script_hash_bytes = current_script_hash.as_bytes();
         let mut args = vec!["verify".into()];
         args.extend_from_slice(&script_group.script.args);
@@ -339,7 +526,7 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
                         &script_group.input_indices,
                         &script_group.output_indices,
                     )))
-                    .syscall(Box::new(Debugger::new(&debug_printer)))
+                    .syscall(Box::new(Debugger::new(debug_printer)))
                     .build();
                 let mut machine = AsmMachine::new(machine, None);
                 machine
@@ -374,7 +561,7 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
                     &script_group.input_indices,
                     &script_group.output_indices,
                 )))
-                .syscall(Box::new(Debugger::new(&debug_printer)))
+                .syscall(Box::new(Debugger::new(debug_printer)))
                 .build();
                 let mut machine = TraceMachine::new(machine);
                 machine
@@ -384,11 +571,7 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
                 (code, machine.machine.cycles())
             }
         };
-        if code == 0 {
-            Ok(cycles)
-        } else {
-            Err(ScriptError::ValidationFailure(code))
-        }
+        Ok((code, cycles))
     }
 
     #[cfg(not(all(unix, target_pointer_width = "64")))]
@@ -397,16 +580,9 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
         program: &Bytes,
         script_group: &ScriptGroup,
         max_cycles: Cycle,
-    ) -> Result<Cycle, ScriptError> {
+        debug_printer: &Fn(&str),
+    ) -> Result<(i8, Cycle), ScriptError> {
         let current_script_hash = script_group.script.hash();
-        let prefix = format!("script group: {:x}", current_script_hash);
-        let debug_printer = |message: &str| {
-            if let Some(ref printer) = self.debug_printer {
-                printer(&current_script_hash, message);
-            } else {
-                debug!("{} DEBUG OUTPUT: {}", prefix, message);
-            };
-        };
         let current_script_hash_bytes = current_script_hash.as_bytes();
         let mut args = vec!["verify".into()];
         args.extend_from_slice(&script_group.script.args);
@@ -437,18 +613,14 @@ impl<'a, DL: DataLoader> TransactionScriptsVerifier<'a, DL> {
             &script_group.input_indices,
             &script_group.output_indices,
         )))
-        .syscall(Box::new(Debugger::new(&debug_printer)))
+        .syscall(Box::new(Debugger::new(debug_printer)))
         .build();
         let mut machine = TraceMachine::new(machine);
         machine
             .load_program(&program, &args)
             .map_err(ScriptError::VMError)?;
         let code = machine.run().map_err(ScriptError::VMError)?;
-        if code == 0 {
-            Ok(machine.machine.cycles())
-        } else {
-            Err(ScriptError::ValidationFailure(code))
-        }
+        Ok((code, machine.machine.cycles()))
     }
 }
 
@@ -1265,6 +1437,166 @@ mod tests {
         assert_eq!(verifier.verify(100_000_000), Ok(2_818_104));
     }
 
+    #[test]
+    fn check_script_group_reports() {
+        let mut file = open_cell_verify();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let privkey = Privkey::from_slice(&[1; 32][..]);
+        let pubkey = privkey.pubkey().unwrap();
+        let mut args = vec![Bytes::from(b"foo".to_vec()), Bytes::from(b"bar".to_vec())];
+
+        let signature = sign_args(&args, &privkey);
+        args.push(Bytes::from(to_hex_pubkey(&pubkey)));
+        args.push(Bytes::from(to_hex_signature(&signature)));
+
+        let code_hash: H256 = (&blake2b_256(&buffer)).into();
+        let lock = Script::new(args, code_hash.to_owned(), ScriptHashType::Data);
+        let type_ = Script::new(vec![], h256!("0x1"), ScriptHashType::Data);
+
+        let dep_out_point = OutPoint::new_cell(h256!("0x123"), 8);
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::from_data(&data)
+            .capacity(Capacity::bytes(data.len()).unwrap())
+            .build();
+        let dep_cell = ResolvedOutPoint::cell_only(
+            CellMetaBuilder::from_cell_output(output, data)
+                .block_info(BlockInfo::new(1, 0, H256::zero()))
+                .out_point(dep_out_point.cell.as_ref().unwrap().clone())
+                .build(),
+        );
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .dep(dep_out_point)
+            .build();
+
+        let output = CellOutput::new(
+            capacity_bytes!(100),
+            H256::zero(),
+            lock.clone(),
+            Some(type_.clone()),
+        );
+        let dummy_cell = ResolvedOutPoint::cell_only(
+            CellMetaBuilder::from_cell_output(output, Bytes::new())
+                .block_info(BlockInfo::new(1, 0, H256::zero()))
+                .build(),
+        );
+
+        let rtx = ResolvedTransaction {
+            transaction: &transaction,
+            resolved_deps: vec![dep_cell],
+            resolved_inputs: vec![dummy_cell],
+        };
+        let config = ScriptConfig {
+            runner: Runner::default(),
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let verifier = TransactionScriptsVerifier::new(&rtx, &data_loader, &config);
+
+        assert_eq!(
+            verifier.verify(100_000_000).err(),
+            Some(ScriptError::InvalidCodeHash)
+        );
+        // The lock group is still reported although the type group fails
+        assert_eq!(
+            verifier.verify_script_groups(100_000_000),
+            Ok(vec![
+                ScriptGroupReport {
+                    script_hash: lock.hash(),
+                    group_type: ScriptGroupType::Lock,
+                    input_indices: vec![0],
+                    output_indices: vec![],
+                    cycles: 1_409_052,
+                    exit_code: Some(0),
+                    error: None,
+                    debug_messages: vec![],
+                },
+                ScriptGroupReport {
+                    script_hash: type_.hash(),
+                    group_type: ScriptGroupType::Type,
+                    input_indices: vec![0],
+                    output_indices: vec![],
+                    cycles: 0,
+                    exit_code: None,
+                    error: Some(ScriptError::InvalidCodeHash),
+                    debug_messages: vec![],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn check_profile() {
+        let mut file = open_cell_verify();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let (privkey, pubkey) = random_keypair();
+        let mut args = vec![Bytes::from(b"foo".to_vec()), Bytes::from(b"bar".to_vec())];
+
+        let signature = sign_args(&args, &privkey);
+        args.push(Bytes::from(to_hex_pubkey(&pubkey)));
+        args.push(Bytes::from(to_hex_signature(&signature)));
+
+        let code_hash: H256 = (&blake2b_256(&buffer)).into();
+        let dep_out_point = OutPoint::new_cell(h256!("0x123"), 8);
+        let data = Bytes::from(buffer);
+        let output = CellOutputBuilder::from_data(&data)
+            .capacity(Capacity::bytes(data.len()).unwrap())
+            .build();
+        let dep_cell = ResolvedOutPoint::cell_only(
+            CellMetaBuilder::from_cell_output(output, data)
+                .block_info(BlockInfo::new(1, 0, H256::zero()))
+                .out_point(dep_out_point.cell.as_ref().unwrap().clone())
+                .build(),
+        );
+
+        let script = Script::new(args, code_hash, ScriptHashType::Data);
+        let script_hash = script.hash();
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), 0))
+            .dep(dep_out_point)
+            .build();
+
+        let output = CellOutputBuilder::default()
+            .capacity(capacity_bytes!(100))
+            .lock(script)
+            .build();
+        let dummy_cell = ResolvedOutPoint::cell_only(
+            CellMetaBuilder::from_cell_output(output, Bytes::new())
+                .block_info(BlockInfo::new(1, 0, H256::zero()))
+                .build(),
+        );
+
+        let rtx = ResolvedTransaction {
+            transaction: &transaction,
+            resolved_deps: vec![dep_cell],
+            resolved_inputs: vec![dummy_cell],
+        };
+        let store = new_store();
+        let data_loader = DataLoaderWrapper::new(&store);
+        let config = ScriptConfig {
+            runner: Runner::default(),
+        };
+        let mut verifier = TransactionScriptsVerifier::new(&rtx, &data_loader, &config);
+        assert_eq!(verifier.profile(), None);
+
+        let cycles = verifier.verify(100_000_000).unwrap();
+        verifier.enable_profile();
+        // The tracing mode consumes the same cycles, which are all attributed to the script
+        assert_eq!(verifier.verify(100_000_000), Ok(cycles));
+        let profile = verifier.profile().unwrap();
+        assert_eq!(profile.total_cycles(), cycles);
+        let root = format!("{:x};", script_hash);
+        assert!(profile
+            .stacks()
+            .keys()
+            .all(|stack| stack.starts_with(&root)));
+    }
+
     #[test]
     fn check_type_id_one_in_one_out() {
         let (always_success_cell, always_success_cell_data, always_success_script) =
@@ -1,7 +1,9 @@
 use crate::cell_set::{CellSet, CellSetDiff, CellSetOpr, CellSetOverlay};
 use crate::error::SharedError;
+use crate::tx_pool::package::{parent_ids, sort_packages, FeeRateEntry};
+use crate::tx_pool::pending::PendingQueue;
 use crate::tx_pool::types::{DefectEntry, ProposedEntry};
-use crate::tx_pool::{PoolError, TxPool, TxPoolConfig};
+use crate::tx_pool::{tx_weight, FeeEstimator, FeeRate, PoolError, TxPool, TxPoolConfig};
 use crate::tx_proposal_table::TxProposalTable;
 use ckb_chain_spec::consensus::{Consensus, ProposalWindow};
 use ckb_core::block::Block;
@@ -12,20 +14,23 @@ use ckb_core::cell::{
 use ckb_core::extras::EpochExt;
 use ckb_core::header::{BlockNumber, Header};
 use ckb_core::transaction::{OutPoint, ProposalShortId, Transaction};
-use ckb_core::Cycle;
+use ckb_core::{Capacity, Cycle};
 use ckb_dao::DaoCalculator;
 use ckb_logger::{debug_target, error_target, info_target, trace_target};
 use ckb_script::ScriptConfig;
 use ckb_store::{ChainDB, ChainStore, StoreTransaction};
 use ckb_traits::BlockMedianTimeContext;
-use ckb_util::LinkedFnvHashSet;
+use ckb_util::{FnvHashSet, LinkedFnvHashSet};
 use ckb_verification::{ContextualTransactionVerifier, TransactionVerifier};
 use failure::Error as FailureError;
+use faketime::unix_time_as_millis;
 use lru_cache::LruCache;
 use numext_fixed_hash::H256;
 use numext_fixed_uint::U256;
 use std::cell::{Ref, RefCell};
+use std::cmp;
 use std::collections::HashSet;
+use std::slice;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -37,6 +42,7 @@ pub struct ChainState {
     proposal_ids: TxProposalTable,
     // interior mutability for immutable borrow proposal_ids
     tx_pool: RefCell<TxPool>,
+    fee_estimator: RefCell<FeeEstimator>,
     consensus: Arc<Consensus>,
     current_epoch_ext: EpochExt,
     script_config: ScriptConfig,
@@ -87,6 +93,7 @@ impl ChainState {
         }?;
 
         let tx_pool = TxPool::new(tx_pool_config);
+        let fee_estimator = FeeEstimator::load(&store);
         let tip_number = tip_header.number();
         let proposal_window = consensus.tx_proposal_window();
         let proposal_ids = Self::init_proposal_ids(&store, proposal_window, tip_number);
@@ -105,6 +112,7 @@ impl ChainState {
             cell_set,
             proposal_ids,
             tx_pool: RefCell::new(tx_pool),
+            fee_estimator: RefCell::new(fee_estimator),
             consensus,
             current_epoch_ext: epoch_ext,
             script_config,
@@ -347,61 +355,218 @@ impl ChainState {
         self.tx_pool.borrow().get_tx_with_cycles(short_id)
     }
 
-    pub(crate) fn reach_tx_pool_limit(&self, tx_size: usize, cycles: Cycle) -> bool {
-        let tx_pool = self.tx_pool.borrow();
-        tx_pool.reach_size_limit(tx_size) || tx_pool.reach_cycles_limit(cycles)
-    }
-
     // Add a verified tx into pool
     // this method will handle fork related verifications to make sure we are safe during a fork
     pub fn add_tx_to_pool(&self, tx: Transaction, cycles: Cycle) -> Result<Cycle, PoolError> {
         let short_id = tx.proposal_short_id();
+        let tx_hash = tx.hash().to_owned();
         let tx_size = tx.serialized_size();
-        if self.reach_tx_pool_limit(tx_size, cycles) {
-            return Err(PoolError::LimitReached);
-        }
-        match self.resolve_tx_from_pending_and_proposed(&tx) {
+        let (cycles, fee) = match self.resolve_tx_from_pending_and_proposed(&tx) {
             Ok(rtx) => {
-                self.verify_rtx(&rtx, Some(cycles)).and_then(|cycles| {
-                    let mut tx_pool = self.tx_pool.borrow_mut();
-                    if self.contains_proposal_id(&short_id) {
-                        // if tx is proposed, we resolve from proposed, verify again
-                        if let Err(e) = self.proposed_tx_and_descendants(
-                            &mut tx_pool,
-                            Some(cycles),
-                            tx_size,
-                            tx,
-                        ) {
-                            debug_target!(
-                                crate::LOG_TARGET_TX_POOL,
-                                "Failed to add proposed tx {:?}, reason: {:?}",
-                                short_id,
-                                e
-                            );
-                            return Err(e);
-                        }
-                        tx_pool.update_statics_for_add_tx(tx_size, cycles);
-                    } else if tx_pool.enqueue_tx(Some(cycles), tx_size, tx) {
-                        tx_pool.update_statics_for_add_tx(tx_size, cycles);
+                let cycles = self.verify_rtx(&rtx, Some(cycles))?;
+                let fee = DaoCalculator::new(&self.consensus, self.store())
+                    .transaction_fee(&rtx)
+                    .map_err(|_| PoolError::TxFee)?;
+                (cycles, fee)
+            }
+            Err(err) => return Err(PoolError::UnresolvableTransaction(err)),
+        };
+
+        let fee_rate = FeeRate::calculate(fee, self.tx_weight(tx_size, cycles));
+        let mut tx_pool = self.tx_pool.borrow_mut();
+        if fee_rate < tx_pool.config.min_fee_rate {
+            return Err(PoolError::LowFeeRate(fee_rate));
+        }
+        let proposed = self.contains_proposal_id(&short_id);
+        // replace the conflicting txs by fee, and evict the txs paying lower fee rate, once the tx
+        // is added
+        let (replaced, evicted) = tx_pool.add_tx_with_room(
+            tx,
+            tx_size,
+            cycles,
+            fee,
+            fee_rate,
+            |size, cycles| self.tx_weight(size, cycles),
+            |tx_pool, tx| {
+                let added = if proposed {
+                    // if tx is proposed, we resolve from proposed, verify again
+                    if let Err(e) =
+                        self.proposed_tx_and_descendants(tx_pool, Some(cycles), tx_size, tx)
+                    {
+                        debug_target!(
+                            crate::LOG_TARGET_TX_POOL,
+                            "Failed to add proposed tx {:?}, reason: {:?}",
+                            short_id,
+                            e
+                        );
+                        return Err(e);
                     }
-                    Ok(cycles)
-                })
+                    true
+                } else {
+                    tx_pool.enqueue_tx(Some(cycles), Some(fee), tx_size, tx)
+                };
+                if added {
+                    tx_pool.update_statics_for_add_tx(tx_size, cycles);
+                    self.fee_estimator.borrow_mut().track_tx(
+                        tx_hash.clone(),
+                        fee_rate,
+                        self.tip_number(),
+                    );
+                }
+                Ok(added)
+            },
+        )?;
+        for entry in replaced {
+            debug_target!(
+                crate::LOG_TARGET_TX_POOL,
+                "Replace tx {:#x} by tx {:#x} of fee {}",
+                entry.transaction.hash(),
+                tx_hash,
+                fee.as_u64()
+            );
+        }
+        for entry in evicted {
+            debug_target!(
+                crate::LOG_TARGET_TX_POOL,
+                "Evict tx {:#x} for tx {:#x} of fee rate {}",
+                entry.transaction.hash(),
+                tx_hash,
+                fee_rate.as_u64()
+            );
+        }
+        Ok(cycles)
+    }
+
+    // Add a package of verified txs into pool, the txs are in the topological order and each one
+    // may spend the outputs of the previous ones. The min fee rate is checked against the fee
+    // rate of the whole package, so a child paying high fee brings its low fee parents in. The
+    // txs are resolved, verified and checked against the pool limits together before any of them
+    // is added, so either all of them are added or none. A package with a tx already proposed is
+    // rejected, the proposed tx is resolved from the proposed txs only and should be sent alone.
+    //
+    // The txs already in the pool are skipped and their fees are not counted. A package of one
+    // tx is added by `add_tx_to_pool`, which also replaces the conflicting txs by fee.
+    pub fn add_package_to_pool(
+        &self,
+        txs: Vec<(Transaction, Cycle)>,
+    ) -> Result<Vec<Cycle>, PoolError> {
+        if txs.len() == 1 {
+            let (tx, cycles) = txs.into_iter().next().expect("checked len");
+            return self.add_tx_to_pool(tx, cycles).map(|cycles| vec![cycles]);
+        }
+
+        let mut seen_inputs = FnvHashSet::default();
+        let mut previous_txs = Vec::with_capacity(txs.len());
+        let mut cycles_vec = Vec::with_capacity(txs.len());
+        let mut entries = Vec::with_capacity(txs.len());
+        let (mut package_fee, mut package_size, mut package_cycles) = (Capacity::zero(), 0, 0);
+        for (tx, cycles) in txs {
+            let (cycles, fee) = {
+                let rtx = self
+                    .resolve_tx_from_package(&tx, &previous_txs, &mut seen_inputs)
+                    .map_err(PoolError::UnresolvableTransaction)?;
+                let cycles = self.verify_rtx(&rtx, Some(cycles))?;
+                let fee = DaoCalculator::new(&self.consensus, self.store())
+                    .transaction_fee(&rtx)
+                    .map_err(|_| PoolError::TxFee)?;
+                (cycles, fee)
+            };
+            cycles_vec.push(cycles);
+            let short_id = tx.proposal_short_id();
+            if !self.tx_pool.borrow().contains_tx(&short_id) {
+                if self.contains_proposal_id(&short_id) {
+                    return Err(PoolError::PackageRejected(format!(
+                        "tx {:#x} is proposed",
+                        tx.hash()
+                    )));
+                }
+                package_fee = package_fee.safe_add(fee).map_err(|_| PoolError::TxFee)?;
+                package_size += tx.serialized_size();
+                package_cycles += cycles;
+                entries.push((tx.clone(), cycles, fee));
             }
-            Err(err) => Err(PoolError::UnresolvableTransaction(err)),
+            previous_txs.push(tx);
+        }
+        if entries.is_empty() {
+            return Ok(cycles_vec);
+        }
+
+        let fee_rate =
+            FeeRate::calculate(package_fee, self.tx_weight(package_size, package_cycles));
+        let mut tx_pool = self.tx_pool.borrow_mut();
+        if fee_rate < tx_pool.config.min_fee_rate {
+            return Err(PoolError::LowFeeRate(fee_rate));
+        }
+        if tx_pool.reach_size_limit(package_size) || tx_pool.reach_cycles_limit(package_cycles) {
+            let package_txs: Vec<Transaction> =
+                entries.iter().map(|(tx, _, _)| tx.to_owned()).collect();
+            let evicted = tx_pool.evict_for_txs(
+                &package_txs,
+                package_size,
+                package_cycles,
+                fee_rate,
+                |size, cycles| self.tx_weight(size, cycles),
+            )?;
+            for entry in evicted {
+                debug_target!(
+                    crate::LOG_TARGET_TX_POOL,
+                    "Evict tx {:#x} for a package of fee rate {}",
+                    entry.transaction.hash(),
+                    fee_rate.as_u64()
+                );
+            }
+        }
+
+        for (tx, cycles, fee) in entries {
+            let tx_hash = tx.hash().to_owned();
+            let tx_size = tx.serialized_size();
+            // none of the txs is in the pool or proposed, and they spend distinct inputs
+            tx_pool.enqueue_tx(Some(cycles), Some(fee), tx_size, tx);
+            tx_pool.update_statics_for_add_tx(tx_size, cycles);
+            // the txs are committed at the package fee rate
+            self.fee_estimator
+                .borrow_mut()
+                .track_tx(tx_hash, fee_rate, self.tip_number());
         }
+        Ok(cycles_vec)
+    }
+
+    // Estimates the fee rate for a tx to be committed in `target` blocks, which is not lower
+    // than the min fee rate of the pool
+    pub fn estimate_fee_rate(&self, target: BlockNumber) -> Option<FeeRate> {
+        let min_fee_rate = self.tx_pool.borrow().config.min_fee_rate;
+        self.fee_estimator
+            .borrow()
+            .estimate(target)
+            .map(|fee_rate| cmp::max(fee_rate, min_fee_rate))
     }
 
     pub fn resolve_tx_from_pending_and_proposed<'b>(
         &self,
         tx: &'b Transaction,
     ) -> Result<ResolvedTransaction<'b>, UnresolvableError> {
-        let tx_pool = self.tx_pool.borrow_mut();
+        self.resolve_tx_from_package(tx, &[], &mut FnvHashSet::default())
+    }
+
+    // Resolves a tx of a package, which may also spend the outputs of the previous txs of the
+    // package. `seen_inputs` is shared by the txs of the package to find the double spending.
+    pub fn resolve_tx_from_package<'b>(
+        &self,
+        tx: &'b Transaction,
+        previous_txs: &[Transaction],
+        seen_inputs: &mut FnvHashSet<OutPoint>,
+    ) -> Result<ResolvedTransaction<'b>, UnresolvableError> {
+        let tx_pool = self.tx_pool.borrow();
+        let mut package = PendingQueue::new();
+        for previous_tx in previous_txs {
+            package.add_tx(None, None, 0, previous_tx.to_owned());
+        }
         let proposed_provider = OverlayCellProvider::new(&tx_pool.proposed, self);
         let gap_and_proposed_provider = OverlayCellProvider::new(&tx_pool.gap, &proposed_provider);
         let pending_and_proposed_provider =
             OverlayCellProvider::new(&tx_pool.pending, &gap_and_proposed_provider);
-        let mut seen_inputs = HashSet::default();
-        resolve_transaction(tx, &mut seen_inputs, &pending_and_proposed_provider, self)
+        let package_provider = OverlayCellProvider::new(&package, &pending_and_proposed_provider);
+        resolve_transaction(tx, seen_inputs, &package_provider, self)
     }
 
     pub fn resolve_tx_from_proposed<'a>(
@@ -469,9 +634,93 @@ impl ChainState {
                     );
                 }
             } else {
-                tx_pool.enqueue_tx(entry.cycles, entry.size, entry.transaction);
+                // counted again once it passes the fee rate policy of the pending queue
+                tx_pool.update_statics_for_remove_tx(entry.size, entry.cycles.unwrap_or(0));
+                self.readmit_tx(tx_pool, entry.cycles, entry.size, entry.transaction, false);
+            }
+        }
+    }
+
+    // Adds the tx returned to the pending queue or the gap, by a reorg or by its ancestor leaving
+    // the orphan pool. Its fee is computed and the fee rate policy of `add_tx_to_pool` is applied,
+    // so it is neither ranked as a zero fee tx nor admitted below the min fee rate.
+    fn readmit_tx(
+        &self,
+        tx_pool: &mut TxPool,
+        cycles: Option<Cycle>,
+        size: usize,
+        tx: Transaction,
+        gap: bool,
+    ) {
+        let tx_hash = tx.hash().to_owned();
+        match self.admit_tx(tx_pool, cycles, size, &tx) {
+            Ok((fee, fee_rate)) => {
+                let added = if gap {
+                    tx_pool.add_gap(cycles, Some(fee), size, tx)
+                } else {
+                    tx_pool.enqueue_tx(cycles, Some(fee), size, tx)
+                };
+                if added {
+                    tx_pool.update_statics_for_add_tx(size, cycles.unwrap_or(0));
+                    self.fee_estimator
+                        .borrow_mut()
+                        .track_tx(tx_hash, fee_rate, self.tip_number());
+                }
             }
+            Err(err) => {
+                debug_target!(
+                    crate::LOG_TARGET_TX_POOL,
+                    "Failed to readmit tx {:x}, reason: {:?}",
+                    tx_hash,
+                    err
+                );
+            }
+        }
+    }
+
+    // Computes the fee of the tx against the pool and makes room for it if the pool is full.
+    // The unverified tx is weighted by its size only, as `sort_packages` does.
+    fn admit_tx(
+        &self,
+        tx_pool: &mut TxPool,
+        cycles: Option<Cycle>,
+        size: usize,
+        tx: &Transaction,
+    ) -> Result<(Capacity, FeeRate), PoolError> {
+        let fee = self.pool_tx_fee(tx_pool, tx)?;
+        let cycles = cycles.unwrap_or(0);
+        let fee_rate = FeeRate::calculate(fee, self.tx_weight(size, cycles));
+        if fee_rate < tx_pool.config.min_fee_rate {
+            return Err(PoolError::LowFeeRate(fee_rate));
         }
+        if tx_pool.reach_size_limit(size) || tx_pool.reach_cycles_limit(cycles) {
+            tx_pool.evict_for_txs(
+                slice::from_ref(tx),
+                size,
+                cycles,
+                fee_rate,
+                |size, cycles| self.tx_weight(size, cycles),
+            )?;
+        }
+        Ok((fee, fee_rate))
+    }
+
+    // Computes the fee of a tx spending the cells of the chain and the pool
+    fn pool_tx_fee(&self, tx_pool: &TxPool, tx: &Transaction) -> Result<Capacity, PoolError> {
+        let proposed_provider = OverlayCellProvider::new(&tx_pool.proposed, self);
+        let gap_and_proposed_provider = OverlayCellProvider::new(&tx_pool.gap, &proposed_provider);
+        let pending_and_proposed_provider =
+            OverlayCellProvider::new(&tx_pool.pending, &gap_and_proposed_provider);
+        let rtx = resolve_transaction(
+            tx,
+            &mut FnvHashSet::default(),
+            &pending_and_proposed_provider,
+            self,
+        )
+        .map_err(PoolError::UnresolvableTransaction)?;
+        DaoCalculator::new(&self.consensus, self.store())
+            .transaction_fee(&rtx)
+            .map_err(|_| PoolError::TxFee)
     }
 
     pub(crate) fn proposed_tx(
@@ -578,8 +827,20 @@ impl ChainState {
             detached.extend(blk.transactions().iter().skip(1).cloned())
         }
 
-        for blk in attached_blocks {
-            attached.extend(blk.transactions().iter().skip(1).cloned())
+        {
+            let mut fee_estimator = self.fee_estimator.borrow_mut();
+            for blk in attached_blocks {
+                let txs = blk.transactions().iter().skip(1);
+                fee_estimator.process_block(blk.header().number(), txs);
+                attached.extend(blk.transactions().iter().skip(1).cloned())
+            }
+            if let Err(err) = fee_estimator.save(self.store()) {
+                error_target!(
+                    crate::LOG_TARGET_TX_POOL,
+                    "Failed to save fee estimator stats: {:?}",
+                    err
+                );
+            }
         }
 
         let retain: Vec<Transaction> = detached.difference(&attached).cloned().collect();
@@ -596,17 +857,23 @@ impl ChainState {
                 if let Ok(cycles) =
                     self.proposed_tx_and_descendants(&mut tx_pool, cached_cycles, tx_size, tx)
                 {
+                    if let Some(entry) = tx_pool.proposed.get(&tx_short_id) {
+                        let fee_rate =
+                            FeeRate::calculate(entry.fee, self.tx_weight(tx_size, cycles));
+                        self.fee_estimator.borrow_mut().track_tx(
+                            tx_hash.clone(),
+                            fee_rate,
+                            self.tip_number(),
+                        );
+                    }
                     if cached_cycles.is_none() {
                         txs_verify_cache.insert(tx_hash, cycles);
                     }
                     tx_pool.update_statics_for_add_tx(tx_size, cycles);
                 }
-            } else if self.contains_gap(&tx_short_id) {
-                if tx_pool.add_gap(cached_cycles, tx_size, tx) {
-                    tx_pool.update_statics_for_add_tx(tx_size, cached_cycles.unwrap_or(0));
-                }
-            } else if tx_pool.enqueue_tx(cached_cycles, tx_size, tx) {
-                tx_pool.update_statics_for_add_tx(tx_size, cached_cycles.unwrap_or(0));
+            } else {
+                let gap = self.contains_gap(&tx_short_id);
+                self.readmit_tx(&mut tx_pool, cached_cycles, tx_size, tx, gap);
             }
         }
 
@@ -633,7 +900,7 @@ impl ChainState {
                 entries.push((entry.cycles, entry.size, entry.transaction));
             } else if self.contains_gap(entry.key()) {
                 let entry = entry.remove();
-                gaps.push((entry.cycles, entry.size, entry.transaction));
+                gaps.push((entry.cycles, entry.fee, entry.size, entry.transaction));
             }
         }
 
@@ -644,7 +911,7 @@ impl ChainState {
                 entries.push((entry.cycles, entry.size, entry.transaction));
             } else if self.contains_gap(entry.key()) {
                 let entry = entry.remove();
-                gaps.push((entry.cycles, entry.size, entry.transaction));
+                gaps.push((entry.cycles, None, entry.size, entry.transaction));
             }
         }
 
@@ -660,13 +927,15 @@ impl ChainState {
             }
         }
 
-        for (cycles, size, tx) in gaps {
+        for (cycles, fee, size, tx) in gaps {
             debug_target!(
                 crate::LOG_TARGET_TX_POOL,
                 "tx proposed, add to gap {:x}",
                 tx.hash()
             );
-            tx_pool.add_gap(cycles, size, tx);
+            // the conflict txs carry no fee
+            let fee = fee.or_else(|| self.pool_tx_fee(&tx_pool, &tx).ok());
+            tx_pool.add_gap(cycles, fee, size, tx);
         }
     }
 
@@ -674,42 +943,111 @@ impl ChainState {
         self.tx_pool.borrow().last_txs_updated_at
     }
 
+    pub fn tx_weight(&self, size: usize, cycles: Cycle) -> u64 {
+        tx_weight(
+            size,
+            cycles,
+            self.consensus.max_block_bytes(),
+            self.consensus.max_block_cycles(),
+        )
+    }
+
+    // Proposes the pending and gap txs in the order of the fee rate with ancestors, the ones
+    // not resolved yet are treated as zero fee.
     pub fn get_proposals(&self, proposals_limit: usize) -> HashSet<ProposalShortId> {
         let tx_pool = self.tx_pool.borrow();
-        tx_pool
+        let entries: Vec<FeeRateEntry> = tx_pool
             .pending
-            .keys()
-            .chain(tx_pool.gap.keys())
-            .take(proposals_limit)
-            .cloned()
-            .collect()
+            .values()
+            .chain(tx_pool.gap.values())
+            .map(|entry| {
+                let cycles = entry.cycles.unwrap_or(0);
+                FeeRateEntry {
+                    id: entry.transaction.proposal_short_id(),
+                    fee: entry.fee.unwrap_or_else(Capacity::zero),
+                    size: entry.size,
+                    cycles,
+                    weight: self.tx_weight(entry.size, cycles),
+                    parents: parent_ids(&entry.transaction),
+                }
+            })
+            .collect();
+
+        let mut proposals = HashSet::default();
+        for package in sort_packages(&entries) {
+            if proposals.len() >= proposals_limit {
+                break;
+            }
+            // skips the package which exceeds the limit, a smaller one may still fit
+            if proposals.len() + package.len() <= proposals_limit {
+                proposals.extend(package.into_iter().map(|i| entries[i].id.clone()));
+            }
+        }
+        proposals
     }
 
+    // Selects the proposed txs in the order of the fee rate with ancestors, a tx always comes
+    // after its ancestors in the returned entries.
     pub fn get_proposed_txs(
         &self,
         txs_size_limit: usize,
         cycles_limit: Cycle,
     ) -> (Vec<ProposedEntry>, usize, Cycle) {
-        let mut size = 0;
-        let mut cycles = 0;
         let tx_pool = self.tx_pool.borrow();
-        let entries = tx_pool
-            .proposed
-            .txs_iter()
-            .take_while(|tx| {
-                cycles += tx.cycles;
-                size += tx.size;
-                (size < txs_size_limit) && (cycles < cycles_limit)
+        let proposed: Vec<&ProposedEntry> = tx_pool.proposed.txs_iter().collect();
+        let entries: Vec<FeeRateEntry> = proposed
+            .iter()
+            .map(|entry| FeeRateEntry {
+                id: entry.transaction.proposal_short_id(),
+                fee: entry.fee,
+                size: entry.size,
+                cycles: entry.cycles,
+                weight: self.tx_weight(entry.size, entry.cycles),
+                parents: parent_ids(&entry.transaction),
             })
-            .cloned()
             .collect();
-        (entries, size, cycles)
+
+        let mut size = 0;
+        let mut cycles = 0;
+        let mut selected = Vec::new();
+        for package in sort_packages(&entries) {
+            let package_size: usize = package.iter().map(|i| entries[*i].size).sum();
+            let package_cycles: Cycle = package.iter().map(|i| entries[*i].cycles).sum();
+            // skips the package which exceeds the limits, a smaller one may still fit
+            if size + package_size < txs_size_limit && cycles + package_cycles < cycles_limit {
+                size += package_size;
+                cycles += package_cycles;
+                selected.extend(package.into_iter().map(|i| proposed[i].to_owned()));
+            }
+        }
+        (selected, size, cycles)
     }
 
     pub fn tx_pool(&self) -> Ref<TxPool> {
         self.tx_pool.borrow()
     }
 
+    // Records why the tx is rejected by the pool
+    pub fn add_rejected_tx(&self, tx_hash: H256, err: PoolError) {
+        self.tx_pool.borrow_mut().add_rejected(tx_hash, err);
+    }
+
+    // Evicts the pool txs which have stayed longer than their TTLs, returns the number of them
+    pub fn evict_expired_txs(&self) -> usize {
+        let evicted = self
+            .tx_pool
+            .borrow_mut()
+            .evict_expired(unix_time_as_millis());
+        if evicted > 0 {
+            debug_target!(
+                crate::LOG_TARGET_TX_POOL,
+                "evicted {} expired txs from the pool",
+                evicted
+            );
+        }
+        evicted
+    }
+
     pub fn mut_tx_pool(&mut self) -> &mut TxPool {
         self.tx_pool.get_mut()
     }
@@ -8,6 +8,7 @@ use ckb_core::reward::BlockReward;
 use ckb_core::script::Script;
 use ckb_core::Cycle;
 use ckb_db::{DBConfig, RocksDB};
+use ckb_notify::{NotifyController, NotifyService};
 use ckb_reward_calculator::RewardCalculator;
 use ckb_script::ScriptConfig;
 use ckb_store::ChainDB;
@@ -26,6 +27,7 @@ pub struct Shared {
     txs_verify_cache: Arc<Mutex<LruCache<H256, Cycle>>>,
     consensus: Arc<Consensus>,
     script_config: ScriptConfig,
+    notify: NotifyController,
 }
 
 impl Shared {
@@ -34,6 +36,7 @@ impl Shared {
         consensus: Consensus,
         tx_pool_config: TxPoolConfig,
         script_config: ScriptConfig,
+        notify: NotifyController,
     ) -> Result<Self, SharedError> {
         let store = Arc::new(store);
         let consensus = Arc::new(consensus);
@@ -53,6 +56,7 @@ impl Shared {
             consensus,
             script_config,
             txs_verify_cache,
+            notify,
         })
     }
 
@@ -63,6 +67,11 @@ impl Shared {
     pub fn lock_txs_verify_cache(&self) -> MutexGuard<LruCache<H256, Cycle>> {
         lock_or_panic(&self.txs_verify_cache)
     }
+
+    // The events of the tx pool are published to it
+    pub fn notify(&self) -> &NotifyController {
+        &self.notify
+    }
 }
 
 impl ChainProvider for Shared {
@@ -117,6 +126,7 @@ pub struct SharedBuilder {
     tx_pool_config: Option<TxPoolConfig>,
     script_config: Option<ScriptConfig>,
     store_config: Option<StoreConfig>,
+    notify: Option<NotifyController>,
 }
 
 impl Default for SharedBuilder {
@@ -127,6 +137,7 @@ impl Default for SharedBuilder {
             tx_pool_config: None,
             script_config: None,
             store_config: None,
+            notify: None,
         }
     }
 }
@@ -162,6 +173,11 @@ impl SharedBuilder {
         self
     }
 
+    pub fn notify(mut self, notify: NotifyController) -> Self {
+        self.notify = Some(notify);
+        self
+    }
+
     pub fn build(self) -> Result<Shared, SharedError> {
         if let Some(config) = self.store_config {
             config.apply()
@@ -169,7 +185,10 @@ impl SharedBuilder {
         let consensus = self.consensus.unwrap_or_else(Consensus::default);
         let tx_pool_config = self.tx_pool_config.unwrap_or_else(Default::default);
         let script_config = self.script_config.unwrap_or_else(Default::default);
+        let notify = self
+            .notify
+            .unwrap_or_else(|| NotifyService::default().start::<&str>(None));
         let store = ChainDB::new(self.db);
-        Shared::init(store, consensus, tx_pool_config, script_config)
+        Shared::init(store, consensus, tx_pool_config, script_config, notify)
     }
 }
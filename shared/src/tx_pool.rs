@@ -1,9 +1,15 @@
 pub mod pool;
 pub mod types;
 
+mod fee_estimator;
 mod orphan;
-mod pending;
+pub(crate) mod package;
+pub(crate) mod pending;
 mod proposed;
 
+pub use self::fee_estimator::{FeeEstimator, MAX_CONFIRM_BLOCKS};
 pub use self::pool::TxPool;
-pub use self::types::{DefectEntry, PendingEntry, PoolError, ProposedEntry, TxPoolConfig};
+pub use self::types::{
+    tx_weight, DefectEntry, FeeRate, PendingEntry, PoolError, ProposedEntry, TxEntryInfo,
+    TxPoolConfig, TxStatus,
+};
@@ -0,0 +1,253 @@
+use crate::tx_pool::types::FeeRate;
+use ckb_core::header::BlockNumber;
+use ckb_core::transaction::Transaction;
+use ckb_db::Error as DBError;
+use ckb_logger::error_target;
+use ckb_store::{ChainDB, ChainStore, COLUMN_META};
+use ckb_util::FnvHashMap;
+use numext_fixed_hash::H256;
+use serde_derive::{Deserialize, Serialize};
+
+const META_FEE_ESTIMATOR_KEY: &[u8] = b"FEE_ESTIMATOR";
+
+// The max confirmation target in blocks, the txs not committed in it are counted as failures
+pub const MAX_CONFIRM_BLOCKS: BlockNumber = 48;
+// The lower bound of the first fee rate bucket above zero, and the spacing of the buckets
+const MIN_BUCKET_FEE_RATE: u64 = 1_000;
+const MAX_BUCKET_FEE_RATE: u64 = 1_000_000_000;
+const BUCKET_SPACING: f64 = 1.1;
+// The weight of the past data is multiplied by the decay on each block
+const DECAY: f64 = 0.998;
+// A fee rate range is estimated only with enough decayed txs, and the share of them confirmed
+// in the target blocks
+const MIN_TRACKED_TXS: f64 = 1.0;
+const SUCCESS_THRESHOLD: f64 = 0.85;
+
+// Decayed counts of the tracked txs in each fee rate bucket
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Stats {
+    // `confirmed[t][b]` counts the txs in bucket `b` committed in `t + 1` blocks
+    confirmed: Vec<Vec<f64>>,
+    // the txs in bucket `b` which were committed or failed to be committed in time
+    total: Vec<f64>,
+}
+
+// Estimates the fee rate for a tx to be committed in the target blocks, from the fee rates of the
+// txs when they entered the pool and the blocks it took for them to be committed.
+#[derive(Clone, Debug)]
+pub struct FeeEstimator {
+    // the lower bounds of the buckets
+    buckets: Vec<u64>,
+    stats: Stats,
+    // the pool txs being tracked, with the tip number when they entered and their buckets
+    tracked: FnvHashMap<H256, (BlockNumber, usize)>,
+    best_number: BlockNumber,
+}
+
+impl Default for FeeEstimator {
+    fn default() -> Self {
+        let mut buckets = vec![0];
+        let mut bound = MIN_BUCKET_FEE_RATE as f64;
+        while bound < MAX_BUCKET_FEE_RATE as f64 {
+            buckets.push(bound as u64);
+            bound *= BUCKET_SPACING;
+        }
+        let stats = Stats {
+            confirmed: vec![vec![0.0; buckets.len()]; MAX_CONFIRM_BLOCKS as usize],
+            total: vec![0.0; buckets.len()],
+        };
+        FeeEstimator {
+            buckets,
+            stats,
+            tracked: FnvHashMap::default(),
+            best_number: 0,
+        }
+    }
+}
+
+impl FeeEstimator {
+    // Loads the stats saved in the store, starts over if they are absent or malformed
+    pub fn load(store: &ChainDB) -> FeeEstimator {
+        let mut estimator = FeeEstimator::default();
+        if let Some(raw) = store.get(COLUMN_META, META_FEE_ESTIMATOR_KEY) {
+            match bincode::deserialize::<Stats>(raw.as_ref()) {
+                Ok(stats) => {
+                    if estimator.matches(&stats) {
+                        estimator.stats = stats;
+                    }
+                }
+                Err(err) => error_target!(
+                    crate::LOG_TARGET_TX_POOL,
+                    "Failed to load fee estimator stats: {:?}",
+                    err
+                ),
+            }
+        }
+        estimator
+    }
+
+    pub fn save(&self, store: &ChainDB) -> Result<(), DBError> {
+        let raw = bincode::serialize(&self.stats).expect("serialize fee estimator stats");
+        let txn = store.begin_transaction();
+        txn.insert_raw(COLUMN_META, META_FEE_ESTIMATOR_KEY, &raw)?;
+        txn.commit()
+    }
+
+    fn matches(&self, stats: &Stats) -> bool {
+        stats.total.len() == self.buckets.len()
+            && stats.confirmed.len() == self.stats.confirmed.len()
+            && stats
+                .confirmed
+                .iter()
+                .all(|counts| counts.len() == self.buckets.len())
+    }
+
+    fn bucket(&self, fee_rate: FeeRate) -> usize {
+        match self.buckets.binary_search(&fee_rate.as_u64()) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        }
+    }
+
+    // Tracks a tx entered the pool when the tip is `tip_number`
+    pub fn track_tx(&mut self, tx_hash: H256, fee_rate: FeeRate, tip_number: BlockNumber) {
+        let bucket = self.bucket(fee_rate);
+        self.tracked.insert(tx_hash, (tip_number, bucket));
+    }
+
+    // Records the tracked txs committed in the attached block, and counts the ones waiting for
+    // longer than the max target as failures. The blocks attached by a reorg are processed too,
+    // but the past data is decayed only once for each block number. The blocks detached by a reorg
+    // are not reverted, their txs have been counted and are tracked again when they return to the
+    // pool.
+    pub fn process_block<'a>(
+        &mut self,
+        number: BlockNumber,
+        txs: impl Iterator<Item = &'a Transaction>,
+    ) {
+        if number > self.best_number {
+            self.best_number = number;
+            for counts in &mut self.stats.confirmed {
+                counts.iter_mut().for_each(|count| *count *= DECAY);
+            }
+            self.stats
+                .total
+                .iter_mut()
+                .for_each(|count| *count *= DECAY);
+        }
+
+        for tx in txs {
+            if let Some((entered, bucket)) = self.tracked.remove(tx.hash()) {
+                let blocks = number.saturating_sub(entered).max(1);
+                for counts in self.stats.confirmed.iter_mut().skip(blocks as usize - 1) {
+                    counts[bucket] += 1.0;
+                }
+                self.stats.total[bucket] += 1.0;
+            }
+        }
+
+        let stats = &mut self.stats;
+        self.tracked.retain(|_, (entered, bucket)| {
+            if number.saturating_sub(*entered) > MAX_CONFIRM_BLOCKS {
+                stats.total[*bucket] += 1.0;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    // Returns the lowest fee rate with which the txs were committed in `target` blocks mostly,
+    // scanning the buckets from the highest fee rate and grouping the adjacent buckets until
+    // they have enough txs. None if the target is out of range or there is not enough data.
+    pub fn estimate(&self, target: BlockNumber) -> Option<FeeRate> {
+        if target == 0 || target > MAX_CONFIRM_BLOCKS {
+            return None;
+        }
+        let confirmed_counts = &self.stats.confirmed[target as usize - 1];
+        let mut best = None;
+        let (mut confirmed, mut total) = (0.0, 0.0);
+        for bucket in (0..self.buckets.len()).rev() {
+            confirmed += confirmed_counts[bucket];
+            total += self.stats.total[bucket];
+            if total >= MIN_TRACKED_TXS {
+                if confirmed / total < SUCCESS_THRESHOLD {
+                    break;
+                }
+                best = Some(bucket);
+                confirmed = 0.0;
+                total = 0.0;
+            }
+        }
+        best.map(|bucket| FeeRate(self.buckets[bucket]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_core::transaction::{CellInput, OutPoint, TransactionBuilder};
+
+    fn build_tx(index: u32) -> Transaction {
+        TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::new_cell(H256::zero(), index), 0))
+            .build()
+    }
+
+    #[test]
+    fn test_estimate_fee_rate() {
+        let mut estimator = FeeEstimator::default();
+        let fast: Vec<_> = (0..10).map(build_tx).collect();
+        let slow: Vec<_> = (10..20).map(build_tx).collect();
+        for tx in &fast {
+            estimator.track_tx(tx.hash().to_owned(), FeeRate(10_000), 0);
+        }
+        for tx in &slow {
+            estimator.track_tx(tx.hash().to_owned(), FeeRate(2_000), 0);
+        }
+        estimator.process_block(3, fast.iter());
+        estimator.process_block(10, slow.iter());
+
+        let fast_rate = estimator.estimate(3).expect("estimate");
+        assert!(fast_rate.as_u64() > 2_000 && fast_rate.as_u64() <= 10_000);
+        let slow_rate = estimator.estimate(10).expect("estimate");
+        assert!(slow_rate.as_u64() <= 2_000);
+        assert_eq!(estimator.estimate(2), None);
+        assert_eq!(estimator.estimate(MAX_CONFIRM_BLOCKS + 1), None);
+    }
+
+    #[test]
+    fn test_expired_txs_fail() {
+        let mut estimator = FeeEstimator::default();
+        let txs: Vec<_> = (0..10).map(build_tx).collect();
+        for tx in &txs {
+            estimator.track_tx(tx.hash().to_owned(), FeeRate(5_000), 0);
+        }
+        estimator.process_block(MAX_CONFIRM_BLOCKS + 1, txs[..1].iter());
+        estimator.process_block(MAX_CONFIRM_BLOCKS + 2, txs[1..].iter());
+        assert!(estimator.tracked.is_empty());
+        assert_eq!(estimator.estimate(MAX_CONFIRM_BLOCKS), None);
+    }
+
+    #[test]
+    fn test_reorg() {
+        let mut estimator = FeeEstimator::default();
+        let txs: Vec<_> = (0..10).map(build_tx).collect();
+        for tx in &txs {
+            estimator.track_tx(tx.hash().to_owned(), FeeRate(5_000), 0);
+        }
+        estimator.process_block(1, txs[..5].iter());
+        estimator.process_block(2, txs[5..].iter());
+        let confirmed_in_one = estimator.stats.confirmed[0].iter().sum::<f64>();
+
+        // the block 2 is detached, its txs return to the pool and are tracked again
+        for tx in &txs[5..] {
+            estimator.track_tx(tx.hash().to_owned(), FeeRate(5_000), 1);
+        }
+        // and they are committed by the attached block of the same number
+        estimator.process_block(2, txs[5..].iter());
+        assert!(estimator.tracked.is_empty());
+        assert_eq!(estimator.best_number, 2);
+        assert!(estimator.stats.confirmed[0].iter().sum::<f64>() > confirmed_in_one + 4.0);
+    }
+}
@@ -52,7 +52,9 @@ impl OrphanPool {
         self.vertices.insert(short_id, entry)
     }
 
-    pub(crate) fn recursion_remove(&mut self, id: &ProposalShortId) {
+    /// remove the orphan and its descendants, returns the removed entries
+    pub(crate) fn recursion_remove(&mut self, id: &ProposalShortId) -> Vec<DefectEntry> {
+        let mut removed = Vec::new();
         let mut queue: VecDeque<ProposalShortId> = VecDeque::new();
         queue.push_back(id.clone());
         while let Some(id) = queue.pop_front() {
@@ -62,8 +64,18 @@ impl OrphanPool {
                         queue.extend(ids);
                     }
                 }
+                removed.push(entry);
             }
         }
+        removed
+    }
+
+    /// the orphan entered the pool earliest
+    pub(crate) fn oldest(&self) -> Option<ProposalShortId> {
+        self.vertices
+            .iter()
+            .min_by_key(|(_, entry)| entry.timestamp)
+            .map(|(id, _)| *id)
     }
 
     pub(crate) fn remove_by_ancestor(&mut self, tx: &Transaction) -> Vec<DefectEntry> {
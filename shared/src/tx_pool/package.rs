@@ -0,0 +1,186 @@
+use crate::tx_pool::types::FeeRate;
+use ckb_core::transaction::{ProposalShortId, Transaction};
+use ckb_core::{Capacity, Cycle};
+use ckb_util::{FnvHashMap, FnvHashSet};
+use std::cmp;
+
+// A transaction to be sorted by the fee rate, `parents` are the transactions whose outputs are
+// spent or referenced as deps by it, only the ones among the sorted entries are counted.
+#[derive(Debug, Clone)]
+pub(crate) struct FeeRateEntry {
+    pub(crate) id: ProposalShortId,
+    pub(crate) fee: Capacity,
+    pub(crate) size: usize,
+    pub(crate) cycles: Cycle,
+    pub(crate) weight: u64,
+    pub(crate) parents: Vec<ProposalShortId>,
+}
+
+pub(crate) fn parent_ids(tx: &Transaction) -> Vec<ProposalShortId> {
+    tx.input_pts_iter()
+        .chain(tx.deps_iter())
+        .filter_map(|out_point| out_point.cell.as_ref())
+        .map(|cell| ProposalShortId::from_tx_hash(&cell.tx_hash))
+        .collect()
+}
+
+// Sorts the entries into packages in the descending order of the fee rate, returns the indices of
+// the entries. A package is an entry together with its ancestors not in the previous packages, in
+// the topological order.
+//
+// An entry is ranked by the lower fee rate of itself and the package of it and all its ancestors,
+// so a child paying a high fee lifts its low fee ancestors, while a low fee child can not ride on
+// its high fee ancestors. The entries of the same fee rate keep their original order.
+pub(crate) fn sort_packages(entries: &[FeeRateEntry]) -> Vec<Vec<usize>> {
+    let positions: FnvHashMap<&ProposalShortId, usize> = entries
+        .iter()
+        .enumerate()
+        .map(|(position, entry)| (&entry.id, position))
+        .collect();
+    let parents: Vec<Vec<usize>> = entries
+        .iter()
+        .map(|entry| {
+            entry
+                .parents
+                .iter()
+                .filter_map(|id| positions.get(id).cloned())
+                .collect()
+        })
+        .collect();
+
+    let mut ranks: Vec<(FeeRate, usize)> = entries
+        .iter()
+        .enumerate()
+        .map(|(position, entry)| {
+            let (fee, weight) = ancestors(&parents, position).into_iter().fold(
+                (entry.fee.as_u64(), entry.weight),
+                |(fee, weight), ancestor| {
+                    (
+                        fee.saturating_add(entries[ancestor].fee.as_u64()),
+                        weight.saturating_add(entries[ancestor].weight),
+                    )
+                },
+            );
+            let fee_rate = cmp::min(
+                FeeRate::calculate(entry.fee, entry.weight),
+                FeeRate::calculate(Capacity::shannons(fee), weight),
+            );
+            (fee_rate, position)
+        })
+        .collect();
+    ranks.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut selected = vec![false; entries.len()];
+    let mut packages = Vec::new();
+    for (_, position) in ranks {
+        if selected[position] {
+            continue;
+        }
+        // post-order traversal, the parents are pushed before their children
+        let mut package = Vec::new();
+        let mut stack = vec![(position, false)];
+        while let Some((position, visited)) = stack.pop() {
+            if selected[position] {
+                continue;
+            }
+            if visited {
+                selected[position] = true;
+                package.push(position);
+            } else {
+                stack.push((position, true));
+                for parent in &parents[position] {
+                    if !selected[*parent] {
+                        stack.push((*parent, false));
+                    }
+                }
+            }
+        }
+        packages.push(package);
+    }
+    packages
+}
+
+fn ancestors(parents: &[Vec<usize>], position: usize) -> FnvHashSet<usize> {
+    let mut ancestors = FnvHashSet::default();
+    let mut stack = parents[position].clone();
+    while let Some(ancestor) = stack.pop() {
+        if ancestors.insert(ancestor) {
+            stack.extend(parents[ancestor].iter().cloned());
+        }
+    }
+    ancestors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u8, fee: u64, weight: u64, parents: Vec<u8>) -> FeeRateEntry {
+        FeeRateEntry {
+            id: ProposalShortId::new([id; 10]),
+            fee: Capacity::shannons(fee),
+            size: weight as usize,
+            cycles: 0,
+            weight,
+            parents: parents
+                .into_iter()
+                .map(|parent| ProposalShortId::new([parent; 10]))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sort_by_fee_rate() {
+        let entries = vec![
+            entry(1, 100, 100, vec![]),
+            entry(2, 300, 100, vec![]),
+            entry(3, 200, 100, vec![]),
+            // the parent is not in the entries, and the same fee rate keeps the order
+            entry(4, 200, 200, vec![9]),
+        ];
+        assert_eq!(
+            sort_packages(&entries),
+            vec![vec![1], vec![2], vec![0], vec![3]]
+        );
+    }
+
+    #[test]
+    fn test_child_pays_for_parent() {
+        let entries = vec![
+            entry(1, 100, 100, vec![]),
+            entry(2, 10, 100, vec![]),
+            entry(3, 500, 100, vec![2]),
+            entry(4, 150, 100, vec![]),
+        ];
+        // the package of 2 and 3 pays 2550 per 1000 weight
+        assert_eq!(sort_packages(&entries), vec![vec![1, 2], vec![3], vec![0]]);
+    }
+
+    #[test]
+    fn test_low_fee_child() {
+        let entries = vec![
+            entry(1, 500, 100, vec![]),
+            entry(2, 10, 100, vec![1]),
+            entry(3, 100, 100, vec![]),
+        ];
+        assert_eq!(sort_packages(&entries), vec![vec![0], vec![2], vec![1]]);
+    }
+
+    #[test]
+    fn test_package_topological_order() {
+        // 4 spends 2 and 3, which both spend 1
+        let entries = vec![
+            entry(4, 1000, 100, vec![2, 3]),
+            entry(3, 0, 100, vec![1]),
+            entry(2, 0, 100, vec![1]),
+            entry(1, 0, 100, vec![]),
+        ];
+        let packages = sort_packages(&entries);
+        assert_eq!(packages.len(), 1);
+        let package = &packages[0];
+        let position = |id| package.iter().position(|p| *p == id).unwrap();
+        assert_eq!(package.len(), 4);
+        assert!(position(3) < position(1) && position(3) < position(2));
+        assert!(position(1) < position(0) && position(2) < position(0));
+    }
+}
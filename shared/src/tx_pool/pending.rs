@@ -1,7 +1,7 @@
 use crate::tx_pool::types::PendingEntry;
 use ckb_core::cell::{CellMetaBuilder, CellProvider, CellStatus};
 use ckb_core::transaction::{OutPoint, ProposalShortId, Transaction};
-use ckb_core::Cycle;
+use ckb_core::{Capacity, Cycle};
 use ckb_util::{LinkedFnvHashMap, LinkedFnvHashMapEntries};
 
 #[derive(Default, Debug, Clone)]
@@ -23,12 +23,13 @@ impl PendingQueue {
     pub(crate) fn add_tx(
         &mut self,
         cycles: Option<Cycle>,
+        fee: Option<Capacity>,
         size: usize,
         tx: Transaction,
     ) -> Option<PendingEntry> {
         let short_id = tx.proposal_short_id();
         self.inner
-            .insert(short_id, PendingEntry::new(tx, cycles, size))
+            .insert(short_id, PendingEntry::new(tx, cycles, fee, size))
     }
 
     pub(crate) fn contains_key(&self, id: &ProposalShortId) -> bool {
@@ -51,6 +52,10 @@ impl PendingQueue {
         self.inner.keys()
     }
 
+    pub(crate) fn values(&self) -> impl Iterator<Item = &PendingEntry> {
+        self.inner.values()
+    }
+
     pub(crate) fn entries(&mut self) -> LinkedFnvHashMapEntries<ProposalShortId, PendingEntry> {
         self.inner.entries()
     }
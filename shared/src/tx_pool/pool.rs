@@ -1,14 +1,20 @@
 //! Top-level Pool type, methods, and tests
-use super::types::{DefectEntry, ProposedEntry, TxPoolConfig};
+use super::types::{
+    DefectEntry, FeeRate, PendingEntry, PoolError, ProposedEntry, TxEntryInfo, TxPoolConfig,
+    TxStatus,
+};
 use crate::tx_pool::orphan::OrphanPool;
+use crate::tx_pool::package::parent_ids;
 use crate::tx_pool::pending::PendingQueue;
 use crate::tx_pool::proposed::ProposedPool;
 use ckb_core::transaction::{OutPoint, ProposalShortId, Transaction};
 use ckb_core::{Capacity, Cycle};
 use ckb_logger::{error_target, trace_target};
+use ckb_util::{FnvHashMap, FnvHashSet};
 use faketime::unix_time_as_millis;
 use lru_cache::LruCache;
 use numext_fixed_hash::H256;
+use std::slice;
 
 #[derive(Debug, Clone)]
 pub struct TxPool {
@@ -25,12 +31,16 @@ pub struct TxPool {
     pub(crate) conflict: LruCache<ProposalShortId, DefectEntry>,
     /// cache for committed transactions hash
     pub(crate) committed_txs_hash_cache: LruCache<ProposalShortId, H256>,
+    /// the reasons why the recent txs are rejected
+    pub(crate) rejected: LruCache<H256, PoolError>,
     /// last txs updated timestamp, used by getblocktemplate
     pub(crate) last_txs_updated_at: u64,
     // sum of all tx_pool tx's virtual sizes.
     pub(crate) total_tx_size: usize,
     // sum of all tx_pool tx's cycles.
     pub(crate) total_tx_cycles: Cycle,
+    // the number of txs evicted by the expiry or the orphan limit
+    pub(crate) evicted_count: u64,
 }
 
 impl TxPool {
@@ -47,9 +57,11 @@ impl TxPool {
             orphan: OrphanPool::new(),
             conflict: LruCache::new(conflict_cache_size),
             committed_txs_hash_cache: LruCache::new(committed_txs_hash_cache_size),
+            rejected: LruCache::new(conflict_cache_size),
             last_txs_updated_at,
             total_tx_size: 0,
             total_tx_cycles: 0,
+            evicted_count: 0,
         }
     }
 
@@ -74,6 +86,10 @@ impl TxPool {
         self.total_tx_cycles
     }
 
+    pub fn evicted_count(&self) -> u64 {
+        self.evicted_count
+    }
+
     pub fn reach_size_limit(&self, tx_size: usize) -> bool {
         (self.total_tx_size + tx_size) > self.config.max_mem_size
     }
@@ -111,18 +127,314 @@ impl TxPool {
         self.total_tx_cycles = total_tx_cycles;
     }
 
+    // Evicts the pending and gap txs to make room for `txs`, see `select_evicted`.
+    pub(crate) fn evict_for_txs<F>(
+        &mut self,
+        txs: &[Transaction],
+        size: usize,
+        cycles: Cycle,
+        fee_rate: FeeRate,
+        weight: F,
+    ) -> Result<Vec<PendingEntry>, PoolError>
+    where
+        F: Fn(usize, Cycle) -> u64,
+    {
+        let evicted =
+            self.select_evicted(txs, size, cycles, fee_rate, weight, &FnvHashSet::default())?;
+        Ok(self.remove_pending_entries(evicted))
+    }
+
+    // Adds a new tx by `insert`, making room for it by replacing the conflicting txs by fee if the
+    // rbf is enabled, and then evicting the txs paying lower fee rate if the pool is still full.
+    // The txs to remove are only selected before `insert`, and removed after it adds the tx, so
+    // nothing is removed if the replacement or the eviction is rejected, if `insert` fails, or if
+    // it returns false since the tx is in the pool already. Returns the replaced and the evicted
+    // entries.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn add_tx_with_room<F, I>(
+        &mut self,
+        tx: Transaction,
+        size: usize,
+        cycles: Cycle,
+        fee: Capacity,
+        fee_rate: FeeRate,
+        weight: F,
+        insert: I,
+    ) -> Result<(Vec<PendingEntry>, Vec<PendingEntry>), PoolError>
+    where
+        F: Fn(usize, Cycle) -> u64,
+        I: FnOnce(&mut TxPool, Transaction) -> Result<bool, PoolError>,
+    {
+        let replaced = if self.config.enable_rbf {
+            self.select_replaced(&tx, fee, fee_rate, &weight)?
+        } else {
+            FnvHashSet::default()
+        };
+        let evicted = self.select_evicted(
+            slice::from_ref(&tx),
+            size,
+            cycles,
+            fee_rate,
+            weight,
+            &replaced,
+        )?;
+        if !insert(self, tx)? {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        Ok((
+            self.remove_pending_entries(replaced),
+            self.remove_pending_entries(evicted),
+        ))
+    }
+
+    // Selects the pending and gap txs to evict to make room for `txs`, each together with its
+    // descendants, given that the `removed` txs are going to be removed. They are ranked by the
+    // fee rate of such a package, so a low fee parent is kept by its high fee children, and a
+    // package is evicted only if `fee_rate` is strictly higher than its fee rate. The ancestors
+    // of `txs` are kept. `LimitReached` is returned if the room can not be made.
+    fn select_evicted<F>(
+        &self,
+        txs: &[Transaction],
+        size: usize,
+        cycles: Cycle,
+        fee_rate: FeeRate,
+        weight: F,
+        removed: &FnvHashSet<ProposalShortId>,
+    ) -> Result<FnvHashSet<ProposalShortId>, PoolError>
+    where
+        F: Fn(usize, Cycle) -> u64,
+    {
+        let fits = |freed_size: usize, freed_cycles: Cycle| {
+            self.total_tx_size.saturating_sub(freed_size) + size <= self.config.max_mem_size
+                && self.total_tx_cycles.saturating_sub(freed_cycles) + cycles
+                    <= self.config.max_cycles
+        };
+        let (mut freed_size, mut freed_cycles) = (0, 0);
+        for entry in removed.iter().filter_map(|id| self.pending.get(id)) {
+            freed_size += entry.size;
+            freed_cycles += entry.cycles.unwrap_or(0);
+        }
+        let mut evicted = FnvHashSet::default();
+        if fits(freed_size, freed_cycles) {
+            return Ok(evicted);
+        }
+
+        let candidates: FnvHashMap<&ProposalShortId, &PendingEntry> = self
+            .pending
+            .inner
+            .iter()
+            .chain(self.gap.inner.iter())
+            .filter(|(id, _)| !removed.contains(*id))
+            .collect();
+        let children = self.pending_children();
+        let mut ancestors = FnvHashSet::default();
+        let mut stack: Vec<ProposalShortId> = txs.iter().flat_map(parent_ids).collect();
+        while let Some(id) = stack.pop() {
+            if let Some(entry) = candidates.get(&id) {
+                if ancestors.insert(id) {
+                    stack.extend(parent_ids(&entry.transaction));
+                }
+            }
+        }
+
+        let descendants = |id: ProposalShortId| {
+            let mut package = FnvHashSet::default();
+            let mut stack = vec![id];
+            while let Some(id) = stack.pop() {
+                if candidates.contains_key(&id) && package.insert(id) {
+                    if let Some(ids) = children.get(&id) {
+                        stack.extend(ids.iter().cloned());
+                    }
+                }
+            }
+            package
+        };
+        let mut ranks: Vec<(FeeRate, FnvHashSet<ProposalShortId>)> = candidates
+            .keys()
+            .filter(|id| !ancestors.contains(**id))
+            .map(|id| {
+                let package = descendants(**id);
+                let (mut package_fee, mut package_weight) = (0u64, 0u64);
+                for id in &package {
+                    let entry = candidates[id];
+                    let entry_fee = entry.fee.unwrap_or_default().as_u64();
+                    package_fee = package_fee.saturating_add(entry_fee);
+                    package_weight += weight(entry.size, entry.cycles.unwrap_or(0));
+                }
+                let rate = FeeRate::calculate(Capacity::shannons(package_fee), package_weight);
+                (rate, package)
+            })
+            .filter(|(rate, _)| *rate < fee_rate)
+            .collect();
+        ranks.sort_by_key(|(rate, _)| *rate);
+
+        for (_, package) in ranks {
+            if fits(freed_size, freed_cycles) {
+                break;
+            }
+            for id in package {
+                if evicted.insert(id) {
+                    let entry = candidates[&id];
+                    freed_size += entry.size;
+                    freed_cycles += entry.cycles.unwrap_or(0);
+                }
+            }
+        }
+        if !fits(freed_size, freed_cycles) {
+            return Err(PoolError::LimitReached);
+        }
+        Ok(evicted)
+    }
+
+    // Replace-by-fee, selects the pending txs spending any input of `tx`, together with their
+    // descendants, if `tx` pays strictly higher fee than all of them, and strictly higher fee rate
+    // than each conflicting one. Returns the selected ids, which are empty if `tx` does not
+    // conflict with the pending txs, or the reason why the replacement is rejected.
+    fn select_replaced<F>(
+        &self,
+        tx: &Transaction,
+        fee: Capacity,
+        fee_rate: FeeRate,
+        weight: F,
+    ) -> Result<FnvHashSet<ProposalShortId>, PoolError>
+    where
+        F: Fn(usize, Cycle) -> u64,
+    {
+        let short_id = tx.proposal_short_id();
+        let inputs: FnvHashSet<&OutPoint> = tx.input_pts_iter().collect();
+        let spends_inputs = |entry: &PendingEntry| {
+            entry.transaction.proposal_short_id() != short_id
+                && entry
+                    .transaction
+                    .input_pts_iter()
+                    .any(|input| inputs.contains(input))
+        };
+        if let Some(entry) = self.gap.values().find(|entry| spends_inputs(*entry)) {
+            return Err(PoolError::ReplaceRejected(format!(
+                "conflicts with the proposed tx {:#x}",
+                entry.transaction.hash()
+            )));
+        }
+        let conflicts: Vec<&PendingEntry> = self
+            .pending
+            .values()
+            .filter(|entry| spends_inputs(*entry))
+            .collect();
+        if conflicts.is_empty() {
+            return Ok(FnvHashSet::default());
+        }
+
+        let children = self.pending_children();
+        let mut replaced = FnvHashSet::default();
+        let mut stack: Vec<ProposalShortId> = conflicts
+            .iter()
+            .map(|entry| entry.transaction.proposal_short_id())
+            .collect();
+        while let Some(id) = stack.pop() {
+            if replaced.insert(id) {
+                if let Some(ids) = children.get(&id) {
+                    stack.extend(ids.iter().cloned());
+                }
+            }
+        }
+        if parent_ids(tx).iter().any(|id| replaced.contains(id)) {
+            return Err(PoolError::ReplaceRejected(
+                "spends the outputs of the replaced txs".to_owned(),
+            ));
+        }
+
+        let mut replaced_fee = 0u64;
+        for id in &replaced {
+            let entry = match self.pending.get(id) {
+                Some(entry) => entry,
+                None => {
+                    return Err(PoolError::ReplaceRejected(format!(
+                        "the descendant {:?} of the replaced txs is proposed",
+                        id
+                    )));
+                }
+            };
+            let entry_fee = entry.fee.ok_or_else(|| {
+                PoolError::ReplaceRejected(format!(
+                    "the fee of tx {:#x} is unknown",
+                    entry.transaction.hash()
+                ))
+            })?;
+            replaced_fee = replaced_fee.saturating_add(entry_fee.as_u64());
+        }
+        if fee.as_u64() <= replaced_fee {
+            return Err(PoolError::ReplaceRejected(format!(
+                "fee {} is not higher than the replaced fee {}",
+                fee.as_u64(),
+                replaced_fee
+            )));
+        }
+        for entry in conflicts {
+            let entry_weight = weight(entry.size, entry.cycles.unwrap_or(0));
+            let rate = FeeRate::calculate(entry.fee.unwrap_or_default(), entry_weight);
+            if fee_rate <= rate {
+                return Err(PoolError::ReplaceRejected(format!(
+                    "fee rate {} is not higher than the fee rate {} of tx {:#x}",
+                    fee_rate.as_u64(),
+                    rate.as_u64(),
+                    entry.transaction.hash()
+                )));
+            }
+        }
+        Ok(replaced)
+    }
+
+    // The children of the pending and gap txs among themselves
+    fn pending_children(&self) -> FnvHashMap<ProposalShortId, Vec<ProposalShortId>> {
+        let mut children: FnvHashMap<ProposalShortId, Vec<ProposalShortId>> = FnvHashMap::default();
+        for (id, entry) in self.pending.inner.iter().chain(self.gap.inner.iter()) {
+            for parent in parent_ids(&entry.transaction) {
+                if self.pending.contains_key(&parent) || self.gap.contains_key(&parent) {
+                    children.entry(parent).or_default().push(*id);
+                }
+            }
+        }
+        children
+    }
+
+    fn remove_pending_entries(
+        &mut self,
+        ids: impl IntoIterator<Item = ProposalShortId>,
+    ) -> Vec<PendingEntry> {
+        let mut entries = Vec::new();
+        for id in ids {
+            if let Some(entry) = self.pending.remove(&id).or_else(|| self.gap.remove(&id)) {
+                self.update_statics_for_remove_tx(entry.size, entry.cycles.unwrap_or(0));
+                entries.push(entry);
+            }
+        }
+        entries
+    }
+
     // enqueue_tx inserts a new transaction into pending queue.
     // If did have this value present, false is returned.
-    pub fn enqueue_tx(&mut self, cycles: Option<Cycle>, size: usize, tx: Transaction) -> bool {
+    pub fn enqueue_tx(
+        &mut self,
+        cycles: Option<Cycle>,
+        fee: Option<Capacity>,
+        size: usize,
+        tx: Transaction,
+    ) -> bool {
         if self.gap.contains_key(&tx.proposal_short_id()) {
             return false;
         }
-        self.pending.add_tx(cycles, size, tx).is_none()
+        self.pending.add_tx(cycles, fee, size, tx).is_none()
     }
 
     // add_gap inserts proposed but still uncommittable transaction.
-    pub fn add_gap(&mut self, cycles: Option<Cycle>, size: usize, tx: Transaction) -> bool {
-        self.gap.add_tx(cycles, size, tx).is_none()
+    pub fn add_gap(
+        &mut self,
+        cycles: Option<Cycle>,
+        fee: Option<Capacity>,
+        size: usize,
+        tx: Transaction,
+    ) -> bool {
+        self.gap.add_tx(cycles, fee, size, tx).is_none()
     }
 
     pub(crate) fn add_orphan(
@@ -133,7 +445,80 @@ impl TxPool {
         unknowns: Vec<OutPoint>,
     ) -> Option<DefectEntry> {
         trace_target!(crate::LOG_TARGET_TX_POOL, "add_orphan {:#x}", &tx.hash());
-        self.orphan.add_tx(cycles, size, tx, unknowns.into_iter())
+        let replaced = self.orphan.add_tx(cycles, size, tx, unknowns.into_iter());
+        let max_orphan_count = self.config.max_orphan_count;
+        if max_orphan_count > 0 {
+            while self.orphan.vertices.len() > max_orphan_count {
+                match self.orphan.oldest() {
+                    Some(id) => {
+                        self.evict_orphan(&id);
+                    }
+                    None => break,
+                }
+            }
+        }
+        replaced
+    }
+
+    // Evicts the orphan with its descendants, returns the number of the evicted txs
+    fn evict_orphan(&mut self, id: &ProposalShortId) -> usize {
+        let entries = self.orphan.recursion_remove(id);
+        for entry in &entries {
+            trace_target!(
+                crate::LOG_TARGET_TX_POOL,
+                "evict orphan {:#x}",
+                entry.transaction.hash()
+            );
+            self.update_statics_for_remove_tx(entry.size, entry.cycles.unwrap_or(0));
+        }
+        self.evicted_count += entries.len() as u64;
+        entries.len()
+    }
+
+    // Evicts the pending txs and the orphans which have stayed in the pool longer than their
+    // TTLs, each together with its pending and gap descendants, since a gap tx can not be
+    // committed without its parent. The gap txs are not expired themselves and the proposed txs
+    // are kept, since they are proposed already. Returns the number of the evicted txs.
+    pub fn evict_expired(&mut self, now: u64) -> usize {
+        let is_expired =
+            |timestamp: u64, ttl: u64| ttl > 0 && now.saturating_sub(timestamp) > ttl * 1000;
+        let mut evicted = 0;
+
+        let pending_ttl = self.config.pending_ttl;
+        let mut stack: Vec<ProposalShortId> = self
+            .pending
+            .inner
+            .iter()
+            .filter(|(_, entry)| is_expired(entry.timestamp, pending_ttl))
+            .map(|(id, _)| *id)
+            .collect();
+        if !stack.is_empty() {
+            let children = self.pending_children();
+            let mut expired = FnvHashSet::default();
+            while let Some(id) = stack.pop() {
+                if expired.insert(id) {
+                    if let Some(ids) = children.get(&id) {
+                        stack.extend(ids.iter().cloned());
+                    }
+                }
+            }
+            let entries = self.remove_pending_entries(expired);
+            self.evicted_count += entries.len() as u64;
+            evicted += entries.len();
+        }
+
+        let orphan_ttl = self.config.orphan_ttl;
+        let expired: Vec<ProposalShortId> = self
+            .orphan
+            .vertices
+            .iter()
+            .filter(|(_, entry)| is_expired(entry.timestamp, orphan_ttl))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            evicted += self.evict_orphan(&id);
+        }
+        evicted
     }
 
     pub(crate) fn add_proposed(
@@ -171,6 +556,82 @@ impl TxPool {
             || self.conflict.contains_key(id)
     }
 
+    // All the txs in the pool with their cycles, the proposed ones first in the topological order,
+    // then the gap, pending and orphan ones, so that they can be added back in order
+    pub fn txs_with_cycles(&self) -> Vec<(Transaction, Option<Cycle>)> {
+        let proposed = self
+            .proposed
+            .txs_iter()
+            .map(|entry| (entry.transaction.to_owned(), Some(entry.cycles)));
+        let queued = self
+            .gap
+            .values()
+            .chain(self.pending.values())
+            .map(|entry| (entry.transaction.to_owned(), entry.cycles));
+        let orphans = self
+            .orphan
+            .vertices
+            .values()
+            .map(|entry| (entry.transaction.to_owned(), entry.cycles));
+        proposed.chain(queued).chain(orphans).collect()
+    }
+
+    // The information of the pending, gap, proposed and orphan txs
+    pub fn entries_info(&self) -> Vec<(H256, TxEntryInfo)> {
+        let pending = self
+            .pending
+            .values()
+            .map(|entry| pending_entry_info(entry, TxStatus::Pending));
+        let gap = self
+            .gap
+            .values()
+            .map(|entry| pending_entry_info(entry, TxStatus::Gap));
+        let proposed = self.proposed.txs_iter().map(proposed_entry_info);
+        let orphan = self
+            .orphan
+            .vertices
+            .values()
+            .map(|entry| defect_entry_info(entry, TxStatus::Orphan));
+        pending
+            .chain(gap)
+            .chain(proposed)
+            .chain(orphan)
+            .map(|(tx, info)| (tx.hash().to_owned(), info))
+            .collect()
+    }
+
+    // The tx and its information, including the conflict ones
+    pub fn get_entry_info(&self, id: &ProposalShortId) -> Option<(Transaction, TxEntryInfo)> {
+        self.pending
+            .get(id)
+            .map(|entry| pending_entry_info(entry, TxStatus::Pending))
+            .or_else(|| {
+                self.gap
+                    .get(id)
+                    .map(|entry| pending_entry_info(entry, TxStatus::Gap))
+            })
+            .or_else(|| self.proposed.get(id).map(proposed_entry_info))
+            .or_else(|| {
+                self.orphan
+                    .get(id)
+                    .map(|entry| defect_entry_info(entry, TxStatus::Orphan))
+            })
+            .or_else(|| {
+                self.conflict
+                    .get(id)
+                    .map(|entry| defect_entry_info(entry, TxStatus::Conflict))
+            })
+            .map(|(tx, info)| (tx.to_owned(), info))
+    }
+
+    pub fn add_rejected(&mut self, tx_hash: H256, err: PoolError) {
+        self.rejected.insert(tx_hash, err);
+    }
+
+    pub fn get_rejected(&self, tx_hash: &H256) -> Option<&PoolError> {
+        self.rejected.get(tx_hash)
+    }
+
     pub fn get_tx_with_cycles(&self, id: &ProposalShortId) -> Option<(Transaction, Option<Cycle>)> {
         self.pending
             .get(id)
@@ -253,11 +714,428 @@ impl TxPool {
     pub fn remove_expired<'a>(&mut self, ids: impl Iterator<Item = &'a ProposalShortId>) {
         for id in ids {
             if let Some(entry) = self.gap.remove(id) {
-                self.enqueue_tx(entry.cycles, entry.size, entry.transaction);
+                self.enqueue_tx(entry.cycles, entry.fee, entry.size, entry.transaction);
             }
             for entry in self.proposed.remove(id) {
-                self.enqueue_tx(Some(entry.cycles), entry.size, entry.transaction);
+                self.enqueue_tx(
+                    Some(entry.cycles),
+                    Some(entry.fee),
+                    entry.size,
+                    entry.transaction,
+                );
             }
         }
     }
 }
+
+fn pending_entry_info(entry: &PendingEntry, status: TxStatus) -> (&Transaction, TxEntryInfo) {
+    let info = TxEntryInfo {
+        status,
+        size: entry.size,
+        cycles: entry.cycles,
+        fee: entry.fee,
+        timestamp: entry.timestamp,
+    };
+    (&entry.transaction, info)
+}
+
+fn proposed_entry_info(entry: &ProposedEntry) -> (&Transaction, TxEntryInfo) {
+    let info = TxEntryInfo {
+        status: TxStatus::Proposed,
+        size: entry.size,
+        cycles: Some(entry.cycles),
+        fee: Some(entry.fee),
+        timestamp: entry.timestamp,
+    };
+    (&entry.transaction, info)
+}
+
+fn defect_entry_info(entry: &DefectEntry, status: TxStatus) -> (&Transaction, TxEntryInfo) {
+    let info = TxEntryInfo {
+        status,
+        size: entry.size,
+        cycles: entry.cycles,
+        fee: None,
+        timestamp: entry.timestamp,
+    };
+    (&entry.transaction, info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_core::transaction::{CellInput, CellOutputBuilder, TransactionBuilder};
+    use ckb_core::Bytes;
+
+    fn build_tx(inputs: Vec<(&H256, u32)>, outputs_len: usize) -> Transaction {
+        TransactionBuilder::default()
+            .inputs(
+                inputs.into_iter().map(|(txid, index)| {
+                    CellInput::new(OutPoint::new_cell(txid.to_owned(), index), 0)
+                }),
+            )
+            .outputs((0..outputs_len).map(|i| {
+                CellOutputBuilder::default()
+                    .capacity(Capacity::bytes(i + 1).unwrap())
+                    .build()
+            }))
+            .outputs_data((0..outputs_len).map(|_| Bytes::new()))
+            .build()
+    }
+
+    fn add_pending(pool: &mut TxPool, tx: &Transaction, fee: u64, size: usize) {
+        pool.enqueue_tx(Some(0), Some(Capacity::shannons(fee)), size, tx.clone());
+        pool.update_statics_for_add_tx(size, 0);
+    }
+
+    // tx1 pays 1000 per 1000 weight, tx2 pays 500 and its child tx3 pays 3000
+    fn full_pool() -> (TxPool, Transaction, Transaction, Transaction) {
+        let mut pool = TxPool::new(TxPoolConfig {
+            max_mem_size: 300,
+            ..Default::default()
+        });
+        let tx1 = build_tx(vec![(&H256::zero(), 1)], 1);
+        let tx2 = build_tx(vec![(&H256::zero(), 2)], 1);
+        let tx3 = build_tx(vec![(tx2.hash(), 0)], 1);
+        add_pending(&mut pool, &tx1, 100, 100);
+        add_pending(&mut pool, &tx2, 50, 100);
+        add_pending(&mut pool, &tx3, 300, 100);
+        (pool, tx1, tx2, tx3)
+    }
+
+    fn evicted_ids(entries: Vec<PendingEntry>) -> FnvHashSet<ProposalShortId> {
+        entries
+            .into_iter()
+            .map(|entry| entry.transaction.proposal_short_id())
+            .collect()
+    }
+
+    #[test]
+    fn test_evict_lowest_package_fee_rate() {
+        let (mut pool, tx1, tx2, tx3) = full_pool();
+        let tx = build_tx(vec![(&H256::zero(), 4)], 1);
+        // tx2 pays 500 but the package of tx2 and tx3 pays 1750, higher than tx1
+        let evicted = pool
+            .evict_for_txs(&[tx], 100, 0, FeeRate(2000), |size, _| size as u64)
+            .expect("evict");
+
+        let expected = vec![tx1.proposal_short_id()];
+        assert_eq!(evicted_ids(evicted), expected.into_iter().collect());
+        assert!(pool.pending.contains_key(&tx2.proposal_short_id()));
+        assert!(pool.pending.contains_key(&tx3.proposal_short_id()));
+        assert_eq!(pool.total_tx_size(), 200);
+    }
+
+    #[test]
+    fn test_evict_package_with_descendants() {
+        let (mut pool, _, _, _) = full_pool();
+        let tx = build_tx(vec![(&H256::zero(), 4)], 1);
+        // the package of tx2 and tx3 is evicted only for a higher fee rate than 1750
+        assert_eq!(
+            pool.evict_for_txs(&[tx.clone()], 200, 0, FeeRate(1500), |size, _| size as u64)
+                .map(evicted_ids),
+            Err(PoolError::LimitReached)
+        );
+        assert_eq!(pool.pending_size(), 3);
+
+        let evicted = pool
+            .evict_for_txs(&[tx], 200, 0, FeeRate(2000), |size, _| size as u64)
+            .expect("evict");
+        assert_eq!(evicted.len(), 3);
+        assert_eq!(pool.total_tx_size(), 0);
+    }
+
+    #[test]
+    fn test_evict_keeps_ancestors() {
+        let (mut pool, tx1, tx2, tx3) = full_pool();
+        let tx = build_tx(vec![(tx2.hash(), 0)], 1);
+        let evicted = pool
+            .evict_for_txs(&[tx], 100, 0, FeeRate(2000), |size, _| size as u64)
+            .expect("evict");
+
+        let expected = vec![tx1.proposal_short_id()];
+        assert_eq!(evicted_ids(evicted), expected.into_iter().collect());
+        assert!(pool.pending.contains_key(&tx2.proposal_short_id()));
+        assert!(pool.pending.contains_key(&tx3.proposal_short_id()));
+        assert_eq!(pool.total_tx_size(), 200);
+    }
+
+    #[test]
+    fn test_evict_nothing_for_low_fee_rate() {
+        let (mut pool, _, _, _) = full_pool();
+        let tx = build_tx(vec![(&H256::zero(), 4)], 1);
+        assert_eq!(
+            pool.evict_for_txs(&[tx], 100, 0, FeeRate(400), |size, _| size as u64)
+                .map(evicted_ids),
+            Err(PoolError::LimitReached)
+        );
+        assert_eq!(pool.pending_size(), 3);
+        assert_eq!(pool.total_tx_size(), 300);
+    }
+
+    type Removed = (FnvHashSet<ProposalShortId>, FnvHashSet<ProposalShortId>);
+
+    // Adds the tx by `add_tx_with_room`, whose insert returns `inserted` after adding the tx to
+    // the pending queue if it is `Ok(true)`
+    fn add_with_room(
+        pool: &mut TxPool,
+        tx: &Transaction,
+        size: usize,
+        fee: u64,
+        fee_rate: u64,
+        inserted: Result<bool, PoolError>,
+    ) -> Result<Removed, PoolError> {
+        pool.add_tx_with_room(
+            tx.clone(),
+            size,
+            0,
+            Capacity::shannons(fee),
+            FeeRate(fee_rate),
+            |size, _| size as u64,
+            |pool, tx| {
+                if inserted == Ok(true) {
+                    add_pending(pool, &tx, fee, size);
+                }
+                inserted
+            },
+        )
+        .map(|(replaced, evicted)| (evicted_ids(replaced), evicted_ids(evicted)))
+    }
+
+    fn replace_by_fee(
+        pool: &mut TxPool,
+        tx: &Transaction,
+        size: usize,
+        fee: u64,
+        fee_rate: u64,
+    ) -> Result<Removed, PoolError> {
+        pool.config.enable_rbf = true;
+        add_with_room(pool, tx, size, fee, fee_rate, Ok(true))
+    }
+
+    #[test]
+    fn test_evict_after_insert() {
+        let (mut pool, tx1, _, _) = full_pool();
+        let tx = build_tx(vec![(&H256::zero(), 4)], 1);
+        // tx1 is kept if the tx fails to be inserted or is in the pool already
+        assert_eq!(
+            add_with_room(&mut pool, &tx, 100, 200, 2000, Err(PoolError::TxFee)),
+            Err(PoolError::TxFee)
+        );
+        assert_eq!(
+            add_with_room(&mut pool, &tx, 100, 200, 2000, Ok(false)),
+            Ok((FnvHashSet::default(), FnvHashSet::default()))
+        );
+        assert!(pool.pending.contains_key(&tx1.proposal_short_id()));
+        assert_eq!(pool.pending_size(), 3);
+        assert_eq!(pool.total_tx_size(), 300);
+
+        let (replaced, evicted) =
+            add_with_room(&mut pool, &tx, 100, 200, 2000, Ok(true)).expect("add");
+        assert!(replaced.is_empty());
+        assert_eq!(evicted, vec![tx1.proposal_short_id()].into_iter().collect());
+        assert!(pool.pending.contains_key(&tx.proposal_short_id()));
+        assert_eq!(pool.pending_size(), 3);
+        assert_eq!(pool.total_tx_size(), 300);
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let (mut pool, tx1, tx2, tx3) = full_pool();
+        // spends the input of tx2
+        let tx = build_tx(vec![(&H256::zero(), 2)], 2);
+        let (replaced, evicted) = replace_by_fee(&mut pool, &tx, 100, 400, 4000).expect("replace");
+
+        let expected = vec![tx2.proposal_short_id(), tx3.proposal_short_id()];
+        assert_eq!(replaced, expected.into_iter().collect());
+        assert!(evicted.is_empty());
+        assert!(pool.pending.contains_key(&tx1.proposal_short_id()));
+        assert!(pool.pending.contains_key(&tx.proposal_short_id()));
+        assert_eq!(pool.total_tx_size(), 200);
+    }
+
+    #[test]
+    fn test_replace_by_fee_after_insert() {
+        let (mut pool, _, tx2, tx3) = full_pool();
+        pool.config.enable_rbf = true;
+        let tx = build_tx(vec![(&H256::zero(), 2)], 2);
+        // tx2 and tx3 stay if the replacement fails to be inserted
+        assert_eq!(
+            add_with_room(&mut pool, &tx, 100, 400, 4000, Err(PoolError::TxFee)),
+            Err(PoolError::TxFee)
+        );
+        assert!(pool.pending.contains_key(&tx2.proposal_short_id()));
+        assert!(pool.pending.contains_key(&tx3.proposal_short_id()));
+        assert!(!pool.pending.contains_key(&tx.proposal_short_id()));
+        assert_eq!(pool.total_tx_size(), 300);
+    }
+
+    #[test]
+    fn test_replace_by_fee_without_conflicts() {
+        let (mut pool, _, _, _) = full_pool();
+        let tx = build_tx(vec![(&H256::zero(), 4)], 1);
+        assert_eq!(
+            replace_by_fee(&mut pool, &tx, 0, 0, 0),
+            Ok((FnvHashSet::default(), FnvHashSet::default()))
+        );
+        assert_eq!(pool.pending_size(), 4);
+    }
+
+    #[test]
+    fn test_replace_by_fee_rejected() {
+        let (mut pool, _, _, _) = full_pool();
+        let tx = build_tx(vec![(&H256::zero(), 2)], 2);
+        let replace = |pool: &mut TxPool, fee, fee_rate| match replace_by_fee(
+            pool, &tx, 100, fee, fee_rate,
+        ) {
+            Err(PoolError::ReplaceRejected(_)) => true,
+            _ => false,
+        };
+
+        // not higher than the fee of tx2 and tx3
+        assert!(replace(&mut pool, 350, 4000));
+        // not higher than the fee rate of tx2
+        assert!(replace(&mut pool, 400, 500));
+        assert_eq!(pool.pending_size(), 3);
+        assert_eq!(pool.total_tx_size(), 300);
+    }
+
+    #[test]
+    fn test_replace_by_fee_in_full_pool() {
+        let (mut pool, tx1, tx2, tx3) = full_pool();
+        // spends the input of tx1, and needs room for another 100 after replacing it
+        let tx = build_tx(vec![(&H256::zero(), 1)], 2);
+        assert_eq!(
+            replace_by_fee(&mut pool, &tx, 200, 400, 1500),
+            Err(PoolError::LimitReached)
+        );
+        // tx1 is not replaced if the room can not be made
+        assert!(pool.pending.contains_key(&tx1.proposal_short_id()));
+        assert_eq!(pool.pending_size(), 3);
+        assert_eq!(pool.total_tx_size(), 300);
+
+        let (replaced, evicted) = replace_by_fee(&mut pool, &tx, 200, 400, 2000).expect("replace");
+        assert_eq!(
+            replaced,
+            vec![tx1.proposal_short_id()].into_iter().collect()
+        );
+        let expected = vec![tx2.proposal_short_id(), tx3.proposal_short_id()];
+        assert_eq!(evicted, expected.into_iter().collect());
+        assert_eq!(pool.pending_size(), 1);
+        assert_eq!(pool.total_tx_size(), 200);
+    }
+
+    #[test]
+    fn test_replace_by_fee_conflicts_with_gap() {
+        let mut pool = TxPool::new(TxPoolConfig::default());
+        let tx1 = build_tx(vec![(&H256::zero(), 1)], 1);
+        pool.add_gap(Some(0), Some(Capacity::zero()), 100, tx1);
+
+        let tx = build_tx(vec![(&H256::zero(), 1)], 2);
+        match replace_by_fee(&mut pool, &tx, 100, 1000, 1000) {
+            Err(PoolError::ReplaceRejected(_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(pool.gap_size(), 1);
+    }
+
+    #[test]
+    fn test_evict_expired_pending_with_descendants() {
+        let (mut pool, tx1, tx2, tx3) = full_pool();
+        pool.config.pending_ttl = 60;
+        let now = 120_000;
+        for (tx, timestamp) in vec![(&tx1, now), (&tx2, 0), (&tx3, now)] {
+            pool.pending
+                .inner
+                .get_mut(&tx.proposal_short_id())
+                .unwrap()
+                .timestamp = timestamp;
+        }
+
+        assert_eq!(pool.evict_expired(now), 2);
+        assert!(pool.pending.contains_key(&tx1.proposal_short_id()));
+        assert!(!pool.pending.contains_key(&tx3.proposal_short_id()));
+        assert_eq!(pool.total_tx_size(), 100);
+        assert_eq!(pool.evicted_count(), 2);
+        assert_eq!(pool.evict_expired(now), 0);
+    }
+
+    #[test]
+    fn test_evict_expired_pending_with_gap_child() {
+        let mut pool = TxPool::new(TxPoolConfig {
+            pending_ttl: 60,
+            ..Default::default()
+        });
+        let tx1 = build_tx(vec![(&H256::zero(), 1)], 1);
+        let tx2 = build_tx(vec![(tx1.hash(), 0)], 1);
+        let tx3 = build_tx(vec![(&H256::zero(), 3)], 1);
+        add_pending(&mut pool, &tx1, 100, 100);
+        pool.add_gap(Some(0), Some(Capacity::zero()), 100, tx2.clone());
+        pool.add_gap(Some(0), Some(Capacity::zero()), 100, tx3.clone());
+        pool.update_statics_for_add_tx(200, 0);
+        pool.pending
+            .inner
+            .get_mut(&tx1.proposal_short_id())
+            .unwrap()
+            .timestamp = 0;
+        for tx in &[&tx2, &tx3] {
+            pool.gap
+                .inner
+                .get_mut(&tx.proposal_short_id())
+                .unwrap()
+                .timestamp = 0;
+        }
+
+        // the gap child tx2 is evicted with its expired parent, the gap tx3 does not expire
+        assert_eq!(pool.evict_expired(120_000), 2);
+        assert_eq!(pool.pending_size(), 0);
+        assert!(!pool.gap.contains_key(&tx2.proposal_short_id()));
+        assert!(pool.gap.contains_key(&tx3.proposal_short_id()));
+        assert_eq!(pool.total_tx_size(), 100);
+    }
+
+    #[test]
+    fn test_orphan_limit_and_expiry() {
+        let mut pool = TxPool::new(TxPoolConfig {
+            orphan_ttl: 60,
+            max_orphan_count: 2,
+            ..Default::default()
+        });
+        let add_orphan = |pool: &mut TxPool, tx: &Transaction, unknowns: Vec<OutPoint>| {
+            pool.update_statics_for_add_tx(100, 0);
+            pool.add_orphan(Some(0), 100, tx.clone(), unknowns);
+        };
+        let tx1 = build_tx(vec![(&H256::zero(), 1)], 1);
+        let tx2 = build_tx(vec![(tx1.hash(), 0)], 1);
+        let tx3 = build_tx(vec![(tx2.hash(), 0)], 1);
+        let tx4 = build_tx(vec![(&H256::zero(), 4)], 1);
+        add_orphan(&mut pool, &tx2, tx1.output_pts());
+        add_orphan(&mut pool, &tx3, tx2.output_pts());
+        pool.orphan
+            .vertices
+            .get_mut(&tx2.proposal_short_id())
+            .unwrap()
+            .timestamp = 1;
+        pool.orphan
+            .vertices
+            .get_mut(&tx3.proposal_short_id())
+            .unwrap()
+            .timestamp = 2;
+
+        // the oldest tx2 is evicted with its descendant tx3
+        add_orphan(&mut pool, &tx4, vec![OutPoint::new_cell(H256::zero(), 3)]);
+        assert_eq!(pool.orphan_size(), 1);
+        assert!(pool.orphan.contains_key(&tx4.proposal_short_id()));
+        assert_eq!(pool.total_tx_size(), 100);
+
+        pool.orphan
+            .vertices
+            .get_mut(&tx4.proposal_short_id())
+            .unwrap()
+            .timestamp = 0;
+        assert_eq!(pool.evict_expired(120_000), 1);
+        assert_eq!(pool.orphan_size(), 0);
+        assert_eq!(pool.total_tx_size(), 0);
+        assert_eq!(pool.evicted_count(), 3);
+    }
+}
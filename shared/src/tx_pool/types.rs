@@ -7,7 +7,9 @@ use ckb_core::Capacity;
 use ckb_core::Cycle;
 use ckb_verification::TransactionError;
 use failure::Fail;
+use faketime::unix_time_as_millis;
 use serde_derive::{Deserialize, Serialize};
+use std::cmp;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 
@@ -24,6 +26,28 @@ pub struct TxPoolConfig {
     pub max_conflict_cache_size: usize,
     // committed transactions hash cache capacity
     pub max_committed_txs_hash_cache_size: usize,
+    // txs paying lower fee rate are rejected, in shannons per 1000 units of weight
+    #[serde(default)]
+    pub min_fee_rate: FeeRate,
+    // replace the conflicting pending txs by the one paying higher fee
+    #[serde(default)]
+    pub enable_rbf: bool,
+    // the pool saved on shutdown is reloaded if it is not older than <max_persisted_age> seconds,
+    // 0 disables the persistence
+    #[serde(default)]
+    pub max_persisted_age: u64,
+    // the pending txs not proposed in <pending_ttl> seconds are evicted with their descendants,
+    // 0 disables the expiry
+    #[serde(default)]
+    pub pending_ttl: u64,
+    // the orphans not resolved in <orphan_ttl> seconds are evicted with their descendants,
+    // 0 disables the expiry
+    #[serde(default)]
+    pub orphan_ttl: u64,
+    // the oldest orphans are evicted when there are more than <max_orphan_count> orphans,
+    // 0 means no limit
+    #[serde(default)]
+    pub max_orphan_count: usize,
 }
 
 impl Default for TxPoolConfig {
@@ -34,6 +58,12 @@ impl Default for TxPoolConfig {
             max_verify_cache_size: 100_000,
             max_conflict_cache_size: 1_000,
             max_committed_txs_hash_cache_size: 100_000,
+            min_fee_rate: FeeRate::default(),
+            enable_rbf: false,
+            max_persisted_age: 0,
+            pending_ttl: 0,
+            orphan_ttl: 0,
+            max_orphan_count: 0,
         }
     }
 }
@@ -56,6 +86,12 @@ pub enum PoolError {
     Duplicate,
     /// tx fee
     TxFee,
+    /// The fee rate is lower than the min fee rate of the pool
+    LowFeeRate(FeeRate),
+    /// The tx can not replace the conflicting pending txs by fee, with the reason
+    ReplaceRejected(String),
+    /// The txs can not be added as a package, with the reason
+    PackageRejected(String),
 }
 
 impl PoolError {
@@ -86,6 +122,8 @@ pub struct DefectEntry {
     pub cycles: Option<Cycle>,
     /// tx size
     pub size: usize,
+    /// the time when the entry is created, in milliseconds
+    pub timestamp: u64,
 }
 
 impl DefectEntry {
@@ -101,6 +139,7 @@ impl DefectEntry {
             refs_count,
             cycles,
             size,
+            timestamp: unix_time_as_millis(),
         }
     }
 }
@@ -112,17 +151,28 @@ pub struct PendingEntry {
     pub transaction: Transaction,
     /// Cycles
     pub cycles: Option<Cycle>,
+    /// fee, None if the tx has not been resolved
+    pub fee: Option<Capacity>,
     /// tx size
     pub size: usize,
+    /// the time when the entry is created, in milliseconds
+    pub timestamp: u64,
 }
 
 impl PendingEntry {
     /// Create new transaction pool entry
-    pub fn new(tx: Transaction, cycles: Option<Cycle>, size: usize) -> PendingEntry {
+    pub fn new(
+        tx: Transaction,
+        cycles: Option<Cycle>,
+        fee: Option<Capacity>,
+        size: usize,
+    ) -> PendingEntry {
         PendingEntry {
             transaction: tx,
             cycles,
+            fee,
             size,
+            timestamp: unix_time_as_millis(),
         }
     }
 }
@@ -140,6 +190,8 @@ pub struct ProposedEntry {
     pub fee: Capacity,
     /// tx size
     pub size: usize,
+    /// the time when the entry is created, in milliseconds
+    pub timestamp: u64,
 }
 
 impl ProposedEntry {
@@ -157,10 +209,69 @@ impl ProposedEntry {
             cycles,
             fee,
             size,
+            timestamp: unix_time_as_millis(),
         }
     }
 }
 
+/// The status of a tx in the pool
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Not proposed yet
+    Pending,
+    /// Proposed but not committable yet
+    Gap,
+    /// Committable
+    Proposed,
+    /// Some inputs or deps are unknown
+    Orphan,
+    /// Conflicts with the proposed txs
+    Conflict,
+}
+
+/// The information of a tx in the pool
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxEntryInfo {
+    /// the status
+    pub status: TxStatus,
+    /// tx size
+    pub size: usize,
+    /// Cycles, None if the tx has not been verified
+    pub cycles: Option<Cycle>,
+    /// fee, None if the tx has not been resolved
+    pub fee: Option<Capacity>,
+    /// the time when the tx entered the status, in milliseconds
+    pub timestamp: u64,
+}
+
+/// The fee rate in shannons per 1000 units of transaction weight, see `tx_weight`.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct FeeRate(pub u64);
+
+impl FeeRate {
+    pub fn calculate(fee: Capacity, weight: u64) -> FeeRate {
+        let rate = u128::from(fee.as_u64()) * 1000 / u128::from(cmp::max(weight, 1));
+        FeeRate(cmp::min(rate, u128::from(u64::max_value())) as u64)
+    }
+
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+/// The weight of a transaction is its size, or the size in proportion to its cycles if the cycles
+/// take a larger share of the block limits, so that a transaction is weighed by the scarcer one.
+pub fn tx_weight(size: usize, cycles: Cycle, max_block_bytes: u64, max_block_cycles: Cycle) -> u64 {
+    let cycles_weight = u128::from(cycles) * u128::from(max_block_bytes)
+        / u128::from(cmp::max(max_block_cycles, 1));
+    cmp::max(
+        size as u64,
+        cmp::min(cycles_weight, u128::from(u64::max_value())) as u64,
+    )
+}
+
 impl Hash for ProposedEntry {
     fn hash<H: Hasher>(&self, state: &mut H) {
         Hash::hash(&self.transaction, state);
@@ -0,0 +1,100 @@
+use ckb_shared::shared::Shared;
+use ckb_stop_handler::{SignalSender, StopHandler};
+use crossbeam_channel::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+
+pub struct TxPoolExpiryController {
+    stop: StopHandler<()>,
+}
+
+impl Drop for TxPoolExpiryController {
+    fn drop(&mut self) {
+        self.stop.try_send();
+    }
+}
+
+// Evicts the pool txs which have stayed longer than their TTLs periodically
+pub struct TxPoolExpiryService {
+    shared: Shared,
+    interval: Duration,
+}
+
+impl TxPoolExpiryService {
+    pub fn new(shared: Shared, interval: Duration) -> TxPoolExpiryService {
+        TxPoolExpiryService { shared, interval }
+    }
+
+    pub fn start<S: ToString>(self, thread_name: Option<S>) -> TxPoolExpiryController {
+        let (signal_sender, signal_receiver) = crossbeam_channel::bounded::<()>(1);
+        let mut thread_builder = thread::Builder::new();
+        // Mainly for test: give a empty thread_name
+        if let Some(name) = thread_name {
+            thread_builder = thread_builder.name(name.to_string());
+        }
+        let thread = thread_builder
+            .spawn(move || loop {
+                match signal_receiver.recv_timeout(self.interval) {
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.shared.lock_chain_state().evict_expired_txs();
+                    }
+                    _ => break,
+                }
+            })
+            .expect("Start TxPoolExpiryService failed");
+
+        TxPoolExpiryController {
+            stop: StopHandler::new(SignalSender::Crossbeam(signal_sender), thread),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_pool_executor::tests::setup_with_tx_pool_config;
+    use crate::TxPoolExecutor;
+    use ckb_core::transaction::{CellInput, CellOutputBuilder, OutPoint, TransactionBuilder};
+    use ckb_core::{capacity_bytes, Bytes, Capacity};
+    use ckb_shared::tx_pool::TxPoolConfig;
+    use ckb_store::ChainStore;
+
+    #[test]
+    fn test_evict_expired_periodically() {
+        let (shared, always_success_out_point) = setup_with_tx_pool_config(
+            1,
+            TxPoolConfig {
+                pending_ttl: 1,
+                ..Default::default()
+            },
+        );
+        let last_block = shared
+            .store()
+            .get_block(&shared.lock_chain_state().tip_hash())
+            .unwrap();
+        let last_cellbase = last_block.transactions().first().unwrap();
+        let tx = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new_cell(last_cellbase.hash().to_owned(), 0),
+                0,
+            ))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(50))
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .dep(always_success_out_point)
+            .build();
+        TxPoolExecutor::new(shared.clone())
+            .verify_and_add_tx_to_pool(tx)
+            .expect("add tx");
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 1);
+
+        let controller = TxPoolExpiryService::new(shared.clone(), Duration::from_millis(100))
+            .start::<&str>(None);
+        thread::sleep(Duration::from_millis(1500));
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 0);
+        drop(controller);
+    }
+}
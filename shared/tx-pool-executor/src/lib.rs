@@ -1,3 +1,7 @@
+mod expiry;
+mod persist;
 mod tx_pool_executor;
 
+pub use expiry::{TxPoolExpiryController, TxPoolExpiryService};
+pub use persist::{TxPoolPersistController, TxPoolPersistService};
 pub use tx_pool_executor::TxPoolExecutor;
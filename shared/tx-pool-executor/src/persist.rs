@@ -0,0 +1,225 @@
+use crate::TxPoolExecutor;
+use ckb_core::transaction::Transaction;
+use ckb_core::Cycle;
+use ckb_logger::{debug, error, info};
+use ckb_shared::shared::Shared;
+use ckb_stop_handler::{SignalSender, StopHandler};
+use faketime::unix_time_as_millis;
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::thread;
+
+// The pool txs saved on shutdown, with the cycles of their scripts
+#[derive(Serialize, Deserialize)]
+struct PersistedTxPool {
+    saved_at: u64,
+    txs: Vec<(Transaction, Option<Cycle>)>,
+}
+
+pub struct TxPoolPersistController {
+    stop: StopHandler<()>,
+}
+
+impl Drop for TxPoolPersistController {
+    fn drop(&mut self) {
+        self.stop.try_send();
+    }
+}
+
+// Reloads the persisted pool on start, and saves the pool to the file when stopped
+pub struct TxPoolPersistService {
+    shared: Shared,
+    path: PathBuf,
+    // in seconds
+    max_age: u64,
+}
+
+impl TxPoolPersistService {
+    pub fn new(shared: Shared, path: PathBuf, max_age: u64) -> TxPoolPersistService {
+        TxPoolPersistService {
+            shared,
+            path,
+            max_age,
+        }
+    }
+
+    // Loads the persisted pool before returning, so the pool is restored before the network and
+    // sync services start, and saves the pool on a thread waiting for the stop signal.
+    pub fn start<S: ToString>(self, thread_name: Option<S>) -> TxPoolPersistController {
+        self.load();
+        let (signal_sender, signal_receiver) = crossbeam_channel::bounded::<()>(1);
+        let mut thread_builder = thread::Builder::new();
+        // Mainly for test: give a empty thread_name
+        if let Some(name) = thread_name {
+            thread_builder = thread_builder.name(name.to_string());
+        }
+        let thread = thread_builder
+            .spawn(move || {
+                let _ = signal_receiver.recv();
+                self.save();
+            })
+            .expect("Start TxPoolPersistService failed");
+
+        TxPoolPersistController {
+            stop: StopHandler::new(SignalSender::Crossbeam(signal_sender), thread),
+        }
+    }
+
+    // The txs are verified again and added to the pool one by one, the cycles are only used to
+    // skip running the scripts.
+    pub(crate) fn load(&self) {
+        let raw = match fs::read(&self.path) {
+            Ok(raw) => raw,
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return,
+            Err(err) => {
+                error!("Failed to read the persisted tx pool: {:?}", err);
+                return;
+            }
+        };
+        // the txs are not loaded twice if the node stops without saving the pool
+        if let Err(err) = fs::remove_file(&self.path) {
+            error!("Failed to remove the persisted tx pool: {:?}", err);
+        }
+        let persisted: PersistedTxPool = match bincode::deserialize(&raw) {
+            Ok(persisted) => persisted,
+            Err(err) => {
+                error!("Malformed persisted tx pool: {:?}", err);
+                return;
+            }
+        };
+        let age = unix_time_as_millis().saturating_sub(persisted.saved_at) / 1000;
+        if age > self.max_age {
+            info!("Discard the tx pool persisted {} seconds ago", age);
+            return;
+        }
+
+        {
+            let mut txs_verify_cache = self.shared.lock_txs_verify_cache();
+            for (tx, cycles) in &persisted.txs {
+                if let Some(cycles) = cycles {
+                    txs_verify_cache.insert(tx.hash().to_owned(), *cycles);
+                }
+            }
+        }
+        let tx_pool_executor = TxPoolExecutor::new(self.shared.clone());
+        let total = persisted.txs.len();
+        let mut loaded = 0;
+        for (tx, _) in persisted.txs {
+            let tx_hash = tx.hash().to_owned();
+            match tx_pool_executor.verify_and_add_tx_to_pool(tx) {
+                Ok(_) => loaded += 1,
+                Err(err) => debug!("Drop persisted tx {:#x}: {:?}", tx_hash, err),
+            }
+        }
+        info!("Loaded {} of {} persisted txs into the pool", loaded, total);
+    }
+
+    pub(crate) fn save(&self) {
+        let txs = self.shared.lock_chain_state().tx_pool().txs_with_cycles();
+        let total = txs.len();
+        let persisted = PersistedTxPool {
+            saved_at: unix_time_as_millis(),
+            txs,
+        };
+        let raw = bincode::serialize(&persisted).expect("serialize persisted tx pool");
+        match fs::write(&self.path, raw) {
+            Ok(()) => info!("Saved {} txs of the pool to {}", total, self.path.display()),
+            Err(err) => error!("Failed to save the tx pool: {:?}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_pool_executor::tests::setup;
+    use ckb_core::transaction::{CellInput, CellOutputBuilder, OutPoint, TransactionBuilder};
+    use ckb_core::{capacity_bytes, Bytes, Capacity};
+    use ckb_shared::tx_pool::{TxPool, TxPoolConfig};
+    use ckb_store::ChainStore;
+
+    fn build_txs(shared: &Shared, always_success_out_point: &OutPoint) -> Vec<Transaction> {
+        let last_block = shared
+            .store()
+            .get_block(&shared.lock_chain_state().tip_hash())
+            .unwrap();
+        let last_cellbase = last_block.transactions().first().unwrap();
+        (0..3u32)
+            .map(|i| {
+                TransactionBuilder::default()
+                    .input(CellInput::new(
+                        OutPoint::new_cell(last_cellbase.hash().to_owned(), i),
+                        0,
+                    ))
+                    .output(
+                        CellOutputBuilder::default()
+                            .capacity(capacity_bytes!(50))
+                            .build(),
+                    )
+                    .output_data(Bytes::new())
+                    .dep(always_success_out_point.to_owned())
+                    .build()
+            })
+            .collect()
+    }
+
+    fn clear_tx_pool(shared: &Shared) {
+        *shared.lock_chain_state().mut_tx_pool() = TxPool::new(TxPoolConfig::default());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let (shared, always_success_out_point) = setup(10);
+        let txs = build_txs(&shared, &always_success_out_point);
+        let tx_pool_executor = TxPoolExecutor::new(shared.clone());
+        for tx in &txs {
+            tx_pool_executor
+                .verify_and_add_tx_to_pool(tx.to_owned())
+                .expect("add tx");
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tx_pool");
+        let service = TxPoolPersistService::new(shared.clone(), path.clone(), 60);
+        service.save();
+        clear_tx_pool(&shared);
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 0);
+
+        service.load();
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 3);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_discard_expired() {
+        let (shared, always_success_out_point) = setup(10);
+        let max_age = 60;
+        let persisted = PersistedTxPool {
+            saved_at: unix_time_as_millis() - (max_age + 1) * 1000,
+            txs: build_txs(&shared, &always_success_out_point)
+                .into_iter()
+                .map(|tx| (tx, None))
+                .collect(),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tx_pool");
+        fs::write(&path, bincode::serialize(&persisted).unwrap()).unwrap();
+        TxPoolPersistService::new(shared.clone(), path.clone(), max_age).load();
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 0);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_load_malformed() {
+        let (shared, _) = setup(1);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tx_pool");
+        fs::write(&path, b"malformed").unwrap();
+        TxPoolPersistService::new(shared.clone(), path.clone(), 60).load();
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 0);
+        assert!(!path.exists());
+    }
+}
@@ -5,8 +5,10 @@ use ckb_store::ChainStore;
 use ckb_traits::chain_provider::ChainProvider;
 use ckb_traits::BlockMedianTimeContext;
 use ckb_verification::TransactionVerifier;
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use numext_fixed_hash::H256;
+use std::slice;
+use std::sync::Arc;
 
 struct StoreBlockMedianTimeContext<'a, CS> {
     store: &'a CS,
@@ -47,13 +49,41 @@ impl TxPoolExecutor {
             .map(|cycles_vec| *cycles_vec.get(0).expect("tx verified cycles"))
     }
 
+    // Verifies and adds the txs to pool one by one, stops at the first rejected one
     pub fn verify_and_add_txs_to_pool(
         &self,
         txs: Vec<Transaction>,
+    ) -> Result<Vec<Cycle>, PoolError> {
+        self.verify_and_add(txs, false)
+    }
+
+    // Verifies and adds the txs to pool as a package, see `ChainState::add_package_to_pool`.
+    // The txs are in the topological order, and each one except the first spends an output of
+    // a previous one, so that a low fee tx is only brought in by its own descendants.
+    pub fn verify_and_add_package_to_pool(
+        &self,
+        txs: Vec<Transaction>,
+    ) -> Result<Vec<Cycle>, PoolError> {
+        self.verify_and_add(txs, true)
+    }
+
+    fn verify_and_add(
+        &self,
+        txs: Vec<Transaction>,
+        package: bool,
     ) -> Result<Vec<Cycle>, PoolError> {
         if txs.is_empty() {
             return Ok(Vec::new());
         }
+        if let Err(err) = check_txs(&txs, package) {
+            let chain_state = self.shared.lock_chain_state();
+            for tx in &txs {
+                chain_state.add_rejected_tx(tx.hash().to_owned(), err.to_owned());
+            }
+            drop(chain_state);
+            self.notify_txs(&txs, Some(&err));
+            return Err(err);
+        }
         // resolve txs
         // early release the chain_state lock because tx verification is slow
         let (
@@ -74,11 +104,17 @@ impl TxPoolExecutor {
             let mut resolved_txs = Vec::with_capacity(txs.len());
             let mut unresolvable_txs = Vec::with_capacity(txs.len());
             let mut cached_txs = Vec::with_capacity(txs.len());
-            for tx in &txs {
+            let mut seen_inputs = FnvHashSet::default();
+            for (index, tx) in txs.iter().enumerate() {
                 if let Some(cycles) = txs_verify_cache.get(tx.hash()) {
                     cached_txs.push((tx.hash().to_owned(), Ok(*cycles)));
                 } else {
-                    match chain_state.resolve_tx_from_pending_and_proposed(tx) {
+                    let resolved = if package {
+                        chain_state.resolve_tx_from_package(tx, &txs[..index], &mut seen_inputs)
+                    } else {
+                        chain_state.resolve_tx_from_pending_and_proposed(tx)
+                    };
+                    match resolved {
                         Ok(resolved_tx) => resolved_txs.push((tx.hash().to_owned(), resolved_tx)),
                         Err(err) => unresolvable_txs.push((
                             tx.hash().to_owned(),
@@ -100,7 +136,13 @@ impl TxPoolExecutor {
 
         // immediately return if resolved_txs is empty
         if resolved_txs.is_empty() && cached_txs.is_empty() {
-            let (_tx, err) = unresolvable_txs.get(0).expect("unresolved tx exists");
+            let (tx_hash, err) = unresolvable_txs.get(0).expect("unresolved tx exists");
+            self.shared
+                .lock_chain_state()
+                .add_rejected_tx(tx_hash.to_owned(), err.to_owned());
+            if let Some(tx) = txs.iter().find(|tx| tx.hash() == tx_hash) {
+                self.notify_txs(slice::from_ref(tx), Some(err));
+            }
             return Err(err.to_owned());
         }
 
@@ -153,8 +195,8 @@ impl TxPoolExecutor {
                 .collect::<Vec<(H256, Result<Cycle, _>)>>()
         };
 
-        // join all txs
-        let cycles_vec = {
+        // join all txs, the hashes are unique as checked
+        let verified = {
             let mut cycles_vec = cycles_vec
                 .into_iter()
                 .chain(cached_txs)
@@ -165,22 +207,92 @@ impl TxPoolExecutor {
                     cycles_vec
                         .remove(tx.hash())
                         .expect("verified tx should exists")
-                        .map(|cycles| (cycles, tx.to_owned()))
+                        .map(|cycles| (tx.to_owned(), cycles))
                 })
-                .collect::<Vec<Result<(Cycle, Transaction), PoolError>>>()
+                .collect::<Vec<Result<(Transaction, Cycle), PoolError>>>()
         };
-        cycles_vec
-            .into_iter()
-            .map(|result| match result {
-                Ok((cycles, tx)) => chain_state.add_tx_to_pool(tx, cycles),
-                Err(err) => Err(err),
-            })
-            .collect()
+
+        if package {
+            // the txs of the package are rejected together
+            let result = verified
+                .into_iter()
+                .collect::<Result<Vec<(Transaction, Cycle)>, PoolError>>()
+                .and_then(|package| chain_state.add_package_to_pool(package));
+            if let Err(err) = &result {
+                for tx in &txs {
+                    chain_state.add_rejected_tx(tx.hash().to_owned(), err.to_owned());
+                }
+            }
+            drop(chain_state);
+            self.notify_txs(&txs, result.as_ref().err());
+            return result;
+        }
+
+        let mut cycles_vec = Vec::with_capacity(txs.len());
+        let mut rejected = None;
+        for (tx, result) in txs.iter().zip(verified) {
+            match result.and_then(|(tx, cycles)| chain_state.add_tx_to_pool(tx, cycles)) {
+                Ok(cycles) => cycles_vec.push(cycles),
+                Err(err) => {
+                    chain_state.add_rejected_tx(tx.hash().to_owned(), err.to_owned());
+                    rejected = Some((tx, err));
+                    break;
+                }
+            }
+        }
+        drop(chain_state);
+        self.notify_txs(&txs[..cycles_vec.len()], None);
+        match rejected {
+            Some((tx, err)) => {
+                self.notify_txs(slice::from_ref(tx), Some(&err));
+                Err(err)
+            }
+            None => Ok(cycles_vec),
+        }
     }
+
+    // Publishes the txs added to the pool, or the rejected ones with the reason
+    fn notify_txs(&self, txs: &[Transaction], rejected: Option<&PoolError>) {
+        let notify = self.shared.notify();
+        for tx in txs {
+            match rejected {
+                None => notify.notify_new_transaction(Arc::new(tx.to_owned())),
+                Some(err) => {
+                    notify.notify_rejected_transaction(Arc::new((tx.to_owned(), err.to_string())))
+                }
+            }
+        }
+    }
+}
+
+// Rejects the duplicate txs, and the package in which a tx other than the first does not spend
+// an output of a previous one
+fn check_txs(txs: &[Transaction], package: bool) -> Result<(), PoolError> {
+    let mut hashes = FnvHashSet::default();
+    if txs.iter().any(|tx| !hashes.insert(tx.hash())) {
+        return Err(PoolError::Duplicate);
+    }
+    if package {
+        let mut previous = FnvHashSet::default();
+        for tx in txs {
+            let spends_previous = tx
+                .input_pts_iter()
+                .filter_map(|out_point| out_point.cell.as_ref())
+                .any(|cell| previous.contains(&cell.tx_hash));
+            if !previous.is_empty() && !spends_previous {
+                return Err(PoolError::PackageRejected(format!(
+                    "tx {:#x} does not spend the previous txs of the package",
+                    tx.hash()
+                )));
+            }
+            previous.insert(tx.hash().to_owned());
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use ckb_chain::chain::ChainService;
     use ckb_chain_spec::consensus::Consensus;
@@ -191,6 +303,7 @@ mod tests {
     use ckb_core::{capacity_bytes, Bytes, Capacity};
     use ckb_notify::NotifyService;
     use ckb_shared::shared::{Shared, SharedBuilder};
+    use ckb_shared::tx_pool::{FeeRate, TxPoolConfig};
     use ckb_test_chain_utils::always_success_cell;
     use ckb_traits::ChainProvider;
     use ckb_verification::TransactionError;
@@ -198,7 +311,14 @@ mod tests {
     use numext_fixed_uint::U256;
     use std::sync::Arc;
 
-    fn setup(height: u64) -> (Shared, OutPoint) {
+    pub(crate) fn setup(height: u64) -> (Shared, OutPoint) {
+        setup_with_tx_pool_config(height, TxPoolConfig::default())
+    }
+
+    pub(crate) fn setup_with_tx_pool_config(
+        height: u64,
+        tx_pool_config: TxPoolConfig,
+    ) -> (Shared, OutPoint) {
         let (always_success_cell, always_success_cell_data, always_success_script) =
             always_success_cell();
         let always_success_tx = TransactionBuilder::default()
@@ -223,6 +343,7 @@ mod tests {
 
         let shared = SharedBuilder::default()
             .consensus(consensus)
+            .tx_pool_config(tx_pool_config)
             .build()
             .unwrap();
 
@@ -408,4 +529,112 @@ mod tests {
             Err(PoolError::InvalidTx(TransactionError::Immature)),
         );
     }
+
+    #[test]
+    fn test_verify_and_add_package_to_pool() {
+        let (shared, always_success_out_point) = setup_with_tx_pool_config(
+            10,
+            TxPoolConfig {
+                min_fee_rate: FeeRate(1_000),
+                ..Default::default()
+            },
+        );
+        let last_block = shared
+            .store()
+            .get_block(&shared.lock_chain_state().tip_hash())
+            .unwrap();
+        let last_cellbase = last_block.transactions().first().unwrap();
+        let (_, _, always_success_script) = always_success_cell();
+        let build_tx = |previous_output: OutPoint, capacity: Capacity| {
+            TransactionBuilder::default()
+                .input(CellInput::new(previous_output, 0))
+                .output(
+                    CellOutputBuilder::default()
+                        .capacity(capacity)
+                        .lock(always_success_script.to_owned())
+                        .build(),
+                )
+                .output_data(Bytes::new())
+                .dep(always_success_out_point.to_owned())
+                .build()
+        };
+        // the parent pays no fee, the child pays 1 CKB
+        let parent = build_tx(
+            OutPoint::new_cell(last_cellbase.hash().to_owned(), 0),
+            capacity_bytes!(50),
+        );
+        let child = build_tx(
+            OutPoint::new_cell(parent.hash().to_owned(), 0),
+            capacity_bytes!(49),
+        );
+        let invalid_child = build_tx(
+            OutPoint::new_cell(parent.hash().to_owned(), 0),
+            capacity_bytes!(51),
+        );
+
+        let tx_pool_executor = TxPoolExecutor::new(shared.clone());
+        match tx_pool_executor.verify_and_add_tx_to_pool(parent.clone()) {
+            Err(PoolError::LowFeeRate(_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        // none of the package is added if any tx fails
+        assert!(tx_pool_executor
+            .verify_and_add_package_to_pool(vec![parent.clone(), invalid_child])
+            .is_err());
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 0);
+        // the child does not bring in an unrelated tx
+        let unrelated = build_tx(
+            OutPoint::new_cell(last_cellbase.hash().to_owned(), 1),
+            capacity_bytes!(50),
+        );
+        match tx_pool_executor.verify_and_add_package_to_pool(vec![
+            unrelated,
+            parent.clone(),
+            child.clone(),
+        ]) {
+            Err(PoolError::PackageRejected(_)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 0);
+
+        let result = tx_pool_executor
+            .verify_and_add_package_to_pool(vec![parent, child])
+            .expect("verify package");
+        assert_eq!(result.len(), 2);
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 2);
+    }
+
+    #[test]
+    fn test_verify_and_add_duplicate_txs_to_pool() {
+        let (shared, always_success_out_point) = setup(10);
+        let last_block = shared
+            .store()
+            .get_block(&shared.lock_chain_state().tip_hash())
+            .unwrap();
+        let last_cellbase = last_block.transactions().first().unwrap();
+        let tx = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new_cell(last_cellbase.hash().to_owned(), 0),
+                0,
+            ))
+            .output(
+                CellOutputBuilder::default()
+                    .capacity(capacity_bytes!(50))
+                    .build(),
+            )
+            .output_data(Bytes::new())
+            .dep(always_success_out_point.to_owned())
+            .build();
+
+        let tx_pool_executor = TxPoolExecutor::new(shared.clone());
+        assert_eq!(
+            tx_pool_executor.verify_and_add_txs_to_pool(vec![tx.clone(), tx.clone()]),
+            Err(PoolError::Duplicate)
+        );
+        assert_eq!(
+            tx_pool_executor.verify_and_add_package_to_pool(vec![tx.clone(), tx]),
+            Err(PoolError::Duplicate)
+        );
+        assert_eq!(shared.lock_chain_state().tx_pool().pending_size(), 0);
+    }
 }
@@ -4,9 +4,10 @@ use ckb_core::{
 };
 use ckb_jsonrpc_types::{
     Alert, BannedAddress, Block, BlockNumber, BlockTemplate, BlockView, Capacity,
-    CellOutputWithOutPoint, CellTransaction, CellWithStatus, ChainInfo, DryRunResult, EpochNumber,
-    EpochView, HeaderView, LiveCell, LockHashIndexState, Node, OutPoint, PeerState, Timestamp,
-    Transaction, TransactionWithStatus, TxPoolInfo, Unsigned, Version,
+    CellOutputWithOutPoint, CellTransactionsPage, CellWithStatus, ChainInfo, DryRunResult,
+    EpochNumber, EpochView, HeaderView, JsonBytes, LiveCellsPage, LockHashCapacity,
+    LockHashIndexState, Node, OutPoint, PeerState, Timestamp, Transaction, TransactionWithStatus,
+    TxPoolInfo, Unsigned, Version,
 };
 use ckb_util::Mutex;
 use jsonrpc_client_core::{expand_params, jsonrpc_client, Result as JsonRpcResult};
@@ -260,18 +261,13 @@ impl RpcClient {
     pub fn get_live_cells_by_lock_hash(
         &self,
         lock_hash: H256,
-        page: u64,
         per_page: u64,
         reverse_order: Option<bool>,
-    ) -> Vec<LiveCell> {
+        cursor: Option<JsonBytes>,
+    ) -> LiveCellsPage {
         self.inner()
             .lock()
-            .get_live_cells_by_lock_hash(
-                lock_hash,
-                Unsigned(page),
-                Unsigned(per_page),
-                reverse_order,
-            )
+            .get_live_cells_by_lock_hash(lock_hash, Unsigned(per_page), reverse_order, cursor)
             .call()
             .expect("rpc call get_live_cells_by_lock_hash")
     }
@@ -279,18 +275,13 @@ impl RpcClient {
     pub fn get_transactions_by_lock_hash(
         &self,
         lock_hash: H256,
-        page: u64,
         per_page: u64,
         reverse_order: Option<bool>,
-    ) -> Vec<CellTransaction> {
+        cursor: Option<JsonBytes>,
+    ) -> CellTransactionsPage {
         self.inner()
             .lock()
-            .get_transactions_by_lock_hash(
-                lock_hash,
-                Unsigned(page),
-                Unsigned(per_page),
-                reverse_order,
-            )
+            .get_transactions_by_lock_hash(lock_hash, Unsigned(per_page), reverse_order, cursor)
             .call()
             .expect("rpc call get_transactions_by_lock_hash")
     }
@@ -323,6 +314,14 @@ impl RpcClient {
             .expect("rpc call get_lock_hash_index_states")
     }
 
+    pub fn get_capacity_by_lock_hash(&self, lock_hash: H256) -> Option<LockHashCapacity> {
+        self.inner()
+            .lock()
+            .get_capacity_by_lock_hash(lock_hash)
+            .call()
+            .expect("rpc call get_capacity_by_lock_hash")
+    }
+
     pub fn calculate_dao_maximum_withdraw(&self, out_point: OutPoint, hash: H256) -> CoreCapacity {
         self.inner()
             .lock()
@@ -384,10 +383,11 @@ jsonrpc_client!(pub struct Inner {
     pub fn remove_node(&mut self, peer_id: String) -> RpcRequest<()>;
     pub fn process_block_without_verify(&mut self, _data: Block) -> RpcRequest<Option<H256>>;
 
-    pub fn get_live_cells_by_lock_hash(&mut self, lock_hash: H256, page: Unsigned, per_page: Unsigned, reverse_order: Option<bool>) -> RpcRequest<Vec<LiveCell>>;
-    pub fn get_transactions_by_lock_hash(&mut self, lock_hash: H256, page: Unsigned, per_page: Unsigned, reverse_order: Option<bool>) -> RpcRequest<Vec<CellTransaction>>;
+    pub fn get_live_cells_by_lock_hash(&mut self, lock_hash: H256, per_page: Unsigned, reverse_order: Option<bool>, cursor: Option<JsonBytes>) -> RpcRequest<LiveCellsPage>;
+    pub fn get_transactions_by_lock_hash(&mut self, lock_hash: H256, per_page: Unsigned, reverse_order: Option<bool>, cursor: Option<JsonBytes>) -> RpcRequest<CellTransactionsPage>;
     pub fn index_lock_hash(&mut self, lock_hash: H256, index_from: Option<BlockNumber>) -> RpcRequest<LockHashIndexState>;
     pub fn deindex_lock_hash(&mut self, lock_hash: H256) -> RpcRequest<()>;
     pub fn get_lock_hash_index_states(&mut self) -> RpcRequest<Vec<LockHashIndexState>>;
+    pub fn get_capacity_by_lock_hash(&mut self, lock_hash: H256) -> RpcRequest<Option<LockHashCapacity>>;
     pub fn calculate_dao_maximum_withdraw(&mut self, _out_point: OutPoint, _hash: H256) -> RpcRequest<Capacity>;
 });
@@ -21,18 +21,24 @@ impl Spec for IndexerBasic {
         let rpc_client = node0.rpc_client();
 
         info!("Should return empty result before index the lock hash");
-        let live_cells = rpc_client.get_live_cells_by_lock_hash(lock_hash.clone(), 0, 10, None);
-        let cell_transactions =
-            rpc_client.get_transactions_by_lock_hash(lock_hash.clone(), 0, 10, None);
+        let live_cells = rpc_client
+            .get_live_cells_by_lock_hash(lock_hash.clone(), 10, None, None)
+            .live_cells;
+        let cell_transactions = rpc_client
+            .get_transactions_by_lock_hash(lock_hash.clone(), 10, None, None)
+            .transactions;
         assert_eq!(0, live_cells.len());
         assert_eq!(0, cell_transactions.len());
 
         info!("Live cells size should be 1, cell transactions size should be 1");
         rpc_client.index_lock_hash(lock_hash.clone(), Some(0));
         let result = wait_until(5, || {
-            let live_cells = rpc_client.get_live_cells_by_lock_hash(lock_hash.clone(), 0, 20, None);
-            let cell_transactions =
-                rpc_client.get_transactions_by_lock_hash(lock_hash.clone(), 0, 20, None);
+            let live_cells = rpc_client
+                .get_live_cells_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .live_cells;
+            let cell_transactions = rpc_client
+                .get_transactions_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .transactions;
             live_cells.len() == 1 && cell_transactions.len() == 1
         });
         if !result {
@@ -56,9 +62,12 @@ impl Spec for IndexerBasic {
             "Live cells size should be 4 (1 + 3), cell transactions size should be 10 (1 + 6 + 3)"
         );
         let result = wait_until(5, || {
-            let live_cells = rpc_client.get_live_cells_by_lock_hash(lock_hash.clone(), 0, 20, None);
-            let cell_transactions =
-                rpc_client.get_transactions_by_lock_hash(lock_hash.clone(), 0, 20, None);
+            let live_cells = rpc_client
+                .get_live_cells_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .live_cells;
+            let cell_transactions = rpc_client
+                .get_transactions_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .transactions;
             live_cells.len() == 4 && cell_transactions.len() == 10
         });
         if !result {
@@ -66,10 +75,12 @@ impl Spec for IndexerBasic {
         }
 
         info!("Get live cells and transactions in reverse order");
-        let live_cells =
-            rpc_client.get_live_cells_by_lock_hash(lock_hash.clone(), 0, 20, Some(true));
-        let cell_transactions =
-            rpc_client.get_transactions_by_lock_hash(lock_hash.clone(), 0, 20, Some(true));
+        let live_cells = rpc_client
+            .get_live_cells_by_lock_hash(lock_hash.clone(), 20, Some(true), None)
+            .live_cells;
+        let cell_transactions = rpc_client
+            .get_transactions_by_lock_hash(lock_hash.clone(), 20, Some(true), None)
+            .transactions;
         let tip_number = rpc_client.get_tip_header().inner.number;
         assert_eq!(tip_number, live_cells[0].created_by.block_number);
         assert_eq!(tip_number, cell_transactions[0].created_by.block_number);
@@ -80,9 +91,12 @@ impl Spec for IndexerBasic {
         node0.waiting_for_sync(node1, 5);
         info!("Live cells size should be 5, cell transactions size should be 5");
         let result = wait_until(5, || {
-            let live_cells = rpc_client.get_live_cells_by_lock_hash(lock_hash.clone(), 0, 20, None);
-            let cell_transactions =
-                rpc_client.get_transactions_by_lock_hash(lock_hash.clone(), 0, 20, None);
+            let live_cells = rpc_client
+                .get_live_cells_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .live_cells;
+            let cell_transactions = rpc_client
+                .get_transactions_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .transactions;
             live_cells.len() == 5 && cell_transactions.len() == 5
         });
         if !result {
@@ -91,9 +105,12 @@ impl Spec for IndexerBasic {
 
         info!("Should remove data after deindex");
         rpc_client.deindex_lock_hash(lock_hash.clone());
-        let live_cells = rpc_client.get_live_cells_by_lock_hash(lock_hash.clone(), 0, 10, None);
-        let cell_transactions =
-            rpc_client.get_transactions_by_lock_hash(lock_hash.clone(), 0, 10, None);
+        let live_cells = rpc_client
+            .get_live_cells_by_lock_hash(lock_hash.clone(), 10, None, None)
+            .live_cells;
+        let cell_transactions = rpc_client
+            .get_transactions_by_lock_hash(lock_hash.clone(), 10, None, None)
+            .transactions;
         assert_eq!(0, live_cells.len());
         assert_eq!(0, cell_transactions.len());
 
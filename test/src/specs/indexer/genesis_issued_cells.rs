@@ -30,9 +30,12 @@ impl Spec for GenesisIssuedCells {
         info!("Should return live cells and cell transactions of genesis issued cells");
         rpc_client.index_lock_hash(lock_hash.clone(), Some(0));
         let result = wait_until(5, || {
-            let live_cells = rpc_client.get_live_cells_by_lock_hash(lock_hash.clone(), 0, 20, None);
-            let cell_transactions =
-                rpc_client.get_transactions_by_lock_hash(lock_hash.clone(), 0, 20, None);
+            let live_cells = rpc_client
+                .get_live_cells_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .live_cells;
+            let cell_transactions = rpc_client
+                .get_transactions_by_lock_hash(lock_hash.clone(), 20, None, None)
+                .transactions;
             live_cells.len() == 1 && cell_transactions.len() == 1
         });
         if !result {
@@ -46,6 +46,29 @@ pub struct StatsArgs {
     pub to: Option<u64>,
 }
 
+pub struct IndexerArgs {
+    pub config: Box<CKBAppConfig>,
+    pub consensus: Consensus,
+    pub command: IndexerCommand,
+}
+
+pub enum IndexerCommand {
+    Verify,
+    Rebuild {
+        lock_hash: Option<String>,
+        from: u64,
+    },
+    Compact,
+    Stats,
+}
+
+pub struct VerifyTxArgs {
+    pub config: Box<CKBAppConfig>,
+    pub consensus: Consensus,
+    pub tx_file: PathBuf,
+    pub profile: Option<PathBuf>,
+}
+
 pub struct InitArgs {
     pub root_dir: PathBuf,
     pub chain: String,
@@ -14,6 +14,12 @@ pub const CMD_HASHES: &str = "hashes";
 pub const CMD_BLAKE256: &str = "blake256";
 pub const CMD_BLAKE160: &str = "blake160";
 pub const CMD_SECP256K1_LOCK: &str = "secp256k1-lock";
+pub const CMD_VERIFY_TX: &str = "verify-tx";
+pub const CMD_RPC_DISCOVER: &str = "rpc-discover";
+pub const CMD_INDEXER: &str = "indexer";
+pub const CMD_VERIFY: &str = "verify";
+pub const CMD_REBUILD: &str = "rebuild";
+pub const CMD_COMPACT: &str = "compact";
 
 pub const ARG_CONFIG_DIR: &str = "config-dir";
 pub const ARG_FORMAT: &str = "format";
@@ -27,6 +33,7 @@ pub const ARG_RPC_PORT: &str = "rpc-port";
 pub const ARG_FORCE: &str = "force";
 pub const ARG_LOG_TO: &str = "log-to";
 pub const ARG_BUNDLED: &str = "bundled";
+pub const ARG_ALL: &str = "all";
 pub const ARG_BA_CODE_HASH: &str = "ba-code-hash";
 pub const ARG_BA_ARG: &str = "ba-arg";
 pub const ARG_BA_HASH_TYPE: &str = "ba-hash-type";
@@ -34,6 +41,9 @@ pub const ARG_BA_DATA: &str = "ba-data";
 pub const ARG_BA_ADVANCED: &str = "ba-advanced";
 pub const ARG_FROM: &str = "from";
 pub const ARG_TO: &str = "to";
+pub const ARG_LOCK_HASH: &str = "lock-hash";
+pub const ARG_TX_FILE: &str = "tx-file";
+pub const ARG_PROFILE: &str = "profile";
 
 const GROUP_BA: &str = "ba";
 
@@ -60,6 +70,7 @@ fn basic_app<'b>() -> App<'static, 'b> {
         .subcommand(init())
         .subcommand(prof())
         .subcommand(stats())
+        .subcommand(indexer())
 }
 
 pub fn get_matches(version: &Version) -> ArgMatches<'static> {
@@ -102,6 +113,47 @@ pub(crate) fn stats() -> App<'static, 'static> {
         )
 }
 
+pub(crate) fn indexer() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_INDEXER)
+        .about("Maintains the indexer database, the ckb node must be stopped first")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name(CMD_VERIFY)
+                .about("Verifies the index states against the chain database"),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_REBUILD)
+                .about(
+                    "Rebuilds the index of one or all indexed lock hashes\n\
+                     Example:\n\
+                     ckb -C <dir> indexer rebuild --lock-hash 0x... --from 1000",
+                )
+                .arg(
+                    Arg::with_name(ARG_LOCK_HASH)
+                        .long(ARG_LOCK_HASH)
+                        .value_name("hash")
+                        .validator(is_hex)
+                        .takes_value(true)
+                        .help("Specifies the lock hash to rebuild [default: all indexed hashes]"),
+                )
+                .arg(
+                    Arg::with_name(ARG_FROM)
+                        .long(ARG_FROM)
+                        .takes_value(true)
+                        .default_value("0")
+                        .help("Specifies from block number, exclusively."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_COMPACT)
+                .about("Compacts the indexer database to reclaim the space of the deleted entries"),
+        )
+        .subcommand(
+            SubCommand::with_name(CMD_STATS)
+                .about("Prints the index states, capacity and live cells count of lock hashes"),
+        )
+}
+
 fn prof() -> App<'static, 'static> {
     SubCommand::with_name(CMD_PROF)
         .about(
@@ -171,6 +223,8 @@ fn cli() -> App<'static, 'static> {
         .subcommand(cli_blake256())
         .subcommand(cli_blake160())
         .subcommand(cli_secp256k1_lock())
+        .subcommand(cli_verify_tx())
+        .subcommand(cli_rpc_discover())
 }
 
 fn cli_hashes() -> App<'static, 'static> {
@@ -186,6 +240,17 @@ fn cli_hashes() -> App<'static, 'static> {
         )
 }
 
+fn cli_rpc_discover() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_RPC_DISCOVER)
+        .about("Prints the OpenRPC document of the rpc methods enabled in the current config")
+        .arg(
+            Arg::with_name(ARG_ALL)
+                .short("a")
+                .long(ARG_ALL)
+                .help("Prints all the rpc methods instead of the enabled ones."),
+        )
+}
+
 fn arg_hex_data() -> Arg<'static, 'static> {
     Arg::with_name(ARG_DATA)
         .short("d")
@@ -231,6 +296,33 @@ fn cli_secp256k1_lock() -> App<'static, 'static> {
         )
 }
 
+pub(crate) fn cli_verify_tx() -> App<'static, 'static> {
+    SubCommand::with_name(CMD_VERIFY_TX)
+        .about(
+            "Verifies the scripts of a transaction against the local chain database\n\
+             Example: Verify the transaction then output flame graph\n\
+             ckb -C <dir> cli verify-tx tx.json --profile tx.folded\n\
+             flamegraph.pl tx.folded > tx.svg",
+        )
+        .arg(
+            Arg::with_name(ARG_TX_FILE)
+                .value_name("path")
+                .required(true)
+                .index(1)
+                .help("The transaction file in the JSON format of the RPC."),
+        )
+        .arg(
+            Arg::with_name(ARG_PROFILE)
+                .long(ARG_PROFILE)
+                .value_name("path")
+                .takes_value(true)
+                .help(
+                    "Traces the script execution and writes the cycles by call stacks to <path>, \
+                     in the folded stacks format of flame graph.",
+                ),
+        )
+}
+
 fn init() -> App<'static, 'static> {
     SubCommand::with_name(CMD_INIT)
         .about("Creates a CKB direcotry or reinitializes an existing one")
@@ -5,7 +5,10 @@ mod exit_code;
 mod sentry_config;
 
 pub use app_config::{AppConfig, CKBAppConfig, MinerAppConfig};
-pub use args::{ExportArgs, ImportArgs, InitArgs, MinerArgs, ProfArgs, RunArgs, StatsArgs};
+pub use args::{
+    ExportArgs, ImportArgs, IndexerArgs, IndexerCommand, InitArgs, MinerArgs, ProfArgs, RunArgs,
+    StatsArgs, VerifyTxArgs,
+};
 pub use ckb_miner::BlockAssemblerConfig;
 pub use exit_code::ExitCode;
 
@@ -153,6 +156,27 @@ impl Setup {
         })
     }
 
+    pub fn indexer<'m>(self, matches: &ArgMatches<'m>) -> Result<IndexerArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let config = self.config.into_ckb()?;
+        let command = match matches.subcommand() {
+            (cli::CMD_VERIFY, _) => IndexerCommand::Verify,
+            (cli::CMD_REBUILD, Some(sub_matches)) => IndexerCommand::Rebuild {
+                lock_hash: sub_matches.value_of(cli::ARG_LOCK_HASH).map(str::to_string),
+                from: value_t!(sub_matches, cli::ARG_FROM, u64)?,
+            },
+            (cli::CMD_COMPACT, _) => IndexerCommand::Compact,
+            (cli::CMD_STATS, _) => IndexerCommand::Stats,
+            _ => unreachable!(),
+        };
+
+        Ok(IndexerArgs {
+            config,
+            consensus,
+            command,
+        })
+    }
+
     pub fn import<'m>(self, matches: &ArgMatches<'m>) -> Result<ImportArgs, ExitCode> {
         let consensus = self.consensus()?;
         let config = self.config.into_ckb()?;
@@ -181,6 +205,20 @@ impl Setup {
         })
     }
 
+    pub fn verify_tx<'m>(self, matches: &ArgMatches<'m>) -> Result<VerifyTxArgs, ExitCode> {
+        let consensus = self.consensus()?;
+        let config = self.config.into_ckb()?;
+        let tx_file = value_t!(matches.value_of(cli::ARG_TX_FILE), PathBuf)?;
+        let profile = matches.value_of(cli::ARG_PROFILE).map(PathBuf::from);
+
+        Ok(VerifyTxArgs {
+            config,
+            consensus,
+            tx_file,
+            profile,
+        })
+    }
+
     pub fn init<'m>(matches: &ArgMatches<'m>) -> Result<InitArgs, ExitCode> {
         if matches.is_present("list-specs") {
             eprintln!(
@@ -296,7 +334,7 @@ fn consensus_from_spec(spec: &ChainSpec) -> Result<Consensus, ExitCode> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::cli::CMD_STATS;
+    use crate::cli::{CMD_INDEXER, CMD_REBUILD, CMD_STATS, CMD_VERIFY, CMD_VERIFY_TX};
     use clap::{App, AppSettings};
 
     #[test]
@@ -323,4 +361,76 @@ mod tests {
             .get_matches_from_safe(vec!["", CMD_STATS, "--from", "10", "--to", "100"]);
         assert!(stats.is_ok());
     }
+
+    #[test]
+    fn indexer_args() {
+        let app = App::new("indexer_args_test")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(cli::indexer());
+
+        let indexer = app.clone().get_matches_from_safe(vec!["", CMD_INDEXER]);
+        assert!(indexer.is_err());
+
+        let indexer = app
+            .clone()
+            .get_matches_from_safe(vec!["", CMD_INDEXER, CMD_VERIFY]);
+        assert!(indexer.is_ok());
+
+        let indexer = app
+            .clone()
+            .get_matches_from_safe(vec!["", CMD_INDEXER, CMD_STATS]);
+        assert!(indexer.is_ok());
+
+        let indexer =
+            app.clone()
+                .get_matches_from_safe(vec!["", CMD_INDEXER, CMD_REBUILD, "--from", "10"]);
+        assert!(indexer.is_ok());
+
+        let indexer = app.clone().get_matches_from_safe(vec![
+            "",
+            CMD_INDEXER,
+            CMD_REBUILD,
+            "--lock-hash",
+            "0xa6ed2e",
+        ]);
+        assert!(indexer.is_ok());
+
+        let indexer = app.clone().get_matches_from_safe(vec![
+            "",
+            CMD_INDEXER,
+            CMD_REBUILD,
+            "--lock-hash",
+            "a6ed2e",
+        ]);
+        assert!(indexer.is_err());
+    }
+
+    #[test]
+    fn verify_tx_args() {
+        let app = App::new("verify_tx_args_test")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(cli::cli_verify_tx());
+
+        let verify_tx = app.clone().get_matches_from_safe(vec!["", CMD_VERIFY_TX]);
+        assert!(verify_tx.is_err());
+
+        let verify_tx = app
+            .clone()
+            .get_matches_from_safe(vec!["", CMD_VERIFY_TX, "tx.json"]);
+        assert!(verify_tx.is_ok());
+        let matches = verify_tx.unwrap();
+        let sub_matches = matches.subcommand_matches(CMD_VERIFY_TX).unwrap();
+        assert_eq!(sub_matches.value_of(cli::ARG_PROFILE), None);
+
+        let verify_tx = app.clone().get_matches_from_safe(vec![
+            "",
+            CMD_VERIFY_TX,
+            "tx.json",
+            "--profile",
+            "tx.folded",
+        ]);
+        let matches = verify_tx.unwrap();
+        let sub_matches = matches.subcommand_matches(CMD_VERIFY_TX).unwrap();
+        assert_eq!(sub_matches.value_of(cli::ARG_PROFILE), Some("tx.folded"));
+    }
 }
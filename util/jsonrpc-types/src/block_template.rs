@@ -27,6 +27,9 @@ pub struct BlockTemplate {
     pub proposals: Vec<ProposalShortId>,
     pub cellbase: CellbaseTemplate,
     pub work_id: Unsigned,
+    // Identifies the tip and the pool state the template is built on, pass it back to
+    // `get_block_template` to wait until the template is changed
+    pub long_poll_id: String,
     pub dao: JsonBytes,
 }
 
@@ -1,7 +1,31 @@
-use crate::Cycle;
+use crate::{Cycle, Unsigned};
+use numext_fixed_hash::H256;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
 pub struct DryRunResult {
     pub cycles: Cycle,
+    pub script_groups: Vec<ScriptGroupResult>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptGroupType {
+    Lock,
+    Type,
+}
+
+// The verification result of a script group, `exit_code` is null if the script fails before
+// exiting, and `error` is the reason of the failure. `debug_messages` are the messages printed
+// by the script via the debug syscall.
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct ScriptGroupResult {
+    pub script_hash: H256,
+    pub group_type: ScriptGroupType,
+    pub input_indices: Vec<Unsigned>,
+    pub output_indices: Vec<Unsigned>,
+    pub cycles: Cycle,
+    pub exit_code: Option<i8>,
+    pub error: Option<String>,
+    pub debug_messages: Vec<String>,
 }
@@ -1,4 +1,4 @@
-use crate::{BlockNumber, CellOutput, Unsigned};
+use crate::{BlockNumber, Capacity, CellOutput, JsonBytes, ScriptHashType, Unsigned};
 use numext_fixed_hash::H256;
 use serde_derive::{Deserialize, Serialize};
 
@@ -16,6 +16,55 @@ pub struct CellTransaction {
     pub consumed_by: Option<TransactionPoint>,
 }
 
+// This is used as return value of get_live_cells_by_lock_hash RPC,
+// `cursor` points to the last returned cell, it's used to fetch the next page
+#[derive(Serialize, Deserialize)]
+pub struct LiveCellsPage {
+    pub live_cells: Vec<LiveCell>,
+    pub cursor: Option<JsonBytes>,
+}
+
+// This is used as return value of get_transactions_by_lock_hash RPC,
+// `cursor` points to the last returned transaction, it's used to fetch the next page
+#[derive(Serialize, Deserialize)]
+pub struct CellTransactionsPage {
+    pub transactions: Vec<CellTransaction>,
+    pub cursor: Option<JsonBytes>,
+}
+
+// This is used as parameter of get_live_cells_by_lock_hash RPC, all the conditions are optional
+// and the block number range is inclusive
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct LiveCellFilter {
+    pub from_block_number: Option<BlockNumber>,
+    pub to_block_number: Option<BlockNumber>,
+    pub has_type_script: Option<bool>,
+    pub empty_data_only: Option<bool>,
+    pub min_capacity: Option<Capacity>,
+    pub max_capacity: Option<Capacity>,
+}
+
+// This is used as notification of subscribe_lock_hash RPC, `rollback` means the index of the
+// lock hash is rolled back to the block due to fork, and the events after it are reverted
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IndexerEvent {
+    CellCreated {
+        lock_hash: H256,
+        live_cell: LiveCell,
+    },
+    CellConsumed {
+        lock_hash: H256,
+        created_by: TransactionPoint,
+        consumed_by: TransactionPoint,
+    },
+    Rollback {
+        lock_hash: H256,
+        block_number: BlockNumber,
+        block_hash: H256,
+    },
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct TransactionPoint {
     pub block_number: BlockNumber,
@@ -29,3 +78,34 @@ pub struct LockHashIndexState {
     pub block_number: BlockNumber,
     pub block_hash: H256,
 }
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TypeHashIndexState {
+    pub type_hash: H256,
+    pub block_number: BlockNumber,
+    pub block_hash: H256,
+}
+
+// Matches the lock scripts which have the same `code_hash` and `hash_type`, and whose args
+// (concatenated in order) start with `args_prefix`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LockPattern {
+    pub code_hash: H256,
+    pub hash_type: ScriptHashType,
+    pub args_prefix: JsonBytes,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LockPatternIndexState {
+    pub pattern_hash: H256,
+    pub lock_pattern: LockPattern,
+    pub block_number: BlockNumber,
+    pub block_hash: H256,
+}
+
+// This is used as return value of get_capacity_by_lock_hash RPC
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LockHashCapacity {
+    pub capacity: Capacity,
+    pub cells_count: Unsigned,
+}
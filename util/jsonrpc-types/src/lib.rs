@@ -9,6 +9,7 @@ mod indexer;
 mod net;
 mod pool;
 mod proposal_short_id;
+mod rpc_stats;
 mod string;
 mod sync;
 
@@ -45,11 +46,19 @@ pub use self::blockchain::{
 pub use self::bytes::JsonBytes;
 pub use self::cell::{CellOutputWithOutPoint, CellWithStatus};
 pub use self::chain_info::ChainInfo;
-pub use self::experiment::DryRunResult;
-pub use self::indexer::{CellTransaction, LiveCell, LockHashIndexState, TransactionPoint};
+pub use self::experiment::{DryRunResult, ScriptGroupResult, ScriptGroupType};
+pub use self::indexer::{
+    CellTransaction, CellTransactionsPage, IndexerEvent, LiveCell, LiveCellFilter, LiveCellsPage,
+    LockHashCapacity, LockHashIndexState, LockPattern, LockPatternIndexState, TransactionPoint,
+    TypeHashIndexState,
+};
 pub use self::net::{BannedAddress, Node, NodeAddress};
-pub use self::pool::TxPoolInfo;
+pub use self::pool::{
+    EstimateResult, PoolTransaction, PoolTransactionStatus, RawTxPool, RejectedTransaction,
+    TxPoolEntry, TxPoolInfo,
+};
 pub use self::proposal_short_id::ProposalShortId;
+pub use self::rpc_stats::{RpcMethodStats, RpcStats};
 pub use self::sync::PeerState;
 pub use jsonrpc_core::types::{error, id, params, request, response, version};
 pub use serde_derive::{Deserialize, Serialize};
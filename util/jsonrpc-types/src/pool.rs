@@ -1,4 +1,5 @@
-use crate::{Timestamp, Unsigned};
+use crate::{Capacity, Timestamp, TransactionView, Unsigned};
+use numext_fixed_hash::H256;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
@@ -9,4 +10,55 @@ pub struct TxPoolInfo {
     pub total_tx_size: Unsigned,
     pub total_tx_cycles: Unsigned,
     pub last_txs_updated_at: Timestamp,
+    pub evicted: Unsigned,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct EstimateResult {
+    pub fee_rate: Unsigned,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct TxPoolEntry {
+    pub hash: H256,
+    pub size: Unsigned,
+    pub cycles: Option<Unsigned>,
+    pub fee: Option<Capacity>,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct RawTxPool {
+    pub pending: Vec<TxPoolEntry>,
+    pub gap: Vec<TxPoolEntry>,
+    pub proposed: Vec<TxPoolEntry>,
+    pub orphan: Vec<TxPoolEntry>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolTransactionStatus {
+    Pending,
+    Gap,
+    Proposed,
+    Orphan,
+    Conflict,
+    Rejected,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct PoolTransaction {
+    pub status: PoolTransactionStatus,
+    // absent for the rejected tx
+    pub transaction: Option<TransactionView>,
+    pub entry: Option<TxPoolEntry>,
+    // why the tx is conflict or rejected
+    pub reason: Option<String>,
+}
+
+// Pushed to the subscribers of the rejected txs
+#[derive(Clone, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct RejectedTransaction {
+    pub transaction: TransactionView,
+    pub reason: String,
 }
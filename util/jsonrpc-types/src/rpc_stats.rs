@@ -0,0 +1,25 @@
+use crate::Unsigned;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RpcMethodStats {
+    pub method: String,
+    // the calls executed, not including the ones rejected by the rate limit
+    pub calls: Unsigned,
+    // the calls returning errors
+    pub errors: Unsigned,
+    // the total latency of the calls in microseconds
+    pub total_latency: Unsigned,
+    // the max latency of the calls in microseconds
+    pub max_latency: Unsigned,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct RpcStats {
+    // the methods called since the node started, sorted by name
+    pub methods: Vec<RpcMethodStats>,
+    // the calls rejected by the rate limit
+    pub rate_limited_calls: Unsigned,
+    // the batch requests rejected by the max batch size
+    pub oversized_batches: Unsigned,
+}
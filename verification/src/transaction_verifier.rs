@@ -6,7 +6,7 @@ use ckb_core::{
     BlockNumber, Capacity, Cycle, EpochNumber,
 };
 use ckb_resource::CODE_HASH_DAO;
-use ckb_script::{ScriptConfig, TransactionScriptsVerifier};
+use ckb_script::{ScriptConfig, ScriptGroupReport, TransactionScriptsVerifier};
 use ckb_store::{data_loader_wrapper::DataLoaderWrapper, ChainStore};
 use ckb_traits::BlockMedianTimeContext;
 use lru_cache::LruCache;
@@ -180,6 +180,20 @@ impl<'a, CS: ChainStore<'a>> ScriptVerifier<'a, CS> {
         .verify(max_cycles)
         .map_err(TransactionError::ScriptFailure)
     }
+
+    pub fn verify_script_groups(
+        &self,
+        max_cycles: Cycle,
+    ) -> Result<Vec<ScriptGroupReport>, TransactionError> {
+        let data_loader = DataLoaderWrapper::new(self.chain_store);
+        TransactionScriptsVerifier::new(
+            &self.resolved_transaction,
+            &data_loader,
+            &self.script_config,
+        )
+        .verify_script_groups(max_cycles)
+        .map_err(TransactionError::ScriptFailure)
+    }
 }
 
 pub struct EmptyVerifier<'a> {